@@ -44,6 +44,10 @@ pub enum AnalysisErrorKind {
     constructor_type: ValueType,
     actual_type: ValueType,
   },
+  PatternMismatchLiteralType {
+    literal_type: ValueType,
+    value_type: ValueType,
+  },
   IncorrectNumberOfArguments {
     expected: usize,
     actual: usize,
@@ -73,11 +77,33 @@ pub enum AnalysisErrorKind {
     actual: ValueType,
   },
   TypeMismatchInStringInterpolation(ValueType),
+  TypeMismatchInCollectionLiteral {
+    expected: ValueType,
+    actual: ValueType,
+  },
+  UnhashableDictKeyType(ValueType),
+  UndefinedCharacterClass(String),
+  DuplicateCaptureName(String),
+  InvalidInterpolationPatternHole,
+  AmbiguousInterpolationPattern,
   TypeMismatchInMatchCase {
     expected: ValueType,
     actual: ValueType,
   },
   InvalidValueForConst,
+  ExternDefNotFunction,
+  UnsupportedExternType(ValueType),
+  InvalidBlockHandleUse,
+  BreakOutsideLoop,
+  UnderscoreOutsidePattern,
+  TraitNotSatisfied {
+    typ: ValueType,
+    constraint: TypeConstraint,
+    missing_members: Vec<String>,
+  },
+  NonExhaustiveMatch(Vec<String>),
+  UnreachableMatchCase,
+  UnsolvedType(ValueType),
 }
 
 impl fmt::Display for AnalysisError {
@@ -179,6 +205,15 @@ impl fmt::Display for AnalysisError {
         constructor_type, actual_type,
       ),
 
+      PatternMismatchLiteralType {
+        literal_type,
+        value_type,
+      } => write!(
+        f,
+        "Cannot match a value of type {} against a literal of type {}.",
+        value_type, literal_type,
+      ),
+
       PatternMismatchTupleSize {
         pattern_size,
         value_size,
@@ -202,10 +237,40 @@ impl fmt::Display for AnalysisError {
 
       TypeMismatchInStringInterpolation(actual) => write!(
         f,
-        "Expected type String in interpolation, but found type {}.",
+        "Expected type String in interpolation, but found type {}, which has no to_string method.",
         actual
       ),
 
+      TypeMismatchInCollectionLiteral { expected, actual } => write!(
+        f,
+        "Expected every element of this literal to have type {}, but found type {}.",
+        expected, actual
+      ),
+
+      UnhashableDictKeyType(typ) => write!(
+        f,
+        "Cannot use type {} as a Dict key. Keys must be Int, Float or String.",
+        typ
+      ),
+
+      UndefinedCharacterClass(name) => write!(f, "Character class '{}' is not defined.", name),
+
+      DuplicateCaptureName(name) => write!(
+        f,
+        "Capture name '{}' is used more than once in this regular expression.",
+        name
+      ),
+
+      InvalidInterpolationPatternHole => write!(
+        f,
+        "Holes in a string pattern can only be names or '_'."
+      ),
+
+      AmbiguousInterpolationPattern => write!(
+        f,
+        "Two holes are next to each other in this string pattern, so the first one will always match an empty string."
+      ),
+
       TypeMismatchInTypeAssertion { expected, actual } => write!(
         f,
         "Type assertion failed. Type {} is not convertible to type {}.",
@@ -225,6 +290,58 @@ impl fmt::Display for AnalysisError {
       ),
 
       InvalidValueForConst => write!(f, "Value of const statement must be a literal."),
+
+      ExternDefNotFunction => write!(
+        f,
+        "Only functions can be extern definitions, not methods or operators."
+      ),
+
+      UnsupportedExternType(typ) => write!(
+        f,
+        "Type {} can't be passed to or returned from C. Extern definitions can use Int, Float, String, Pointer and the sized numeric types.",
+        typ
+      ),
+
+      InvalidBlockHandleUse => write!(
+        f,
+        "$self can only be used to return early from the block that it's in, e.g. `$self..return value`."
+      ),
+
+      BreakOutsideLoop => write!(f, "break can only be used in a loop's block."),
+
+      UnderscoreOutsidePattern => write!(
+        f,
+        "_ can only be used in a pattern, to ignore the value that it matches."
+      ),
+
+      TraitNotSatisfied {
+        typ,
+        constraint,
+        missing_members,
+      } => write!(
+        f,
+        "Type {} does not satisfy trait {}, which needs {}.",
+        typ,
+        constraint,
+        missing_members.join(", ")
+      ),
+
+      NonExhaustiveMatch(missing_patterns) => write!(
+        f,
+        "Match is not exhaustive. No case matches {}.",
+        missing_patterns.join(", ")
+      ),
+
+      UnreachableMatchCase => write!(
+        f,
+        "This case is unreachable, since the cases before it match every value it would."
+      ),
+
+      UnsolvedType(typ) => write!(
+        f,
+        "Cannot infer the full type of this value, only that it's {}. A type assertion can say what the rest is.",
+        typ
+      ),
     }
   }
 }
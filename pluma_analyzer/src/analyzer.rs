@@ -1,16 +1,42 @@
 use crate::analysis_error::{AnalysisError, AnalysisErrorKind};
 use crate::binding::*;
+use crate::exports::*;
 use crate::scope::*;
 use crate::type_utils;
+use crate::unifier::Unifier;
 use pluma_ast::*;
 use pluma_diagnostics::*;
+use pluma_match::{MatchCompiler, Variant};
 use pluma_visitor::*;
 use std::collections::HashMap;
 use std::iter::Iterator;
 
+/// A trait's fields and methods, by name.
+type TraitMembers = (Vec<(String, ValueType)>, Vec<(Vec<String>, ValueType)>);
+
 pub struct Analyzer<'a> {
   pub diagnostics: Vec<Diagnostic>,
+  pub exports: ModuleExports,
   scope: &'a mut Scope,
+
+  /// The generic params (e.g. `A`) that are in scope in the def being
+  /// analyzed, with the type that each one stands for in its body.
+  generic_params: HashMap<String, ValueType>,
+
+  /// Every trait that each of those params is constrained by, since a
+  /// param can be constrained more than once (`where A :: Foo, A :: Bar`).
+  generic_constraints: HashMap<String, Vec<TypeConstraint>>,
+
+  unifier: Unifier,
+
+  /// The trait constraints on the generic params of the defs used so far,
+  /// which are checked once the params' types are known.
+  trait_constraints: Vec<(ValueType, TypeConstraint, Position)>,
+
+  /// The return types of the blocks being analyzed, innermost last, which
+  /// `$self..return` and `break` are checked against. A block that isn't
+  /// passed anywhere has no return type until it first returns early.
+  block_return_types: Vec<Option<ValueType>>,
 }
 
 impl<'a> Analyzer<'a> {
@@ -18,6 +44,12 @@ impl<'a> Analyzer<'a> {
     Analyzer {
       scope,
       diagnostics: Vec::new(),
+      exports: ModuleExports::default(),
+      generic_params: HashMap::new(),
+      generic_constraints: HashMap::new(),
+      unifier: Unifier::new(),
+      trait_constraints: Vec::new(),
+      block_return_types: Vec::new(),
     }
   }
 
@@ -30,6 +62,14 @@ impl<'a> Analyzer<'a> {
     self.diagnostics.push(diag)
   }
 
+  fn error_count(&self) -> usize {
+    self
+      .diagnostics
+      .iter()
+      .filter(|diag| diag.is_error())
+      .count()
+  }
+
   fn check_result(&mut self, result: Result<(), Diagnostic>) {
     if let Err(diag) = result {
       self.diagnostic(diag);
@@ -45,37 +85,277 @@ impl<'a> Analyzer<'a> {
   }
 
   fn compatible_types(&mut self, expected: &ValueType, actual: &ValueType) -> bool {
-    expected == actual
+    self.unifier.unify(expected, actual)
+  }
+
+  /// Whether a type conforms to the prelude's `ToString` trait, i.e. it
+  /// has a `to_string` method that returns a String.
+  fn has_to_string_method(&mut self, typ: &ValueType) -> bool {
+    let binding = match self.scope.get_type_binding(typ) {
+      Some(binding) => binding,
+      None => return false,
+    };
+
+    match binding.methods.get(&vec!["to_string".to_owned()]) {
+      Some(ValueType::Func(_, return_type)) => **return_type == ValueType::String,
+      _ => false,
+    }
+  }
+
+  /// Checks the trait constraints whose generic params have been solved,
+  /// keeping the rest until their types are known.
+  fn check_trait_constraints(&mut self) {
+    let constraints = std::mem::take(&mut self.trait_constraints);
+
+    for (variable, constraint, pos) in constraints {
+      let typ = self.unifier.resolve(&variable);
+
+      match typ {
+        ValueType::Variable(_) => self.trait_constraints.push((variable, constraint, pos)),
+
+        // The mismatch that made this unknown has already been reported
+        ValueType::Unknown => {}
+
+        _ => {
+          let missing_members = self.missing_trait_members(&typ, &constraint);
+
+          if !missing_members.is_empty() {
+            self.error(AnalysisError {
+              pos,
+              kind: AnalysisErrorKind::TraitNotSatisfied {
+                typ,
+                constraint,
+                missing_members,
+              },
+            });
+          }
+        }
+      }
+    }
+  }
+
+  /// The fields and methods that a trait requires but a type doesn't have
+  /// (with matching types), written as they would be in the trait, e.g.
+  /// `.name` or `..to_string`. Traits are satisfied structurally, so any
+  /// type with all of the members conforms.
+  fn missing_trait_members(&mut self, typ: &ValueType, constraint: &TypeConstraint) -> Vec<String> {
+    // A trait that isn't defined (e.g. `Any` in code that doesn't use the
+    // prelude) can't be checked
+    let (mut required_fields, mut required_methods) = match self.trait_members(constraint) {
+      Some(members) => members,
+      None => return Vec::new(),
+    };
+
+    let (fields, methods) = match typ {
+      // A generic param has the members of each trait it's constrained by
+      ValueType::Constrained(name, own_constraint) => {
+        let own_constraints = self
+          .generic_constraints
+          .get(name)
+          .cloned()
+          .unwrap_or_else(|| vec![own_constraint.clone()]);
+
+        if own_constraints.contains(constraint) {
+          return Vec::new();
+        }
+
+        let mut fields = HashMap::new();
+        let mut methods = HashMap::new();
+
+        for own_constraint in &own_constraints {
+          if let Some((own_fields, own_methods)) = self.trait_members(own_constraint) {
+            fields.extend(own_fields);
+            methods.extend(own_methods);
+          }
+        }
+
+        (fields, methods)
+      }
+
+      _ => match self.scope.get_type_binding(typ) {
+        Some(binding) => {
+          let fields = match &binding.kind {
+            TypeBindingKind::Struct { fields, .. } | TypeBindingKind::Trait { fields, .. } => {
+              fields
+                .iter()
+                .map(|(field_name, field)| (field_name.clone(), field.typ.clone()))
+                .collect()
+            }
+            _ => HashMap::new(),
+          };
+
+          (fields, binding.methods.clone())
+        }
+        None => (HashMap::new(), HashMap::new()),
+      },
+    };
+
+    // Methods of generic types are declared in terms of the type's params
+    let type_params = match typ {
+      ValueType::Generic(name, args) => match self.scope.get_declared_generic_type(name) {
+        Some(ValueType::Generic(_, params)) => Some((params, args.clone())),
+        _ => None,
+      },
+      _ => None,
+    };
+
+    // Listed in a stable order, fields first
+    required_fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    required_methods.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut missing_members = Vec::new();
+
+    for (field_name, field_type) in required_fields {
+      let satisfied = match fields.get(&field_name) {
+        Some(actual_type) => self.compatible_types(&field_type, actual_type),
+        None => false,
+      };
+
+      if !satisfied {
+        missing_members.push(format!(".{}", field_name));
+      }
+    }
+
+    for (method_parts, method_type) in required_methods {
+      let satisfied = match methods.get(&method_parts) {
+        Some(actual_type) => {
+          let actual_type = match &type_params {
+            Some((params, args)) => type_utils::substitute_generics(actual_type, params, args),
+            None => actual_type.clone(),
+          };
+
+          self.compatible_types(&method_type, &actual_type)
+        }
+        None => false,
+      };
+
+      if !satisfied {
+        missing_members.push(format!("..{}", method_parts.join(" ")));
+      }
+    }
+
+    missing_members
+  }
+
+  /// The fields and methods that a trait requires, or None if the trait
+  /// isn't defined.
+  fn trait_members(&mut self, constraint: &TypeConstraint) -> Option<TraitMembers> {
+    let members = match constraint {
+      TypeConstraint::InlineTrait { fields, methods } => (
+        fields.clone(),
+        methods
+          .iter()
+          .map(|(parts, return_type)| {
+            let (names, param_types) = parts.iter().cloned().unzip();
+            (
+              names,
+              ValueType::Func(param_types, Box::new(return_type.clone())),
+            )
+          })
+          .collect(),
+      ),
+
+      TypeConstraint::NamedTrait(name) | TypeConstraint::GenericTrait(name, _) => {
+        let args = match constraint {
+          TypeConstraint::GenericTrait(_, args) => args.clone(),
+          _ => Vec::new(),
+        };
+
+        let binding = self
+          .scope
+          .get_type_binding(&ValueType::Named(name.clone()))?;
+
+        let (fields, generic_params) = match &binding.kind {
+          TypeBindingKind::Trait {
+            fields,
+            generic_params,
+          } => (fields, generic_params),
+          _ => return None,
+        };
+
+        let substitute =
+          |typ: &ValueType| type_utils::substitute_generics(typ, generic_params, &args);
+
+        let fields = fields
+          .iter()
+          .map(|(field_name, field)| (field_name.clone(), substitute(&field.typ)))
+          .collect::<Vec<(String, ValueType)>>();
+
+        let methods = binding
+          .methods
+          .iter()
+          .map(|(method_parts, method_type)| (method_parts.clone(), substitute(method_type)))
+          .collect::<Vec<(Vec<String>, ValueType)>>();
+
+        (fields, methods)
+      }
+    };
+
+    Some(members)
+  }
+
+  fn export_binding(&mut self, visibility: ExportVisibility, name: &str) {
+    if let Some(binding) = self.scope.peek_binding(name) {
+      let export = Export::Binding(name.to_owned(), binding.clone());
+      self.exports.add(visibility, export);
+    }
+  }
+
+  fn export_type(&mut self, visibility: ExportVisibility, typ: &ValueType) {
+    if let Some(type_binding) = self.scope.get_type_binding(typ) {
+      let mut type_binding = type_binding.clone();
+
+      // A trait's methods are part of the trait, but other types' methods
+      // are exported by the defs that define them
+      if !matches!(type_binding.kind, TypeBindingKind::Trait { .. }) {
+        type_binding.methods.clear();
+      }
+
+      self
+        .exports
+        .add(visibility, Export::Type(typ.clone(), type_binding));
+    }
+  }
+
+  /// Adds a method to its receiver type, and exports it with the
+  /// visibility of the def that defines it.
+  fn add_type_method(
+    &mut self,
+    visibility: ExportVisibility,
+    receiver_type: ValueType,
+    method_parts: Vec<String>,
+    param_types: Vec<ValueType>,
+    return_type: ValueType,
+    pos: Position,
+  ) {
+    let method_type = ValueType::Func(param_types.clone(), Box::new(return_type.clone()));
+    let export = Export::Method(receiver_type.clone(), method_parts.clone(), method_type);
+
+    let result =
+      self
+        .scope
+        .add_type_method(receiver_type, method_parts, param_types, return_type, pos);
+
+    if result.is_ok() {
+      self.exports.add(visibility, export);
+    }
+
+    self.check_result(result);
   }
 
   fn collect_def(
     &mut self,
     pos: Position,
+    visibility: ExportVisibility,
     generic_type_constraints: &mut GenericTypeConstraints,
     kind: &mut DefKind,
     return_type: &Option<TypeExprNode>,
   ) {
-    let mut constraints_map = HashMap::new();
-
-    for (constraint_name, constraint_type_id) in generic_type_constraints {
-      let constraint = if constraint_type_id.generics.is_empty() {
-        TypeConstraint::NamedTrait(constraint_type_id.name.clone())
-      } else {
-        TypeConstraint::GenericTrait(
-          constraint_type_id.name.clone(),
-          constraint_type_id
-            .generics
-            .iter()
-            .map(|type_expr| type_utils::type_expr_to_value_type(type_expr))
-            .collect(),
-        )
-      };
-
-      constraints_map.insert(
-        constraint_name.name.clone(),
-        ValueType::Constrained(constraint),
-      );
-    }
+    let generic_params = type_utils::generic_param_types(generic_type_constraints);
+    let (generic_names, generic_types): (Vec<ValueType>, Vec<ValueType>) = generic_params
+      .into_iter()
+      .map(|(name, typ)| (ValueType::Named(name), typ))
+      .unzip();
 
     match kind {
       DefKind::Function { signature } => {
@@ -84,18 +364,7 @@ impl<'a> Analyzer<'a> {
 
         for (part_name, part_type) in signature {
           name_parts.push(part_name.name.clone());
-
-          let param_type = type_utils::type_expr_to_value_type(part_type);
-
-          if let ValueType::Named(name) = &param_type {
-            if let Some(constraint) = constraints_map.get(name) {
-              param_types.push(constraint.clone());
-            } else {
-              param_types.push(param_type);
-            }
-          } else {
-            param_types.push(param_type);
-          }
+          param_types.push(type_utils::type_expr_to_value_type(part_type));
         }
 
         let return_type = match return_type {
@@ -103,21 +372,27 @@ impl<'a> Analyzer<'a> {
           None => ValueType::Nothing,
         };
 
-        println!("param_types {:#?}", param_types);
+        // Generic params are written as names, e.g. `A`, until here
+        let def_type = type_utils::substitute_generics(
+          &ValueType::Func(param_types, Box::new(return_type)),
+          &generic_names,
+          &generic_types,
+        );
 
-        let def_type = ValueType::Func(param_types, Box::new(return_type));
         let merged_name = name_parts.join(" ");
 
         self
           .scope
-          .add_binding(BindingKind::Def, merged_name, def_type, pos);
+          .add_binding(BindingKind::Def, merged_name.clone(), def_type, pos);
+
+        self.export_binding(visibility, &merged_name);
       }
 
       DefKind::Method {
         receiver,
         signature,
       } => {
-        let receiver_type = ValueType::Named(receiver.name.clone());
+        let receiver_type = type_utils::type_ident_to_value_type(receiver);
 
         let return_type = match return_type {
           Some(type_expr) => type_utils::type_expr_to_value_type(&type_expr),
@@ -132,15 +407,14 @@ impl<'a> Analyzer<'a> {
           param_types.push(type_utils::type_expr_to_value_type(part_type_expr));
         }
 
-        let result = self.scope.add_type_method(
+        self.add_type_method(
+          visibility,
           receiver_type,
           method_parts,
           param_types,
           return_type,
           receiver.pos,
         );
-
-        self.check_result(result);
       }
 
       DefKind::BinaryOperator { op, left, right } => {
@@ -156,15 +430,14 @@ impl<'a> Analyzer<'a> {
         let method_parts = vec!["$".to_owned(), op.name.clone(), "$".to_owned()];
         let param_types = vec![param_type];
 
-        let result = self.scope.add_type_method(
+        self.add_type_method(
+          visibility,
           receiver_type,
           method_parts,
           param_types,
           return_type,
           left.pos,
         );
-
-        self.check_result(result);
       }
 
       DefKind::UnaryOperator { op, right } => {
@@ -178,15 +451,14 @@ impl<'a> Analyzer<'a> {
         let method_parts = vec![op.name.clone(), "$".to_owned()];
         let param_types = vec![];
 
-        let result = self.scope.add_type_method(
+        self.add_type_method(
+          visibility,
           receiver_type,
           method_parts,
           param_types,
           return_type,
           right.pos,
         );
-
-        self.check_result(result);
       }
     }
   }
@@ -213,55 +485,110 @@ impl<'a> Analyzer<'a> {
   }
 
   fn collect_type_def(&mut self, node: &mut TypeDefNode) {
-    let typ = ValueType::Named(node.name.name.clone());
+    let visibility = node.visibility;
+
+    // A generic type's constructors are like generic defs: each use of
+    // one gets its own types for the type's params (e.g. `Some 5` is a
+    // `Maybe<Int>`)
+    let (param_names, params): (Vec<ValueType>, Vec<ValueType>) =
+      type_utils::generic_param_types(&node.generic_type_constraints)
+        .into_iter()
+        .map(|(name, param)| (ValueType::Named(name), param))
+        .unzip();
+
+    let typ = match &node.kind {
+      TypeDefKind::Enum { .. } | TypeDefKind::Struct { .. } if !params.is_empty() => {
+        ValueType::Generic(node.name.name.clone(), params.clone())
+      }
+      _ => ValueType::Named(node.name.name.clone()),
+    };
 
-    match &node.kind {
+    match &mut node.kind {
       TypeDefKind::Enum { variants } => {
-        self
-          .scope
-          .add_type_binding(typ.clone(), TypeBindingKind::Enum, node.name.pos);
+        let mut variant_infos = Vec::new();
 
         for variant in variants {
-          match &variant.kind {
+          match &mut variant.kind {
             EnumVariantKind::Identifier(ident_node) => {
               let variant_name = ident_node.name.clone();
               let variant_type = typ.clone();
 
+              variant_infos.push(Variant {
+                name: variant_name.clone(),
+                payload_type: None,
+              });
+
               self.scope.add_binding(
                 BindingKind::EnumVariant,
-                variant_name,
+                variant_name.clone(),
                 variant_type,
                 ident_node.pos,
               );
+
+              self.export_binding(visibility, &variant_name);
             }
 
             EnumVariantKind::Constructor(constructor_node, param_node) => {
               let constructor_name = constructor_node.name.clone();
-              let param_type = type_utils::type_expr_to_value_type(param_node);
-              let constructor_type = ValueType::Func(vec![param_type], Box::new(typ.clone()));
+              let param_type = type_utils::substitute_generics(
+                &type_utils::type_expr_to_value_type(param_node),
+                &param_names,
+                &params,
+              );
+              param_node.typ = param_type.clone();
+              let constructor_type =
+                ValueType::Func(vec![param_type.clone()], Box::new(typ.clone()));
+
+              variant_infos.push(Variant {
+                name: constructor_name.clone(),
+                payload_type: Some(param_type),
+              });
 
               self.scope.add_binding(
                 BindingKind::EnumVariant,
-                constructor_name,
+                constructor_name.clone(),
                 constructor_type,
                 variant.pos,
               );
+
+              self.export_binding(visibility, &constructor_name);
             }
           }
         }
+
+        self.scope.add_type_binding(
+          typ.clone(),
+          TypeBindingKind::Enum {
+            variants: variant_infos,
+          },
+          node.name.pos,
+        );
       }
 
       TypeDefKind::Struct { inner } => {
-        let fields = type_utils::type_expr_to_struct_fields(inner);
+        let mut fields = type_utils::type_expr_to_struct_fields(inner);
+        let inner_type = type_utils::substitute_generics(
+          &type_utils::type_expr_to_value_type(inner),
+          &param_names,
+          &params,
+        );
+
+        for field in fields.values_mut() {
+          field.typ = type_utils::substitute_generics(&field.typ, &param_names, &params);
+        }
+
+        inner.typ = inner_type.clone();
 
         self.scope.add_type_binding(
           typ.clone(),
-          TypeBindingKind::Struct { fields },
+          TypeBindingKind::Struct {
+            fields,
+            inner_type: inner_type.clone(),
+          },
           node.name.pos,
         );
 
-        let inner_type = type_utils::type_expr_to_value_type(inner);
-        let constructor_type = ValueType::Func(vec![inner_type], Box::new(typ));
+        let constructor_type = ValueType::Func(vec![inner_type], Box::new(typ.clone()));
 
         self.scope.add_binding(
           BindingKind::StructConstructor,
@@ -269,6 +596,8 @@ impl<'a> Analyzer<'a> {
           constructor_type,
           node.name.pos,
         );
+
+        self.export_binding(visibility, &node.name.name);
       }
 
       TypeDefKind::Alias { .. } => {
@@ -277,7 +606,7 @@ impl<'a> Analyzer<'a> {
           .add_type_binding(typ.clone(), TypeBindingKind::Alias, node.name.pos);
       }
 
-      TypeDefKind::Trait { fields, .. } => {
+      TypeDefKind::Trait { fields, methods } => {
         let mut fields_map = HashMap::new();
 
         for field in fields {
@@ -294,13 +623,47 @@ impl<'a> Analyzer<'a> {
           );
         }
 
+        let generic_params = node
+          .name
+          .generics
+          .iter()
+          .map(type_utils::type_expr_to_value_type)
+          .collect();
+
         self.scope.add_type_binding(
           typ.clone(),
-          TypeBindingKind::Trait { fields: fields_map },
+          TypeBindingKind::Trait {
+            fields: fields_map,
+            generic_params,
+          },
           node.name.pos,
         );
+
+        for (signature, return_type) in methods {
+          let mut method_parts = Vec::new();
+          let mut param_types = Vec::new();
+
+          for (part_name, part_type_expr) in signature {
+            method_parts.push(part_name.name.clone());
+            param_types.push(type_utils::type_expr_to_value_type(part_type_expr));
+          }
+
+          let return_type = type_utils::type_expr_to_value_type(return_type);
+
+          let result = self.scope.add_type_method(
+            typ.clone(),
+            method_parts,
+            param_types,
+            return_type,
+            node.name.pos,
+          );
+
+          self.check_result(result);
+        }
       }
     }
+
+    self.export_type(visibility, &typ);
   }
 
   fn collect_intrinsic_type_def(&mut self, node: &mut IntrinsicTypeDefNode) {
@@ -308,21 +671,50 @@ impl<'a> Analyzer<'a> {
       "Int" => Some(ValueType::Int),
       "Float" => Some(ValueType::Float),
       "String" => Some(ValueType::String),
-      _ => None,
+      "Pointer" => Some(ValueType::Pointer),
+      "Never" => Some(ValueType::Never),
+      "List" | "Dict" | "Regex" => Some(type_utils::type_ident_to_value_type(&node.name)),
+      name => SizedType::from_name(name).map(ValueType::Sized),
     };
 
     if let Some(typ) = intrinsic_type {
       self
         .scope
-        .add_type_binding(typ, TypeBindingKind::IntrinsicType, node.name.pos);
+        .add_type_binding(typ.clone(), TypeBindingKind::IntrinsicType, node.name.pos);
+
+      self.export_type(node.visibility, &typ);
     }
   }
 
-  fn destructure_pattern(&mut self, pattern: &PatternNode, typ: &ValueType) {
+  /// Checks a pattern against the type of the value it destructures and
+  /// binds the names it introduces. Refutable patterns (e.g. literals or
+  /// enum variants) are only allowed in match cases.
+  fn destructure_pattern(&mut self, pattern: &PatternNode, typ: &ValueType, refutable: bool) {
     match &pattern.kind {
       PatternKind::Identifier(ident_node, _is_mutable) => {
         let existing_binding = self.scope.get_binding(&ident_node.name);
 
+        // In a match, naming an enum variant tests for that variant
+        if let (true, Some(binding)) = (refutable, existing_binding) {
+          if binding.kind == BindingKind::EnumVariant {
+            let (variant_type, _) = self.unifier.instantiate(&binding.typ.clone());
+
+            if !self.compatible_types(&variant_type, typ) {
+              self.error(AnalysisError {
+                pos: pattern.pos,
+                kind: AnalysisErrorKind::PatternMismatchExpectedConstructor {
+                  constructor_type: variant_type,
+                  actual_type: typ.clone(),
+                },
+              });
+            }
+
+            return;
+          }
+        }
+
+        let existing_binding = self.scope.get_binding(&ident_node.name);
+
         if existing_binding.is_some() {
           self.error(AnalysisError {
             pos: ident_node.pos,
@@ -354,7 +746,7 @@ impl<'a> Analyzer<'a> {
             let element_pattern = element_patterns.get(i).unwrap();
             let element_type = element_types.get(i).unwrap();
 
-            self.destructure_pattern(&element_pattern, &element_type);
+            self.destructure_pattern(&element_pattern, &element_type, refutable);
           }
         }
 
@@ -390,7 +782,7 @@ impl<'a> Analyzer<'a> {
                 },
               }),
               Some((_, element_type)) => {
-                self.destructure_pattern(&element_pattern, &element_type);
+                self.destructure_pattern(&element_pattern, &element_type, refutable);
               }
             }
           }
@@ -416,12 +808,23 @@ impl<'a> Analyzer<'a> {
 
         let existing_binding = binding.unwrap();
 
-        // Constructor patterns are only allowed for struct types
-        if existing_binding.kind != BindingKind::StructConstructor {
+        // Constructor patterns are only allowed for struct types, or for
+        // enum variants in a match
+        let is_constructor = match existing_binding.kind {
+          BindingKind::StructConstructor => true,
+          BindingKind::EnumVariant => refutable,
+          _ => false,
+        };
+
+        if !is_constructor {
           return;
         }
 
-        let (param_type, constructor_type) = match &existing_binding.typ {
+        // A generic enum's variants get their own variables for the enum's
+        // params, which the matched type then solves
+        let (constructor_func_type, _) = self.unifier.instantiate(&existing_binding.typ.clone());
+
+        let (param_type, constructor_type) = match &constructor_func_type {
           ValueType::Func(param_types, return_type) => {
             (param_types.get(0).unwrap().clone(), (**return_type).clone())
           }
@@ -442,11 +845,80 @@ impl<'a> Analyzer<'a> {
           return;
         }
 
-        self.destructure_pattern(param_pattern, &param_type);
+        let param_type = self.unifier.resolve(&param_type);
+        self.destructure_pattern(param_pattern, &param_type, refutable);
       }
 
       PatternKind::Underscore => {}
 
+      PatternKind::Literal(lit_node) if refutable => {
+        let literal_type = self.analyze_literal(lit_node);
+
+        if !self.compatible_types(typ, &literal_type) {
+          self.error(AnalysisError {
+            pos: pattern.pos,
+            kind: AnalysisErrorKind::PatternMismatchLiteralType {
+              literal_type,
+              value_type: typ.clone(),
+            },
+          })
+        }
+      }
+
+      PatternKind::Interpolation(parts) if refutable => {
+        if !self.compatible_types(typ, &ValueType::String) {
+          self.error(AnalysisError {
+            pos: pattern.pos,
+            kind: AnalysisErrorKind::PatternMismatchLiteralType {
+              literal_type: ValueType::String,
+              value_type: typ.clone(),
+            },
+          })
+        }
+
+        // Parts alternate between literal strings and holes, starting and
+        // ending with a literal (which may be empty)
+        for (i, part) in parts.iter().enumerate() {
+          match &part.kind {
+            ExprKind::Literal(LiteralNode {
+              kind: LiteralKind::Str(value),
+              ..
+            }) if i % 2 == 0 => {
+              // Each pair of adjacent holes gets its own warning, pointing
+              // at the pair (from the end of the literal before the first
+              // hole to the start of the one after the second)
+              if value.is_empty() && i > 0 && i < parts.len() - 1 {
+                let pos = (parts[i - 2].pos.1, parts[i + 2].pos.0);
+
+                self.diagnostic(
+                  Diagnostic::warning(AnalysisError {
+                    pos,
+                    kind: AnalysisErrorKind::AmbiguousInterpolationPattern,
+                  })
+                  .with_pos(pos),
+                );
+              }
+            }
+
+            ExprKind::Identifier(ident_node) => {
+              let hole_pattern = PatternNode {
+                pos: part.pos,
+                kind: PatternKind::Identifier(ident_node.clone(), false),
+              };
+
+              self.destructure_pattern(&hole_pattern, &ValueType::String, false);
+            }
+
+            ExprKind::Underscore => {}
+
+            _ => self.error(AnalysisError {
+              pos: part.pos,
+              kind: AnalysisErrorKind::InvalidInterpolationPatternHole,
+            }),
+          }
+        }
+      }
+
       PatternKind::Literal(..) | PatternKind::Interpolation(..) => self.error(AnalysisError {
         pos: pattern.pos,
         kind: AnalysisErrorKind::CannotAssignToLiteral,
@@ -454,15 +926,94 @@ impl<'a> Analyzer<'a> {
     }
   }
 
-  fn analyze_block(&mut self, node: &mut BlockNode) -> ValueType {
+  /// Reports the values that none of a match's cases cover, and the cases
+  /// that can never match because earlier ones already cover them.
+  fn check_match_cases(&mut self, node: &MatchNode) {
+    let subject_type = self.unifier.resolve(&node.subject.typ);
+
+    // Whatever made the subject's type unknown has already been reported
+    if !type_utils::is_fully_known(&subject_type) {
+      return;
+    }
+
+    let compiled = MatchCompiler::new(&*self.scope).compile(&node.cases, &subject_type);
+
+    for case_index in compiled.unreachable_cases {
+      let pos = node.cases[case_index].pattern.pos;
+
+      self.diagnostic(
+        Diagnostic::warning(AnalysisError {
+          pos,
+          kind: AnalysisErrorKind::UnreachableMatchCase,
+        })
+        .with_pos(pos),
+      );
+    }
+
+    if !compiled.missing_patterns.is_empty() {
+      self.error(AnalysisError {
+        pos: (node.pos.0, node.subject.pos.1),
+        kind: AnalysisErrorKind::NonExhaustiveMatch(
+          compiled
+            .missing_patterns
+            .iter()
+            .map(|pattern| format!("`{}`", pattern))
+            .collect(),
+        ),
+      });
+    }
+  }
+
+  /// Analyzes a block expression. When the block is passed somewhere that
+  /// expects a function, `expected_type` is that function's type.
+  fn analyze_block(
+    &mut self,
+    node: &mut BlockNode,
+    expected_type: Option<&ValueType>,
+  ) -> ValueType {
     let mut param_types = Vec::new();
     let mut return_type = ValueType::Nothing;
+    let mut return_pos = node.pos;
+    let mut expected_return_type = None;
+
+    match expected_type {
+      Some(ValueType::Func(expected_param_types, expected_return)) => {
+        for param_type in expected_param_types {
+          param_types.push(self.unifier.resolve(param_type));
+        }
+
+        expected_return_type = Some(*expected_return.clone());
+      }
+
+      _ if node.params.is_empty() => param_types.push(ValueType::Nothing),
+
+      // The params' types are inferred from how they're used
+      _ => {
+        for _param in &node.params {
+          param_types.push(self.unifier.fresh_variable());
+        }
+      }
+    }
+
+    self.scope.enter();
+    self.block_return_types.push(expected_return_type.clone());
 
     if node.params.is_empty() {
-      param_types.push(ValueType::Nothing);
+      let pos = (node.pos.0, node.pos.0);
+
+      for (i, param_type) in param_types.iter().enumerate() {
+        self.scope.add_binding(
+          BindingKind::Param,
+          format!("${}", i),
+          param_type.clone(),
+          pos,
+        );
+      }
     } else {
-      for _param in &node.params {
-        param_types.push(ValueType::Unknown);
+      for (i, pattern) in node.params.iter_mut().enumerate() {
+        let param_type = param_types.get(i).cloned().unwrap_or(ValueType::Unknown);
+
+        self.destructure_pattern(pattern, &param_type, false);
       }
     }
 
@@ -471,15 +1022,88 @@ impl<'a> Analyzer<'a> {
 
       if let StatementKind::Expr(expr) = &stmt.kind {
         return_type = expr.typ.clone();
+        return_pos = expr.pos;
       }
     }
 
-    ValueType::Func(param_types, Box::new(return_type))
+    // Values returned early have to match the value that the block ends
+    // with. When the block was passed somewhere, they've already been
+    // checked against the type expected there.
+    match self.block_return_types.pop().flatten() {
+      Some(returned_type) if return_type == ValueType::Never => return_type = returned_type,
+
+      Some(returned_type)
+        if expected_return_type.is_none()
+          && !self.compatible_types(&returned_type, &return_type) =>
+      {
+        self.error(AnalysisError {
+          pos: return_pos,
+          kind: AnalysisErrorKind::ReturnTypeMismatch {
+            expected: self.unifier.resolve(&returned_type),
+            actual: self.unifier.resolve(&return_type),
+          },
+        })
+      }
+
+      _ => {}
+    }
+
+    let results = self.scope.exit();
+    self.check_results(results);
+
+    self
+      .unifier
+      .resolve(&ValueType::Func(param_types, Box::new(return_type)))
+  }
+
+  /// Checks a value that's returned early from the innermost block, with
+  /// `$self..return` or `break`, against the block's return type.
+  fn analyze_block_return(&mut self, typ: &ValueType, pos: Position) {
+    let expected_type = match self.block_return_types.last_mut() {
+      Some(Some(expected_type)) => expected_type.clone(),
+
+      // The first value returned decides the block's return type
+      Some(return_type) => {
+        *return_type = Some(typ.clone());
+        return;
+      }
+
+      None => unreachable!("block returns should only be analyzed inside blocks"),
+    };
+
+    if !self.compatible_types(&expected_type, typ) {
+      self.error(AnalysisError {
+        pos,
+        kind: AnalysisErrorKind::ReturnTypeMismatch {
+          expected: self.unifier.resolve(&expected_type),
+          actual: self.unifier.resolve(typ),
+        },
+      })
+    }
   }
 
   fn analyze_call(&mut self, node: &mut CallNode) -> ValueType {
+    if node.callee.is_block_return() {
+      return self.analyze_block_return_call(node);
+    }
+
     self.analyze_expr(&mut node.callee);
 
+    // A callee whose type isn't known yet has to be a function that takes
+    // these args
+    if let ValueType::Variable(_) = self.unifier.resolve(&node.callee.typ) {
+      let param_types = node
+        .args
+        .iter()
+        .map(|_| self.unifier.fresh_variable())
+        .collect();
+
+      let func_type = ValueType::Func(param_types, Box::new(self.unifier.fresh_variable()));
+
+      self.unifier.unify(&node.callee.typ, &func_type);
+      node.callee.typ = func_type;
+    }
+
     let callee_type = &node.callee.typ;
 
     match callee_type {
@@ -494,11 +1118,26 @@ impl<'a> Analyzer<'a> {
           })
         }
 
-        for i in 0..param_types.len() {
-          let arg = node.args.get_mut(i).unwrap();
-          self.analyze_expr(arg);
+        // Blocks go last, so that the other args can solve the generic
+        // params in a block's param types (e.g. `A` in `apply { |n| n + 1 }
+        // to 41`) before the block's body needs them
+        let (block_args, other_args): (Vec<usize>, Vec<usize>) = (0..param_types.len())
+          .filter(|&i| i < node.args.len())
+          .partition(|&i| matches!(node.args[i].kind, ExprKind::Block(_)));
 
+        for i in other_args.into_iter().chain(block_args) {
+          let arg = node.args.get_mut(i).unwrap();
           let param_type = param_types.get(i).unwrap();
+
+          // Blocks take their param types from the function they're passed to
+          match (&mut arg.kind, param_type) {
+            (ExprKind::Block(block), ValueType::Func(..)) => {
+              arg.typ = self.analyze_block(block, Some(param_type));
+            }
+
+            _ => self.analyze_expr(arg),
+          }
+
           let given_type = &arg.typ;
 
           if !self.compatible_types(&param_type, &given_type) {
@@ -507,14 +1146,17 @@ impl<'a> Analyzer<'a> {
             self.error(AnalysisError {
               pos,
               kind: AnalysisErrorKind::ParameterTypeMismatch {
-                expected: param_type.clone(),
-                actual: given_type.clone(),
+                expected: self.unifier.resolve(param_type),
+                actual: self.unifier.resolve(given_type),
               },
             })
           }
         }
 
-        *return_type.clone()
+        self.check_trait_constraints();
+
+        // The args may have solved some of the function's generic params
+        self.unifier.resolve(return_type)
       }
 
       _ => {
@@ -528,10 +1170,62 @@ impl<'a> Analyzer<'a> {
     }
   }
 
+  /// Analyzes a call to `$self..return`, whose callee is typed as the
+  /// `return` field of a `BlockHandle` for the innermost block.
+  fn analyze_block_return_call(&mut self, node: &mut CallNode) -> ValueType {
+    for arg in &mut node.args {
+      self.analyze_expr(arg);
+    }
+
+    if self.block_return_types.is_empty() {
+      self.error(AnalysisError {
+        pos: node.callee.pos,
+        kind: AnalysisErrorKind::InvalidBlockHandleUse,
+      });
+
+      return ValueType::Unknown;
+    }
+
+    if node.args.len() != 1 {
+      self.error(AnalysisError {
+        pos: node.pos,
+        kind: AnalysisErrorKind::IncorrectNumberOfArguments {
+          expected: 1,
+          actual: node.args.len(),
+        },
+      });
+
+      return ValueType::Never;
+    }
+
+    let value = &node.args[0];
+    self.analyze_block_return(&value.typ, value.pos);
+
+    let value_type = value.typ.clone();
+    node.callee.typ = ValueType::Func(vec![value_type], Box::new(ValueType::Never));
+
+    ValueType::Never
+  }
+
   fn analyze_def(&mut self, node: &mut DefNode) {
     let mut param_types = Vec::new();
     let mut return_type = ValueType::Nothing;
 
+    // In the def's body, its generic params only match themselves
+    self.generic_params = type_utils::generic_param_types(&node.generic_type_constraints)
+      .into_iter()
+      .collect();
+
+    for (name, param) in type_utils::generic_param_types(&node.generic_type_constraints) {
+      if let ValueType::Constrained(_, constraint) = param {
+        self
+          .generic_constraints
+          .entry(name)
+          .or_default()
+          .push(constraint);
+      }
+    }
+
     match &mut node.kind {
       DefKind::Function { signature } => {
         let params = &node.block.params;
@@ -550,8 +1244,6 @@ impl<'a> Analyzer<'a> {
         }
 
         for (_part_name, part_type) in signature {
-          println!("part_type: {:#?}", part_type);
-
           self.analyze_type_expr(part_type);
 
           param_types.push(part_type.typ.clone());
@@ -594,6 +1286,7 @@ impl<'a> Analyzer<'a> {
     }
 
     self.scope.enter();
+    self.block_return_types.push(Some(return_type.clone()));
 
     if node.block.params.is_empty() {
       let pos = (node.block.pos.0, node.block.pos.0);
@@ -610,7 +1303,7 @@ impl<'a> Analyzer<'a> {
       let mut i = 0;
       for pattern in &mut node.block.params {
         let param_type = param_types.get(i).unwrap();
-        self.destructure_pattern(pattern, param_type);
+        self.destructure_pattern(pattern, param_type, false);
 
         i += 1;
       }
@@ -628,23 +1321,92 @@ impl<'a> Analyzer<'a> {
       }
     }
 
+    self.block_return_types.pop();
+
     if !self.compatible_types(&return_type, &block_return_type) {
       self.error(AnalysisError {
         pos: block_return_pos,
         kind: AnalysisErrorKind::ReturnTypeMismatch {
-          expected: return_type,
-          actual: block_return_type,
+          expected: self.unifier.resolve(&return_type),
+          actual: self.unifier.resolve(&block_return_type),
         },
       })
     }
 
     let results = self.scope.exit();
     self.check_results(results);
+
+    self.generic_params.clear();
+    self.generic_constraints.clear();
+  }
+
+  /// Analyzes `f.bar`, where `f` is the qualifier of an imported module
+  /// (`use foo as f`). Types (and so their constructors and variants) are
+  /// the same wherever they're used, so those nodes are replaced with the
+  /// plain name. Defs keep their qualifier, since modules can have defs
+  /// with the same name. Returns false if the node isn't a qualified name.
+  fn analyze_qualified_name(&mut self, node: &mut ExprNode) -> bool {
+    let (receiver, mut ident_nodes) = match &node.kind {
+      ExprKind::MethodAccess {
+        receiver,
+        method_parts,
+      } => (receiver, method_parts.clone()),
+      ExprKind::FieldAccess { receiver, field } => (receiver, vec![field.clone()]),
+      _ => return false,
+    };
+
+    let qualifier = match &receiver.kind {
+      ExprKind::Identifier(ident) if self.scope.is_qualifier(&ident.name) => ident.name.clone(),
+      _ => return false,
+    };
+
+    let merged_name = ident_nodes
+      .iter()
+      .map(|node| node.name.clone())
+      .collect::<Vec<String>>()
+      .join(" ");
+
+    let mut is_def = false;
+
+    match self.scope.get_qualified_binding(&qualifier, &merged_name) {
+      Some(binding) => {
+        is_def = binding.kind == BindingKind::Def;
+        let is_generic = matches!(
+          binding.kind,
+          BindingKind::Def | BindingKind::EnumVariant | BindingKind::StructConstructor
+        );
+        let typ = binding.typ.clone();
+
+        node.typ = self.binding_type(is_generic, &typ, node.pos);
+      }
+      None => self.error(AnalysisError {
+        pos: node.pos,
+        kind: AnalysisErrorKind::UndefinedName(format!("{}.{}", qualifier, merged_name)),
+      }),
+    }
+
+    if !is_def {
+      node.kind = if ident_nodes.len() == 1 {
+        ExprKind::Identifier(ident_nodes.remove(0))
+      } else {
+        ExprKind::MultiPartIdentifier(ident_nodes)
+      };
+    }
+
+    true
   }
 
   fn analyze_expr(&mut self, node: &mut ExprNode) {
+    if self.analyze_qualified_name(node) {
+      return;
+    }
+
     match &mut node.kind {
       ExprKind::Assignment { left, right } => {
+        self.analyze_expr(right);
+
+        node.typ = ValueType::Nothing;
+
         let existing_binding = self.scope.get_binding(&left.name);
 
         if let Some(binding) = existing_binding {
@@ -655,8 +1417,8 @@ impl<'a> Analyzer<'a> {
             self.error(AnalysisError {
               pos: right.pos,
               kind: AnalysisErrorKind::ReassignmentTypeMismatch {
-                expected: current_type,
-                actual: new_type,
+                expected: self.unifier.resolve(&current_type),
+                actual: self.unifier.resolve(&new_type),
               },
             })
           }
@@ -701,35 +1463,67 @@ impl<'a> Analyzer<'a> {
         }
       }
 
-      ExprKind::Block(block) => node.typ = self.analyze_block(block),
+      ExprKind::Block(block) => node.typ = self.analyze_block(block, None),
+
+      ExprKind::Break => {
+        // `break` is short for `$self..return LoopBreak`, in a loop's block.
+        // It has to be directly inside that block, since in any other block
+        // (e.g. `let stop = { break }`) it would return from that block.
+        let loop_result_type = ValueType::Named("LoopResult".to_owned());
+
+        let in_loop = self.scope.get_type_binding(&loop_result_type).is_some()
+          && match self.block_return_types.last() {
+            Some(Some(return_type)) => self.unifier.resolve(return_type) == loop_result_type,
+            Some(None) | None => false,
+          };
+
+        if in_loop {
+          self.analyze_block_return(&loop_result_type, node.pos);
+        } else {
+          self.error(AnalysisError {
+            pos: node.pos,
+            kind: AnalysisErrorKind::BreakOutsideLoop,
+          });
+        }
+
+        node.typ = ValueType::Never;
+      }
 
       ExprKind::Call(call_node) => node.typ = self.analyze_call(call_node),
 
       ExprKind::EmptyTuple => node.typ = ValueType::Nothing,
 
       ExprKind::FieldAccess { receiver, field } => {
-        let receiver_type_binding = self.scope.get_type_binding(&receiver.typ).unwrap();
+        self.analyze_expr(receiver);
 
-        if let TypeBindingKind::Struct { fields } = &receiver_type_binding.kind {
-          match fields.get(&field.name) {
-            Some(binding) => node.typ = binding.typ.clone(),
+        // The problem that made the receiver unknown has already been
+        // reported
+        if receiver.typ == ValueType::Unknown {
+          return;
+        }
 
-            None => self.error(AnalysisError {
-              pos: field.pos,
-              kind: AnalysisErrorKind::UndefinedFieldForType {
-                field_name: field.name.clone(),
-                receiver_type: receiver.typ.clone(),
-              },
-            }),
-          }
-        } else {
-          self.error(AnalysisError {
+        let receiver_type = self.unifier.resolve(&receiver.typ);
+
+        let field_type = self
+          .scope
+          .get_type_binding(&receiver_type)
+          .and_then(|binding| match &binding.kind {
+            TypeBindingKind::Struct { fields, .. } | TypeBindingKind::Trait { fields, .. } => {
+              fields.get(&field.name).map(|field| field.typ.clone())
+            }
+            _ => None,
+          });
+
+        match field_type {
+          Some(field_type) => node.typ = field_type,
+
+          None => self.error(AnalysisError {
             pos: field.pos,
             kind: AnalysisErrorKind::UndefinedFieldForType {
               field_name: field.name.clone(),
-              receiver_type: receiver.typ.clone(),
+              receiver_type,
             },
-          })
+          }),
         }
       }
 
@@ -743,10 +1537,11 @@ impl<'a> Analyzer<'a> {
       }
 
       ExprKind::Interpolation(parts) => {
-        let string_type = ValueType::Named("String".to_owned());
-
         for part in parts {
-          if !self.compatible_types(&string_type, &part.typ) {
+          self.analyze_expr(part);
+
+          // Other values are converted with their to_string method
+          if part.typ != ValueType::String && !self.has_to_string_method(&part.typ) {
             self.error(AnalysisError {
               pos: part.pos,
               kind: AnalysisErrorKind::TypeMismatchInStringInterpolation(part.typ.clone()),
@@ -754,7 +1549,7 @@ impl<'a> Analyzer<'a> {
           }
         }
 
-        node.typ = string_type;
+        node.typ = ValueType::String;
       }
 
       ExprKind::Literal(lit_node) => node.typ = self.analyze_literal(lit_node),
@@ -773,7 +1568,9 @@ impl<'a> Analyzer<'a> {
         // There is a special case here, where if we are calling a function that's a field
         // on a struct, rather than a method, it will be parsed as a MethodAccess at this point.
         // Check to see if we're in that case:
-        if let TypeBindingKind::Struct { fields } = &receiver_type_binding.kind {
+        if let TypeBindingKind::Struct { fields, .. } | TypeBindingKind::Trait { fields, .. } =
+          &receiver_type_binding.kind
+        {
           if method_parts.len() == 1 {
             let potential_field_name = &method_parts[0].name.clone();
 
@@ -793,6 +1590,15 @@ impl<'a> Analyzer<'a> {
 
         if let Some(method_type) = receiver_type_binding.methods.get(&method_name_parts) {
           node.typ = method_type.clone();
+
+          // Methods of generic types are declared in terms of the type's
+          // params, so fill in the receiver's actual types.
+          if let ValueType::Generic(name, args) = &receiver.typ {
+            if let Some(ValueType::Generic(_, params)) = self.scope.get_declared_generic_type(name)
+            {
+              node.typ = type_utils::substitute_generics(&node.typ, &params, args);
+            }
+          }
         } else {
           let pos = (
             method_parts.first().unwrap().pos.0,
@@ -810,19 +1616,33 @@ impl<'a> Analyzer<'a> {
       }
 
       ExprKind::Match(match_node) => {
-        let _subject_type = &match_node.subject.typ;
+        self.analyze_expr(&mut match_node.subject);
+
+        let subject_type = &match_node.subject.typ;
         let mut case_type: Option<ValueType> = None;
+        let mut patterns_are_valid = true;
+
+        for case in &mut match_node.cases {
+          self.scope.enter();
+
+          let error_count = self.error_count();
+          self.destructure_pattern(&case.pattern, subject_type, true);
+          patterns_are_valid &= self.error_count() == error_count;
+          self.analyze_expr(&mut case.body);
 
-        for case in &match_node.cases {
-          if let Some(expected_case_type) = &case_type {
+          let results = self.scope.exit();
+          self.check_results(results);
+
+          // Cases that never produce a value don't decide the match's type
+          if let Some(expected_case_type) = case_type.as_ref().filter(|t| **t != ValueType::Never) {
             let actual_case_type = &case.body.typ;
 
             if !self.compatible_types(&expected_case_type, &actual_case_type) {
               self.error(AnalysisError {
                 pos: case.body.pos,
                 kind: AnalysisErrorKind::TypeMismatchInMatchCase {
-                  expected: expected_case_type.clone(),
-                  actual: actual_case_type.clone(),
+                  expected: self.unifier.resolve(expected_case_type),
+                  actual: self.unifier.resolve(actual_case_type),
                 },
               });
             }
@@ -831,7 +1651,15 @@ impl<'a> Analyzer<'a> {
           }
         }
 
-        node.typ = case_type.unwrap().clone();
+        // A match with no cases never produces a value
+        node.typ = match case_type {
+          Some(case_type) => self.unifier.resolve(&case_type),
+          None => ValueType::Never,
+        };
+
+        if patterns_are_valid {
+          self.check_match_cases(match_node);
+        }
       }
 
       ExprKind::MultiPartIdentifier(ident_nodes) => {
@@ -843,7 +1671,15 @@ impl<'a> Analyzer<'a> {
         let merged_name = names.join(" ");
 
         match self.scope.get_binding(&merged_name) {
-          Some(binding) => node.typ = binding.typ.clone(),
+          Some(binding) => {
+            let is_generic = matches!(
+              binding.kind,
+              BindingKind::Def | BindingKind::EnumVariant | BindingKind::StructConstructor
+            );
+            let typ = binding.typ.clone();
+
+            node.typ = self.binding_type(is_generic, &typ, node.pos);
+          }
           None => self.error(AnalysisError {
             pos: node.pos,
             kind: AnalysisErrorKind::UndefinedMultiPartName(names),
@@ -877,6 +1713,8 @@ impl<'a> Analyzer<'a> {
       }
 
       ExprKind::UnaryOperation { op, right } => {
+        self.analyze_expr(right);
+
         let receiver_type_binding = match self.scope.get_type_binding(&right.typ) {
           Some(binding) => binding,
           _ => return,
@@ -919,13 +1757,155 @@ impl<'a> Analyzer<'a> {
         node.typ = ValueType::LabeledTuple(entry_types);
       }
 
-      _other => todo!("more expr kinds!"),
+      ExprKind::List(elements) => {
+        let element_type = self.analyze_collection_elements(elements.iter_mut());
+
+        node.typ = ValueType::Generic("List".to_owned(), vec![element_type]);
+      }
+
+      ExprKind::Dict(entries) => {
+        let key_type = self.analyze_collection_elements(entries.iter_mut().map(|(key, _)| key));
+        let value_type =
+          self.analyze_collection_elements(entries.iter_mut().map(|(_, value)| value));
+
+        match key_type {
+          ValueType::Int
+          | ValueType::Float
+          | ValueType::String
+          | ValueType::Sized(..)
+          | ValueType::Unknown => {}
+          _ => self.error(AnalysisError {
+            pos: node.pos,
+            kind: AnalysisErrorKind::UnhashableDictKeyType(key_type.clone()),
+          }),
+        }
+
+        node.typ = ValueType::Generic("Dict".to_owned(), vec![key_type, value_type]);
+      }
+
+      ExprKind::RegExpr(reg_expr) => {
+        let mut captures = Vec::new();
+        self.analyze_reg_expr(reg_expr, &mut captures);
+
+        // Regexes are generic over the type of their captures
+        let captures_type = if captures.is_empty() {
+          ValueType::Nothing
+        } else {
+          ValueType::LabeledTuple(captures)
+        };
+
+        node.typ = ValueType::Generic("Regex".to_owned(), vec![captures_type]);
+      }
+
+      ExprKind::Underscore => self.error(AnalysisError {
+        pos: node.pos,
+        kind: AnalysisErrorKind::UnderscoreOutsidePattern,
+      }),
+    }
+  }
+
+  /// Analyzes the elements (or keys, or values) of a List or Dict literal,
+  /// which must all have the same type. The first element decides what
+  /// that type is. Empty literals have an unknown element type.
+  fn analyze_collection_elements<'e>(
+    &mut self,
+    elements: impl Iterator<Item = &'e mut ExprNode>,
+  ) -> ValueType {
+    let mut element_type: Option<ValueType> = None;
+
+    for element in elements {
+      self.analyze_expr(element);
+
+      match &element_type {
+        None => element_type = Some(element.typ.clone()),
+
+        Some(expected) => {
+          if !self.compatible_types(expected, &element.typ) {
+            self.error(AnalysisError {
+              pos: element.pos,
+              kind: AnalysisErrorKind::TypeMismatchInCollectionLiteral {
+                expected: expected.clone(),
+                actual: element.typ.clone(),
+              },
+            })
+          }
+        }
+      }
+    }
+
+    element_type.unwrap_or(ValueType::Unknown)
+  }
+
+  /// Checks that a regular expression only uses defined character classes,
+  /// and collects its named captures. Each capture is a String.
+  fn analyze_reg_expr(&mut self, node: &RegExprNode, captures: &mut Vec<(String, ValueType)>) {
+    match &node.kind {
+      RegExprKind::Literal(..) => {}
+
+      RegExprKind::CharacterClass(name) => {
+        if pluma_regex::character_class(name).is_none() {
+          self.error(AnalysisError {
+            pos: node.pos,
+            kind: AnalysisErrorKind::UndefinedCharacterClass(name.clone()),
+          });
+        }
+      }
+
+      RegExprKind::NamedCapture(name, inner) => {
+        if captures
+          .iter()
+          .any(|(capture_name, _)| capture_name == name)
+        {
+          self.error(AnalysisError {
+            pos: node.pos,
+            kind: AnalysisErrorKind::DuplicateCaptureName(name.clone()),
+          });
+        } else {
+          captures.push((name.clone(), ValueType::String));
+        }
+
+        self.analyze_reg_expr(inner, captures);
+      }
+
+      RegExprKind::Sequence(parts) | RegExprKind::Alternation(parts) => {
+        for part in parts {
+          self.analyze_reg_expr(part, captures);
+        }
+      }
+
+      RegExprKind::Grouping(inner)
+      | RegExprKind::OneOrMore(inner)
+      | RegExprKind::ZeroOrMore(inner)
+      | RegExprKind::OneOrZero(inner)
+      | RegExprKind::AtLeastCount(inner, _)
+      | RegExprKind::AtMostCount(inner, _)
+      | RegExprKind::ExactCount(inner, _)
+      | RegExprKind::RangeCount(inner, _, _) => self.analyze_reg_expr(inner, captures),
     }
   }
 
   fn analyze_identifier(&mut self, node: &IdentifierNode) -> ValueType {
+    // A block's handle can only be used to return from it, which calls
+    // are checked for before getting here.
+    if node.name == "$self" {
+      self.error(AnalysisError {
+        pos: node.pos,
+        kind: AnalysisErrorKind::InvalidBlockHandleUse,
+      });
+
+      return ValueType::Unknown;
+    }
+
     match self.scope.get_binding(&node.name) {
-      Some(binding) => binding.typ.clone(),
+      Some(binding) => {
+        let is_generic = matches!(
+          binding.kind,
+          BindingKind::Def | BindingKind::EnumVariant | BindingKind::StructConstructor
+        );
+        let typ = binding.typ.clone();
+
+        self.binding_type(is_generic, &typ, node.pos)
+      }
       None => {
         self.error(AnalysisError {
           pos: node.pos,
@@ -937,7 +1917,45 @@ impl<'a> Analyzer<'a> {
     }
   }
 
+  /// The type of a use of a binding. Each use of a generic def or type
+  /// constructor gets its own variables for the generic params.
+  fn binding_type(&mut self, is_generic: bool, typ: &ValueType, pos: Position) -> ValueType {
+    if is_generic {
+      let (typ, constraints) = self.unifier.instantiate(typ);
+
+      for (variable, constraint) in constraints {
+        self.trait_constraints.push((variable, constraint, pos));
+      }
+
+      typ
+    } else {
+      self.unifier.resolve(typ)
+    }
+  }
+
   fn analyze_intrinsic_def(&mut self, node: &mut IntrinsicDefNode) {
+    // Intrinsics are implemented by the emitter for each type, so their
+    // generic params are only names
+    self.generic_params = node
+      .generic_type_constraints
+      .iter()
+      .map(|(generic_name, _)| {
+        let name = generic_name.name.clone();
+        (name.clone(), ValueType::Named(name))
+      })
+      .collect();
+
+    if let DefKind::Method { receiver, .. } = &node.kind {
+      for generic in &receiver.generics {
+        if let TypeExprKind::Single(ident) = &generic.kind {
+          let name = ident.name.clone();
+          self
+            .generic_params
+            .insert(name.clone(), ValueType::Named(name));
+        }
+      }
+    }
+
     match &mut node.kind {
       DefKind::Function { signature } => {
         for (_part_name, part_type) in signature {
@@ -969,12 +1987,56 @@ impl<'a> Analyzer<'a> {
     if let Some(return_type) = &mut node.return_type {
       self.analyze_type_expr(return_type);
     }
+
+    self.generic_params.clear();
+  }
+
+  fn analyze_extern_def(&mut self, node: &mut ExternDefNode) {
+    let signature = match &mut node.kind {
+      DefKind::Function { signature } => signature,
+      _ => {
+        self.error(AnalysisError {
+          pos: node.pos,
+          kind: AnalysisErrorKind::ExternDefNotFunction,
+        });
+
+        return;
+      }
+    };
+
+    let takes_nothing = signature.len() == 1;
+
+    for (_part_name, part_type) in signature {
+      self.analyze_type_expr(part_type);
+
+      let typ = type_utils::type_expr_to_value_type(part_type);
+
+      if !(type_utils::is_c_compatible(&typ) || takes_nothing && typ == ValueType::Nothing) {
+        self.error(AnalysisError {
+          pos: part_type.pos,
+          kind: AnalysisErrorKind::UnsupportedExternType(typ),
+        });
+      }
+    }
+
+    if let Some(return_type) = &mut node.return_type {
+      self.analyze_type_expr(return_type);
+
+      let typ = type_utils::type_expr_to_value_type(return_type);
+
+      if !(type_utils::is_c_compatible(&typ) || typ == ValueType::Nothing) {
+        self.error(AnalysisError {
+          pos: return_type.pos,
+          kind: AnalysisErrorKind::UnsupportedExternType(typ),
+        });
+      }
+    }
   }
 
   fn analyze_let(&mut self, node: &mut LetNode) {
     self.analyze_expr(&mut node.value);
 
-    self.destructure_pattern(&mut node.pattern, &mut node.value.typ);
+    self.destructure_pattern(&mut node.pattern, &mut node.value.typ, false);
   }
 
   fn analyze_literal(&mut self, node: &LiteralNode) -> ValueType {
@@ -985,6 +2047,8 @@ impl<'a> Analyzer<'a> {
       LiteralKind::IntOctal { .. } => ValueType::Int,
       LiteralKind::FloatDecimal { .. } => ValueType::Float,
       LiteralKind::Str { .. } => ValueType::String,
+      LiteralKind::SizedInt(_, sized_type) => ValueType::Sized(*sized_type),
+      LiteralKind::SizedFloat(_, sized_type) => ValueType::Sized(*sized_type),
     }
   }
 
@@ -997,6 +2061,16 @@ impl<'a> Analyzer<'a> {
   }
 
   fn analyze_type_identifier(&mut self, node: &mut TypeIdentifierNode) -> ValueType {
+    if node.generics.is_empty() {
+      if let Some(typ) = self.generic_params.get(&node.name) {
+        return typ.clone();
+      }
+    }
+
+    for generic in &mut node.generics {
+      self.analyze_type_expr(generic);
+    }
+
     let named_value_type = type_utils::type_ident_to_value_type(&node);
 
     match self.scope.get_type_binding(&named_value_type) {
@@ -1068,6 +2142,7 @@ impl<'a> VisitorMut for Analyzer<'a> {
 
         TopLevelStatementKind::Def(def_node) => self.collect_def(
           def_node.pos,
+          def_node.visibility,
           &mut def_node.generic_type_constraints,
           &mut def_node.kind,
           &def_node.return_type,
@@ -1075,11 +2150,20 @@ impl<'a> VisitorMut for Analyzer<'a> {
 
         TopLevelStatementKind::IntrinsicDef(def_node) => self.collect_def(
           def_node.pos,
+          def_node.visibility,
           &mut def_node.generic_type_constraints,
           &mut def_node.kind,
           &def_node.return_type,
         ),
 
+        TopLevelStatementKind::ExternDef(def_node) => self.collect_def(
+          def_node.pos,
+          def_node.visibility,
+          &mut Vec::new(),
+          &mut def_node.kind,
+          &def_node.return_type,
+        ),
+
         TopLevelStatementKind::TypeDef(type_def_node) => self.collect_type_def(type_def_node),
 
         TopLevelStatementKind::IntrinsicTypeDef(type_def_node) => {
@@ -1093,12 +2177,37 @@ impl<'a> VisitorMut for Analyzer<'a> {
     }
   }
 
+  fn leave_module(&mut self, node: &mut ModuleNode) {
+    // Everything has been inferred by now, so fill in the solved types
+    self.check_trait_constraints();
+
+    let unifier = &self.unifier;
+
+    self.scope.map_binding_types(|typ| unifier.resolve(typ));
+
+    let mut solved_types = SolvedTypes {
+      unifier,
+      unsolved: Vec::new(),
+    };
+
+    node.traverse_mut(&mut solved_types);
+
+    for (pos, typ) in solved_types.unsolved {
+      self.error(AnalysisError {
+        pos,
+        kind: AnalysisErrorKind::UnsolvedType(typ),
+      });
+    }
+  }
+
   fn enter_top_level_statement(&mut self, node: &mut TopLevelStatementNode) {
     match &mut node.kind {
       TopLevelStatementKind::Def(def_node) => self.analyze_def(def_node),
 
       TopLevelStatementKind::IntrinsicDef(def_node) => self.analyze_intrinsic_def(def_node),
 
+      TopLevelStatementKind::ExternDef(def_node) => self.analyze_extern_def(def_node),
+
       TopLevelStatementKind::Let(let_node) => self.analyze_let(let_node),
 
       TopLevelStatementKind::Expr(expr) => self.analyze_expr(expr),
@@ -1109,3 +2218,28 @@ impl<'a> VisitorMut for Analyzer<'a> {
     }
   }
 }
+
+/// Replaces the types being inferred in each expression with the types
+/// they were solved to. Values whose types weren't fully solved can't be
+/// compiled, so they're collected too (only the outermost, since the
+/// expressions in them are usually unsolved for the same reason).
+struct SolvedTypes<'u> {
+  unifier: &'u Unifier,
+  unsolved: Vec<(Position, ValueType)>,
+}
+
+impl<'u> VisitorMut for SolvedTypes<'u> {
+  fn enter_expr(&mut self, node: &mut ExprNode) {
+    node.typ = self.unifier.resolve(&node.typ);
+
+    let (start, end) = node.pos;
+    let is_inside_unsolved = self
+      .unsolved
+      .iter()
+      .any(|((outer_start, outer_end), _)| *outer_start <= start && end <= *outer_end);
+
+    if !is_inside_unsolved && self.unifier.has_unsolved_variables(&node.typ) {
+      self.unsolved.push((node.pos, node.typ.clone()));
+    }
+  }
+}
@@ -1,6 +1,8 @@
 use pluma_ast::*;
+use pluma_match::Variant;
 use std::collections::HashMap;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct Binding {
   pub typ: ValueType,
@@ -9,6 +11,7 @@ pub struct Binding {
   pub kind: BindingKind,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct TypeBinding {
   pub ref_count: usize,
@@ -17,7 +20,7 @@ pub struct TypeBinding {
   pub methods: HashMap<Vec<String>, ValueType>,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum BindingKind {
   Const,
@@ -29,11 +32,21 @@ pub enum BindingKind {
   Field,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum TypeBindingKind {
-  Enum,
-  Struct { fields: HashMap<String, Binding> },
+  Enum {
+    variants: Vec<Variant>,
+  },
+  Struct {
+    fields: HashMap<String, Binding>,
+    inner_type: ValueType,
+  },
   Alias,
-  Trait { fields: HashMap<String, Binding> },
+  /// A trait's required methods are kept in its binding's `methods`.
+  Trait {
+    fields: HashMap<String, Binding>,
+    generic_params: Vec<ValueType>,
+  },
   IntrinsicType,
 }
@@ -0,0 +1,135 @@
+use crate::binding::*;
+use pluma_ast::*;
+
+/// A definition that a module can make visible to the modules that `use` it.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Export {
+  Binding(String, Binding),
+  /// Types are exported without their methods, which are exported
+  /// separately, since each method has its own visibility.
+  Type(ValueType, TypeBinding),
+  Method(ValueType, Vec<String>, ValueType),
+}
+
+/// Everything a module defines at its top level, each with the visibility
+/// it was declared with.
+#[derive(Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ModuleExports {
+  exports: Vec<(ExportVisibility, Export)>,
+}
+
+impl ModuleExports {
+  pub fn add(&mut self, visibility: ExportVisibility, export: Export) {
+    self.exports.push((visibility, export));
+  }
+
+  /// The exports that an importing module can see. Public definitions are
+  /// visible everywhere, internal ones only within the same package, and
+  /// private ones nowhere else.
+  pub fn visible_to(&self, same_package: bool) -> impl Iterator<Item = &Export> {
+    self
+      .exports
+      .iter()
+      .filter(move |(visibility, _)| match visibility {
+        ExportVisibility::Public => true,
+        ExportVisibility::Internal => same_package,
+        ExportVisibility::Private => false,
+      })
+      .map(|(_, export)| export)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analyzer::Analyzer;
+  use crate::scope::Scope;
+  use pluma_diagnostics::Diagnostic;
+  use pluma_parser::{Parser, Tokenizer};
+  use pluma_visitor::TraverseMut;
+
+  const IO_MODULE: &str = "
+intrinsic_type String
+
+intrinsic_def out String -> ()
+
+internal
+
+intrinsic_def debug String -> ()
+
+private
+
+intrinsic_def write String -> ()
+";
+
+  fn analyze(
+    source: &str,
+    imports: &[(&ModuleExports, bool, Option<&str>)],
+  ) -> (ModuleExports, Vec<Diagnostic>) {
+    let bytes = Vec::from(source.trim());
+    let tokenizer = Tokenizer::from_source(&bytes, false);
+    let mut parser = Parser::new(&bytes, tokenizer, false);
+    let (mut ast, _, _, errors) = parser.parse_module();
+
+    assert!(errors.is_empty());
+
+    let mut scope = Scope::new();
+    scope.enter();
+
+    for (exports, same_package, qualifier) in imports {
+      scope.import(
+        exports.visible_to(*same_package),
+        qualifier.map(|qualifier| qualifier.to_owned()),
+      );
+    }
+
+    scope.enter();
+
+    let mut analyzer = Analyzer::new(&mut scope);
+    ast.traverse_mut(&mut analyzer);
+
+    (analyzer.exports, analyzer.diagnostics)
+  }
+
+  #[test]
+  fn public_names_are_visible_to_other_packages() {
+    let (io, _) = analyze(IO_MODULE, &[]);
+    let (_, diagnostics) = analyze("out \"hi\"", &[(&io, false, None)]);
+
+    assert!(diagnostics.is_empty());
+  }
+
+  #[test]
+  fn internal_names_are_only_visible_in_the_same_package() {
+    let (io, _) = analyze(IO_MODULE, &[]);
+
+    let (_, diagnostics) = analyze("debug \"hi\"", &[(&io, true, None)]);
+    assert!(diagnostics.is_empty());
+
+    let (_, diagnostics) = analyze("debug \"hi\"", &[(&io, false, None)]);
+    assert!(diagnostics[0].message == "Name 'debug' is not defined.");
+  }
+
+  #[test]
+  fn private_names_are_not_visible() {
+    let (io, _) = analyze(IO_MODULE, &[]);
+    let (_, diagnostics) = analyze("write \"hi\"", &[(&io, true, None)]);
+
+    assert!(diagnostics[0].message == "Name 'write' is not defined.");
+  }
+
+  #[test]
+  fn qualified_names_are_only_visible_through_their_qualifier() {
+    let (io, _) = analyze(IO_MODULE, &[]);
+
+    let (_, diagnostics) = analyze("io.out \"hi\"", &[(&io, false, Some("io"))]);
+    assert!(diagnostics.is_empty());
+
+    let (_, diagnostics) = analyze("out \"hi\"", &[(&io, false, Some("io"))]);
+    assert!(diagnostics[0].message == "Name 'out' is not defined.");
+
+    let (_, diagnostics) = analyze("io.write \"hi\"", &[(&io, false, Some("io"))]);
+    assert!(diagnostics[0].message == "Name 'io.write' is not defined.");
+  }
+}
@@ -1,8 +1,11 @@
 mod analysis_error;
 mod analyzer;
 mod binding;
+mod exports;
 mod scope;
 mod type_utils;
+mod unifier;
 
 pub use analyzer::*;
+pub use exports::*;
 pub use scope::*;
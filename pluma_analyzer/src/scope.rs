@@ -1,7 +1,10 @@
 use crate::analysis_error::{AnalysisError, AnalysisErrorKind};
 use crate::binding::*;
+use crate::exports::*;
+use crate::type_utils;
 use pluma_ast::*;
 use pluma_diagnostics::*;
+use pluma_match::{TypeInfo, Variant};
 use std::collections::HashMap;
 
 struct ScopeLevel {
@@ -31,6 +34,9 @@ impl std::fmt::Debug for ScopeLevel {
 pub struct Scope {
   levels: Vec<ScopeLevel>,
   type_bindings: HashMap<ValueType, TypeBinding>,
+  /// Bindings from modules imported with a qualifier (e.g. `use foo as f`),
+  /// which are only reachable through it (e.g. `f.bar`).
+  qualified_bindings: HashMap<String, HashMap<String, Binding>>,
 }
 
 #[cfg(debug_assertions)]
@@ -59,6 +65,7 @@ impl Scope {
     Scope {
       levels: Vec::new(),
       type_bindings: HashMap::new(),
+      qualified_bindings: HashMap::new(),
     }
   }
 
@@ -73,7 +80,8 @@ impl Scope {
 
     if let Some(exited_level) = self.levels.pop() {
       for (name, binding) in exited_level.bindings {
-        if binding.ref_count == 0 {
+        // Blocks' implicit params ($0, $1, etc.) don't have to be used
+        if binding.ref_count == 0 && !name.starts_with('$') {
           diagnostics.push(
             Diagnostic::warning(AnalysisError {
               pos: binding.pos,
@@ -113,13 +121,20 @@ impl Scope {
   }
 
   pub fn add_type_binding(&mut self, typ: ValueType, kind: TypeBindingKind, pos: (usize, usize)) {
+    // A type that's declared again (e.g. an intrinsic type that's also in
+    // the prelude) keeps the methods it already has
+    let methods = match self.type_bindings.remove(&typ) {
+      Some(existing_binding) => existing_binding.methods,
+      None => HashMap::new(),
+    };
+
     self.type_bindings.insert(
       typ,
       TypeBinding {
         ref_count: 0,
         pos,
         kind,
-        methods: HashMap::new(),
+        methods,
       },
     );
   }
@@ -160,6 +175,80 @@ impl Scope {
     Ok(())
   }
 
+  /// Replaces the type of every binding in scope, e.g. once the types
+  /// that were being inferred are known.
+  pub fn map_binding_types(&mut self, f: impl Fn(&ValueType) -> ValueType) {
+    for level in &mut self.levels {
+      for binding in level.bindings.values_mut() {
+        binding.typ = f(&binding.typ);
+      }
+    }
+  }
+
+  /// Adds the definitions that another module exports to the current
+  /// level. Its types are always added, so that values of those types
+  /// can be used, but its other bindings go under the qualifier if there
+  /// is one.
+  pub fn import<'e>(
+    &mut self,
+    exports: impl Iterator<Item = &'e Export>,
+    qualifier: Option<String>,
+  ) {
+    for export in exports {
+      match export {
+        Export::Binding(name, binding) => {
+          let bindings = match &qualifier {
+            Some(qualifier) => self
+              .qualified_bindings
+              .entry(qualifier.clone())
+              .or_default(),
+            None => &mut self.levels.last_mut().expect("no current scope").bindings,
+          };
+
+          bindings.insert(name.clone(), binding.clone());
+        }
+
+        Export::Type(typ, type_binding) => {
+          self
+            .type_bindings
+            .entry(typ.clone())
+            .or_insert_with(|| type_binding.clone());
+        }
+
+        // Methods can be defined for types from other modules, which are
+        // only known here if they've been imported too
+        Export::Method(typ, method_parts, method_type) => {
+          if let Some(type_binding) = self.get_type_binding(typ) {
+            type_binding
+              .methods
+              .insert(method_parts.clone(), method_type.clone());
+          }
+        }
+      }
+    }
+  }
+
+  pub fn is_qualifier(&self, name: &str) -> bool {
+    self.qualified_bindings.contains_key(name)
+  }
+
+  pub fn get_qualified_binding(&mut self, qualifier: &str, name: &str) -> Option<&Binding> {
+    let binding = self.qualified_bindings.get_mut(qualifier)?.get_mut(name)?;
+
+    binding.ref_count += 1;
+
+    Some(binding)
+  }
+
+  /// Looks up a binding without counting it as a use.
+  pub fn peek_binding(&self, name: &str) -> Option<&Binding> {
+    self
+      .levels
+      .iter()
+      .rev()
+      .find_map(|level| level.bindings.get(name))
+  }
+
   pub fn get_binding(&mut self, name: &String) -> Option<&Binding> {
     for level in self.levels.iter_mut().rev() {
       if let Some(binding) = level.bindings.get_mut(name) {
@@ -173,7 +262,22 @@ impl Scope {
   }
 
   pub fn get_type_binding(&mut self, typ: &ValueType) -> Option<&mut TypeBinding> {
-    if let Some(binding) = self.type_bindings.get_mut(typ) {
+    // Generic types are bound once, under their declared params, and that
+    // binding is shared by every instance (e.g. List<Int> uses List<A>'s).
+    // Generic traits are bound by name.
+    let declared_type = match typ {
+      ValueType::Generic(name, _) if !self.type_bindings.contains_key(typ) => self
+        .get_declared_generic_type(name)
+        .unwrap_or_else(|| ValueType::Named(name.clone())),
+      // A generic param has the members that its trait requires
+      ValueType::Constrained(_, TypeConstraint::NamedTrait(name))
+      | ValueType::Constrained(_, TypeConstraint::GenericTrait(name, _)) => {
+        ValueType::Named(name.clone())
+      }
+      _ => typ.clone(),
+    };
+
+    if let Some(binding) = self.type_bindings.get_mut(&declared_type) {
       // binding.ref_count += 1;
 
       return Some(binding);
@@ -181,4 +285,71 @@ impl Scope {
 
     None
   }
+
+  /// The type that a generic type was declared as, e.g. `List<A>`.
+  pub fn get_declared_generic_type(&self, name: &str) -> Option<ValueType> {
+    self
+      .type_bindings
+      .keys()
+      .find(|typ| matches!(typ, ValueType::Generic(declared_name, _) if declared_name == name))
+      .cloned()
+  }
+
+  /// The binding that a type was declared with. For an instance of a
+  /// generic type, also returns the declared params and the instance's
+  /// args, which fill in the types in the binding.
+  fn declared_type_binding(
+    &self,
+    typ: &ValueType,
+  ) -> Option<(&TypeBinding, Vec<ValueType>, Vec<ValueType>)> {
+    match typ {
+      ValueType::Generic(name, args) => match self.get_declared_generic_type(name)? {
+        ValueType::Generic(declared_name, params) => {
+          let declared_type = ValueType::Generic(declared_name, params.clone());
+
+          Some((
+            self.type_bindings.get(&declared_type)?,
+            params,
+            args.clone(),
+          ))
+        }
+        _ => None,
+      },
+
+      _ => Some((self.type_bindings.get(typ)?, Vec::new(), Vec::new())),
+    }
+  }
+}
+
+impl TypeInfo for Scope {
+  fn enum_variants(&self, typ: &ValueType) -> Option<Vec<Variant>> {
+    let (binding, params, args) = self.declared_type_binding(typ)?;
+
+    match &binding.kind {
+      TypeBindingKind::Enum { variants } => Some(
+        variants
+          .iter()
+          .map(|variant| Variant {
+            name: variant.name.clone(),
+            payload_type: variant
+              .payload_type
+              .as_ref()
+              .map(|payload_type| type_utils::substitute_generics(payload_type, &params, &args)),
+          })
+          .collect(),
+      ),
+      _ => None,
+    }
+  }
+
+  fn struct_inner_type(&self, typ: &ValueType) -> Option<ValueType> {
+    let (binding, params, args) = self.declared_type_binding(typ)?;
+
+    match &binding.kind {
+      TypeBindingKind::Struct { inner_type, .. } => {
+        Some(type_utils::substitute_generics(inner_type, &params, &args))
+      }
+      _ => None,
+    }
+  }
 }
@@ -7,7 +7,75 @@ pub fn type_ident_to_value_type(node: &TypeIdentifierNode) -> ValueType {
     "Int" => ValueType::Int,
     "Float" => ValueType::Float,
     "String" => ValueType::String,
-    _ => ValueType::Named(node.name.clone()),
+    "Pointer" => ValueType::Pointer,
+    "Never" => ValueType::Never,
+    _ if !node.generics.is_empty() => ValueType::Generic(
+      node.name.clone(),
+      node.generics.iter().map(type_expr_to_value_type).collect(),
+    ),
+    name => match SizedType::from_name(name) {
+      Some(sized_type) => ValueType::Sized(sized_type),
+      None => ValueType::Named(node.name.clone()),
+    },
+  }
+}
+
+/// The type that each of a def's generic params stands for in the def,
+/// e.g. `A` in `where A :: Any`.
+pub fn generic_param_types(constraints: &GenericTypeConstraints) -> Vec<(String, ValueType)> {
+  constraints
+    .iter()
+    .map(|(name, constraint_type_id)| {
+      let constraint = if constraint_type_id.generics.is_empty() {
+        TypeConstraint::NamedTrait(constraint_type_id.name.clone())
+      } else {
+        TypeConstraint::GenericTrait(
+          constraint_type_id.name.clone(),
+          constraint_type_id
+            .generics
+            .iter()
+            .map(type_expr_to_value_type)
+            .collect(),
+        )
+      };
+
+      (
+        name.name.clone(),
+        ValueType::Constrained(name.name.clone(), constraint),
+      )
+    })
+    .collect()
+}
+
+/// Replaces each of a generic type's params (e.g. the `A` in `List<A>`)
+/// with the matching arg, wherever it appears in `typ`.
+pub fn substitute_generics(typ: &ValueType, params: &[ValueType], args: &[ValueType]) -> ValueType {
+  if let Some(i) = params.iter().position(|param| param == typ) {
+    if let Some(arg) = args.get(i) {
+      return arg.clone();
+    }
+  }
+
+  let substitute = |typ: &ValueType| substitute_generics(typ, params, args);
+
+  match typ {
+    ValueType::Generic(name, generic_args) => {
+      ValueType::Generic(name.clone(), generic_args.iter().map(substitute).collect())
+    }
+    ValueType::Func(param_types, return_type) => ValueType::Func(
+      param_types.iter().map(substitute).collect(),
+      Box::new(substitute(return_type)),
+    ),
+    ValueType::UnlabeledTuple(entry_types) => {
+      ValueType::UnlabeledTuple(entry_types.iter().map(substitute).collect())
+    }
+    ValueType::LabeledTuple(entries) => ValueType::LabeledTuple(
+      entries
+        .iter()
+        .map(|(label, entry_type)| (label.clone(), substitute(entry_type)))
+        .collect(),
+    ),
+    other => other.clone(),
   }
 }
 
@@ -99,3 +167,32 @@ pub fn type_expr_to_struct_fields(node: &TypeExprNode) -> HashMap<String, Bindin
 
   fields
 }
+
+/// Whether values of this type can be passed to or returned from C as they are.
+pub fn is_c_compatible(typ: &ValueType) -> bool {
+  matches!(
+    typ,
+    ValueType::Int
+      | ValueType::Float
+      | ValueType::String
+      | ValueType::Pointer
+      | ValueType::Sized(_)
+  )
+}
+
+/// Whether a type is known all the way down, with no parts that failed to
+/// analyze or are still being inferred.
+pub fn is_fully_known(typ: &ValueType) -> bool {
+  match typ {
+    ValueType::Unknown | ValueType::Variable(_) => false,
+    ValueType::Generic(_, args) => args.iter().all(is_fully_known),
+    ValueType::Func(param_types, return_type) => {
+      param_types.iter().all(is_fully_known) && is_fully_known(return_type)
+    }
+    ValueType::UnlabeledTuple(entry_types) => entry_types.iter().all(is_fully_known),
+    ValueType::LabeledTuple(entries) => entries
+      .iter()
+      .all(|(_, entry_type)| is_fully_known(entry_type)),
+    _ => true,
+  }
+}
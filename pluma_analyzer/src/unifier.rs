@@ -0,0 +1,316 @@
+use crate::type_utils;
+use pluma_ast::*;
+use std::collections::HashMap;
+
+/// Solves the type variables (`ValueType::Variable`) that come up while
+/// inferring types, Hindley-Milner style. Each time two types are expected
+/// to be the same, they're unified, which either solves variables so that
+/// the types match, or fails.
+pub struct Unifier {
+  next_variable: usize,
+  solutions: HashMap<usize, ValueType>,
+}
+
+impl Unifier {
+  pub fn new() -> Self {
+    Unifier {
+      next_variable: 0,
+      solutions: HashMap::new(),
+    }
+  }
+
+  pub fn fresh_variable(&mut self) -> ValueType {
+    let variable = ValueType::Variable(self.next_variable);
+    self.next_variable += 1;
+    variable
+  }
+
+  /// Gives each generic param in a def's type a fresh variable, so that
+  /// every use of the def can solve its params differently. Also returns
+  /// each new variable with the constraint that its solution must satisfy.
+  pub fn instantiate(&mut self, typ: &ValueType) -> (ValueType, Vec<(ValueType, TypeConstraint)>) {
+    let mut params = Vec::new();
+    collect_generic_params(typ, &mut params);
+
+    if params.is_empty() {
+      return (typ.clone(), Vec::new());
+    }
+
+    let variables = params
+      .iter()
+      .map(|_| self.fresh_variable())
+      .collect::<Vec<ValueType>>();
+
+    let constraints = params
+      .iter()
+      .zip(&variables)
+      .filter_map(|(param, variable)| match param {
+        ValueType::Constrained(_, constraint) => Some((variable.clone(), constraint.clone())),
+        _ => None,
+      })
+      .collect();
+
+    (
+      type_utils::substitute_generics(typ, &params, &variables),
+      constraints,
+    )
+  }
+
+  /// Replaces every solved variable in a type with its solution.
+  pub fn resolve(&self, typ: &ValueType) -> ValueType {
+    let resolve = |typ: &ValueType| self.resolve(typ);
+
+    match typ {
+      ValueType::Variable(id) => match self.solutions.get(id) {
+        Some(solution) => self.resolve(solution),
+        None => typ.clone(),
+      },
+      ValueType::Generic(name, args) => {
+        ValueType::Generic(name.clone(), args.iter().map(resolve).collect())
+      }
+      ValueType::Func(param_types, return_type) => ValueType::Func(
+        param_types.iter().map(resolve).collect(),
+        Box::new(resolve(return_type)),
+      ),
+      ValueType::UnlabeledTuple(entry_types) => {
+        ValueType::UnlabeledTuple(entry_types.iter().map(resolve).collect())
+      }
+      ValueType::LabeledTuple(entries) => ValueType::LabeledTuple(
+        entries
+          .iter()
+          .map(|(label, entry_type)| (label.clone(), resolve(entry_type)))
+          .collect(),
+      ),
+      other => other.clone(),
+    }
+  }
+
+  /// Makes `actual` fit where `expected` is expected, by solving the
+  /// variables in either of them. Returns false if they can't fit, in
+  /// which case some variables may have been solved anyway.
+  pub fn unify(&mut self, expected: &ValueType, actual: &ValueType) -> bool {
+    let expected = self.resolve_variable(expected);
+    let actual = self.resolve_variable(actual);
+
+    if expected == actual {
+      return true;
+    }
+
+    match (&expected, &actual) {
+      // Nothing is ever produced with type Never, so it can stand in for any type
+      (_, ValueType::Never) => true,
+
+      (ValueType::Variable(id), other) | (other, ValueType::Variable(id)) => {
+        // Solving a variable to a type that contains it would make an
+        // infinitely large type, e.g. `?0 = List<?0>`.
+        if self.occurs(*id, other) {
+          return false;
+        }
+
+        self.solutions.insert(*id, other.clone());
+        true
+      }
+
+      (
+        ValueType::Generic(expected_name, expected_args),
+        ValueType::Generic(actual_name, actual_args),
+      ) => expected_name == actual_name && self.unify_all(expected_args, actual_args),
+
+      (
+        ValueType::Func(expected_params, expected_return),
+        ValueType::Func(actual_params, actual_return),
+      ) => {
+        self.unify_all(expected_params, actual_params) && self.unify(expected_return, actual_return)
+      }
+
+      (ValueType::UnlabeledTuple(expected_entries), ValueType::UnlabeledTuple(actual_entries)) => {
+        self.unify_all(expected_entries, actual_entries)
+      }
+
+      (ValueType::LabeledTuple(expected_entries), ValueType::LabeledTuple(actual_entries)) => {
+        expected_entries.len() == actual_entries.len()
+          && expected_entries.iter().zip(actual_entries).all(
+            |((expected_label, expected_type), (actual_label, actual_type))| {
+              expected_label == actual_label && self.unify(expected_type, actual_type)
+            },
+          )
+      }
+
+      _ => false,
+    }
+  }
+
+  /// Whether a type still has variables in it once it's resolved, i.e.
+  /// nothing has said what they stand for.
+  pub fn has_unsolved_variables(&self, typ: &ValueType) -> bool {
+    match self.resolve_variable(typ) {
+      ValueType::Variable(_) => true,
+      ValueType::Generic(_, args) => args.iter().any(|arg| self.has_unsolved_variables(arg)),
+      ValueType::Func(param_types, return_type) => {
+        param_types
+          .iter()
+          .any(|param| self.has_unsolved_variables(param))
+          || self.has_unsolved_variables(&return_type)
+      }
+      ValueType::UnlabeledTuple(entry_types) => entry_types
+        .iter()
+        .any(|entry| self.has_unsolved_variables(entry)),
+      ValueType::LabeledTuple(entries) => entries
+        .iter()
+        .any(|(_, entry)| self.has_unsolved_variables(entry)),
+      _ => false,
+    }
+  }
+
+  fn unify_all(&mut self, expected: &[ValueType], actual: &[ValueType]) -> bool {
+    expected.len() == actual.len()
+      && expected
+        .iter()
+        .zip(actual)
+        .all(|(expected, actual)| self.unify(expected, actual))
+  }
+
+  /// Follows a variable's solutions until they end, without looking
+  /// inside any other type.
+  fn resolve_variable(&self, typ: &ValueType) -> ValueType {
+    match typ {
+      ValueType::Variable(id) => match self.solutions.get(id) {
+        Some(solution) => self.resolve_variable(solution),
+        None => typ.clone(),
+      },
+      other => other.clone(),
+    }
+  }
+
+  fn occurs(&self, id: usize, typ: &ValueType) -> bool {
+    match self.resolve_variable(typ) {
+      ValueType::Variable(other_id) => id == other_id,
+      ValueType::Generic(_, args) => args.iter().any(|arg| self.occurs(id, arg)),
+      ValueType::Func(param_types, return_type) => {
+        param_types.iter().any(|param| self.occurs(id, param)) || self.occurs(id, &return_type)
+      }
+      ValueType::UnlabeledTuple(entry_types) => {
+        entry_types.iter().any(|entry| self.occurs(id, entry))
+      }
+      ValueType::LabeledTuple(entries) => entries.iter().any(|(_, entry)| self.occurs(id, entry)),
+      _ => false,
+    }
+  }
+}
+
+/// Collects the distinct generic params in a type, in the order they're
+/// first found.
+fn collect_generic_params(typ: &ValueType, params: &mut Vec<ValueType>) {
+  match typ {
+    ValueType::Constrained(..) if !params.contains(typ) => params.push(typ.clone()),
+    ValueType::Generic(_, args) => {
+      for arg in args {
+        collect_generic_params(arg, params);
+      }
+    }
+    ValueType::Func(param_types, return_type) => {
+      for param_type in param_types {
+        collect_generic_params(param_type, params);
+      }
+
+      collect_generic_params(return_type, params);
+    }
+    ValueType::UnlabeledTuple(entry_types) => {
+      for entry_type in entry_types {
+        collect_generic_params(entry_type, params);
+      }
+    }
+    ValueType::LabeledTuple(entries) => {
+      for (_, entry_type) in entries {
+        collect_generic_params(entry_type, params);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn generic_param(name: &str) -> ValueType {
+    ValueType::Constrained(
+      name.to_owned(),
+      TypeConstraint::NamedTrait("Any".to_owned()),
+    )
+  }
+
+  fn list_of(typ: ValueType) -> ValueType {
+    ValueType::Generic("List".to_owned(), vec![typ])
+  }
+
+  #[test]
+  fn solves_variables_inside_other_types() {
+    let mut unifier = Unifier::new();
+    let a = unifier.fresh_variable();
+
+    let expected = ValueType::Func(vec![list_of(a.clone())], Box::new(a.clone()));
+    let actual = ValueType::Func(vec![list_of(ValueType::Int)], Box::new(ValueType::Int));
+
+    assert!(unifier.unify(&expected, &actual));
+    assert!(unifier.resolve(&expected) == actual);
+  }
+
+  #[test]
+  fn rejects_conflicting_solutions() {
+    let mut unifier = Unifier::new();
+    let a = unifier.fresh_variable();
+
+    let expected = ValueType::UnlabeledTuple(vec![a.clone(), a]);
+    let actual = ValueType::UnlabeledTuple(vec![ValueType::Int, ValueType::String]);
+
+    assert!(!unifier.unify(&expected, &actual));
+  }
+
+  #[test]
+  fn finds_unsolved_variables() {
+    let mut unifier = Unifier::new();
+    let a = unifier.fresh_variable();
+    let b = unifier.fresh_variable();
+
+    assert!(unifier.unify(&a, &ValueType::Int));
+    assert!(!unifier.has_unsolved_variables(&list_of(a)));
+    assert!(unifier.has_unsolved_variables(&list_of(b)));
+  }
+
+  #[test]
+  fn rejects_infinite_types() {
+    let mut unifier = Unifier::new();
+    let a = unifier.fresh_variable();
+
+    assert!(!unifier.unify(&a, &list_of(a.clone())));
+  }
+
+  #[test]
+  fn instantiate_returns_each_variable_with_its_constraint() {
+    let mut unifier = Unifier::new();
+    let def_type = ValueType::Func(vec![generic_param("A")], Box::new(generic_param("B")));
+
+    let (_, constraints) = unifier.instantiate(&def_type);
+
+    assert!(constraints.len() == 2);
+    assert!(constraints[0].0 != constraints[1].0);
+    assert!(constraints[0].1 == TypeConstraint::NamedTrait("Any".to_owned()));
+  }
+
+  #[test]
+  fn instantiates_generic_params_separately_for_each_use() {
+    let mut unifier = Unifier::new();
+    let def_type = ValueType::Func(vec![generic_param("A")], Box::new(generic_param("A")));
+
+    let (first, _) = unifier.instantiate(&def_type);
+    let (second, _) = unifier.instantiate(&def_type);
+
+    let int_func = ValueType::Func(vec![ValueType::Int], Box::new(ValueType::Int));
+    let string_func = ValueType::Func(vec![ValueType::String], Box::new(ValueType::String));
+
+    assert!(unifier.unify(&first, &int_func));
+    assert!(unifier.unify(&second, &string_func));
+    assert!(!unifier.unify(&first, &string_func));
+  }
+}
@@ -57,6 +57,217 @@ test_analyze! {
     |let b = Right(47)
   "#,
 
+  match_enum_variants (true): r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let n = match c
+    |  | Red => 1
+    |  | Green => 2
+    |  | _ => 3
+  "#,
+
+  match_enum_payload (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Either
+    | | Left(Int)
+    | | Right(String)
+    |
+    |let e = Left(47)
+    |
+    |let s = match e
+    |  | Left 0 => "zero"
+    |  | Left _ => "some number"
+    |  | Right str => str
+  "#,
+
+  match_generic_enum_payload (true): r#"
+    |intrinsic_type Int
+    |
+    |enum Maybe<A> where A :: Any
+    |  | Some A
+    |  | None
+    |
+    |let m = Some 5
+    |
+    |let n = match m
+    |  | Some x => x
+    |  | None => 0
+  "#,
+
+  match_generic_enum_param (true): r#"
+    |intrinsic_type Int
+    |
+    |enum Maybe<A> where A :: Any
+    |  | Some A
+    |  | None
+    |
+    |def unwrap Maybe<Int> or Int -> Int {
+    |  |maybe, default|
+    |  match maybe
+    |    | Some x => x
+    |    | None => default
+    |}
+    |
+    |let a = unwrap (Some 5) or 0
+    |let b = unwrap None or 0
+  "#,
+
+  generic_enum_payload_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Maybe<A> where A :: Any
+    |  | Some A
+    |  | None
+    |
+    |def unwrap Maybe<Int> or Int -> Int {
+    |  |maybe, default|
+    |  match maybe
+    |    | Some x => x
+    |    | None => default
+    |}
+    |
+    |let a = unwrap (Some "five") or 0
+  "#,
+
+  match_not_exhaustive (false): r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let n = match c
+    |  | Red => 1
+    |  | Green => 2
+  "#,
+
+  match_nested_not_exhaustive (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Either
+    | | Left(Int)
+    | | Right(String)
+    |
+    |let pair = (Left(1), Right("two"))
+    |
+    |let n = match pair
+    |  | (Left 0, _) => 0
+    |  | (Left a, Right _) => a
+    |  | (Right _, Right _) => 3
+  "#,
+
+  match_struct_not_exhaustive (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Either
+    | | Left(Int)
+    | | Right(String)
+    |
+    |struct Wrapper (inner :: Either, count :: Int)
+    |
+    |let w = Wrapper (inner: Left(1), count: 2)
+    |
+    |let n = match w
+    |  | Wrapper (inner: Left 0, count: _) => 0
+    |  | Wrapper (inner: Right _, count: 1) => 1
+  "#,
+
+  match_int_literals_not_exhaustive (false): r#"
+    |intrinsic_type Int
+    |
+    |let i = 2
+    |
+    |let n = match i
+    |  | 0 => 100
+    |  | 1 => 200
+  "#,
+
+  match_unreachable_case (false): r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let n = match c
+    |  | Red => 1
+    |  | _ => 2
+    |  | Blue => 3
+  "#,
+
+  match_literal_type_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |let n = 47
+    |
+    |let s = match n
+    |  | "47" => "yes"
+    |  | _ => "no"
+  "#,
+
+  match_interpolation_pattern (true): r#"
+    |intrinsic_type String
+    |
+    |let file = "main.pa"
+    |
+    |let s = match file
+    |  | "$(name).$(_)" => name
+    |  | other => other
+  "#,
+
+  match_interpolation_invalid_hole (false): r#"
+    |intrinsic_type String
+    |
+    |let file = "main.pa"
+    |
+    |let s = match file
+    |  | "$("main").pa" => "main"
+    |  | other => other
+  "#,
+
+  match_interpolation_adjacent_holes (false): r#"
+    |intrinsic_type String
+    |
+    |let file = "main.pa"
+    |
+    |let s = match file
+    |  | "$(_)$(_)$(c).pa" => c
+    |  | other => other
+  "#,
+
+  match_interpolation_type_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |let n = 47
+    |
+    |let s = match n
+    |  | "$(digits)7" => digits
+    |  | _ => "no"
+  "#,
+
+  struct_constructor_and_field_access (true): r#"
+    |intrinsic_type String
+    |
+    |struct Person (name :: String)
+    |
+    |let p = Person (name: "Reid")
+    |let name = p.name
+    |
+    |let n = match p
+    |  | Person (name: other) => other
+  "#,
+
   let_unlabeled_tuple_pattern (true): r#"
     |let tup = (47, "wow", 1.23)
     |let (a, b, c) = tup
@@ -97,6 +308,16 @@ test_analyze! {
     |let (age: age2, wat: name2) = tup
   "#,
 
+  let_mut_reassignment (true): r#"
+    |let mut count = 1
+    |count = 2
+  "#,
+
+  let_mut_reassignment_type_mismatch (false): r#"
+    |let mut count = 1
+    |count = "two"
+  "#,
+
   def_function (true): r#"
     |intrinsic_type Int
     |
@@ -112,6 +333,41 @@ test_analyze! {
     |let n = intToInt 100
   "#,
 
+  block_implicit_param (true): r#"
+    |intrinsic_type Int
+    |intrinsic_def Int + Int -> Int
+    |
+    |def apply { Int -> Int } to Int -> Int {
+    |  |f, x| f x
+    |}
+    |
+    |let n = apply { $0 + 1 } to 41
+  "#,
+
+  block_explicit_param (true): r#"
+    |intrinsic_type Int
+    |intrinsic_def Int + Int -> Int
+    |
+    |def apply { Int -> Int } to Int -> Int {
+    |  |f, x| f x
+    |}
+    |
+    |let offset = 1
+    |let n = apply { |x| x + offset } to 41
+  "#,
+
+  block_param_type_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |intrinsic_def Int + Int -> Int
+    |
+    |def apply { String -> Int } to String -> Int {
+    |  |f, s| f s
+    |}
+    |
+    |let n = apply { $0 + 1 } to "wat"
+  "#,
+
   undefined_type_in_signature (false): r#"
     |def takesSomething Wat {
     |  |arg| ()
@@ -150,4 +406,375 @@ test_analyze! {
     |let n = 47 :: String
     |let s = "lol" :: Int
   "#,
+
+  interpolation_strings (true): r#"
+    |intrinsic_type String
+    |
+    |let name = "world"
+    |let greeting = "hello $(name)!"
+  "#,
+
+  interpolation_to_string (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |intrinsic_def Int .. to_string () -> String
+    |
+    |let count = 3
+    |let message = "$(count) apples"
+  "#,
+
+  interpolation_without_to_string (false): r#"
+    |intrinsic_type String
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let message = "the sky is $(Blue)"
+  "#,
+
+  list_literal (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type List<A> where A :: Any
+    |
+    |intrinsic_def List<A> .. push A -> ()
+    |
+    |let numbers = [1, 2, 3]
+    |numbers..push 4
+  "#,
+
+  list_literal_mismatched_elements (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |let things = [1, "two", 3]
+  "#,
+
+  dict_literal (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |intrinsic_type Dict<K, V> where K :: Any, V :: Any
+    |
+    |intrinsic_def Dict<K, V> .. get K -> V
+    |
+    |let ages = ["ada": 36, "alan": 41]
+    |let age = ages..get "ada"
+  "#,
+
+  dict_literal_unhashable_key (false): r#"
+    |intrinsic_type Int
+    |
+    |let pairs = [(1, 2): 3]
+  "#,
+
+  regex_captures (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |intrinsic_type List<A> where A :: Any
+    |intrinsic_type Regex<C> where C :: Any
+    |
+    |intrinsic_def List<A> .. get Int -> A
+    |intrinsic_def Regex<C> .. captures String -> List<C>
+    |
+    |let date = /<year: digit{4}> "-" <month: digit{2}> /
+    |let found = date..captures "2020-10"
+    |let (year: year, month: month) = found..get 0
+    |
+    |date :: Regex<(year :: String, month :: String)>
+    |month :: String
+  "#,
+
+  regex_undefined_character_class (false): r#"
+    |let r = /"a" vowel "b"/
+  "#,
+
+  regex_duplicate_capture_name (false): r#"
+    |let r = /<word: letter> " " <word: letter> /
+  "#,
+
+  sized_numbers (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type UInt8
+    |intrinsic_type Float32
+    |
+    |intrinsic_def UInt8 + UInt8 -> UInt8
+    |intrinsic_def Int .. to_uint8 () -> UInt8
+    |
+    |let one = 1..to_uint8 ()
+    |let header = 0x7fu8 + one
+    |let scale = 0.5f32
+    |
+    |header :: Byte
+    |scale :: Float32
+  "#,
+
+  sized_numbers_mismatched (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type UInt8
+    |
+    |intrinsic_def UInt8 + UInt8 -> UInt8
+    |
+    |let total = 255u8 + 1
+  "#,
+
+  extern_def (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type Int32
+    |intrinsic_type Pointer
+    |intrinsic_type String
+    |
+    |extern def puts String -> Int32
+    |extern def getenv String -> String
+    |extern def malloc Int -> Pointer
+    |extern def abort ()
+    |
+    |let status = puts "hello"
+    |let home = getenv "HOME"
+    |let buffer = malloc 16
+    |
+    |status :: Int32
+    |home :: String
+    |buffer :: Pointer
+  "#,
+
+  extern_def_unsupported_type (false): r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |extern def paint Color -> ()
+  "#,
+
+  loop_with_break (true): r#"
+    |intrinsic_type Int
+    |intrinsic_def Int + Int -> Int
+    |
+    |enum LoopResult | LoopBreak | LoopContinue
+    |
+    |intrinsic_def loop { () -> LoopResult } -> ()
+    |
+    |let count = 0
+    |
+    |loop {
+    |  count = count + 1
+    |
+    |  match count
+    |    | 10 => break
+    |    | _ => LoopContinue
+    |}
+  "#,
+
+  block_return_early (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |def describe Int -> String {
+    |  |n|
+    |  let description = "not zero"
+    |
+    |  match n
+    |    | 0 => $self..return "zero"
+    |    | _ => description
+    |}
+    |
+    |let answer = { $self..return 47 }
+    |
+    |answer :: { () -> Int }
+  "#,
+
+  block_return_type_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |def describe Int -> String {
+    |  $self..return 47
+    |}
+  "#,
+
+  break_outside_loop (false): r#"
+    |enum LoopResult | LoopBreak | LoopContinue
+    |
+    |def stop () {
+    |  break
+    |}
+  "#,
+
+  break_in_block_inside_loop (false): r#"
+    |enum LoopResult | LoopBreak | LoopContinue
+    |
+    |intrinsic_def loop { () -> LoopResult } -> ()
+    |
+    |loop {
+    |  let stop = { break }
+    |  stop ()
+    |}
+  "#,
+
+  generic_def_inferred (true): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Bool | True | False
+    |
+    |def if Bool then { () -> A } else { () -> A } -> A where A :: Any {
+    |  |cond, then, else|
+    |  match cond
+    |    | True => then ()
+    |    | False => else ()
+    |}
+    |
+    |let number = if True then { 1 } else { 2 }
+    |let text = if False then { "yes" } else { "no" }
+    |
+    |number :: Int
+    |text :: String
+  "#,
+
+  generic_def_mismatch (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Bool | True | False
+    |
+    |def if Bool then { () -> A } else { () -> A } -> A where A :: Any {
+    |  |cond, then, else|
+    |  match cond
+    |    | True => then ()
+    |    | False => else ()
+    |}
+    |
+    |let mixed = if True then { 1 } else { "two" }
+  "#,
+
+  block_params_inferred (true): r#"
+    |intrinsic_type Int
+    |
+    |let twice = { |f| f (f 1) }
+    |
+    |twice :: { { Int -> Int } -> Int }
+  "#,
+
+  block_params_inferred_from_other_args (true): r#"
+    |intrinsic_type Int
+    |
+    |intrinsic_def Int + Int -> Int
+    |
+    |def apply { A -> A } to A -> A where A :: Any {
+    |  |f, value|
+    |  f value
+    |}
+    |
+    |let number = apply { |n| n + 1 } to 41
+    |
+    |number :: Int
+  "#,
+
+  unsolved_type (false): r#"
+    |enum Maybe<A> where A :: Any
+    |  | Some A
+    |  | None
+    |
+    |let nothing = None
+  "#,
+
+  trait_satisfied (true): r#"
+    |intrinsic_type String
+    |
+    |trait Describe
+    |  . name :: String
+    |  .. describe () -> String
+    |
+    |struct Person (name :: String)
+    |
+    |intrinsic_def Person .. describe () -> String
+    |
+    |def describe A -> String where A :: Describe {
+    |  |thing|
+    |  thing.describe()
+    |}
+    |
+    |let description = describe (Person (name: "Reid"))
+  "#,
+
+  trait_not_satisfied (false): r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |trait Describe
+    |  . name :: String
+    |  .. describe () -> String
+    |
+    |struct Point (x :: Int, y :: Int)
+    |
+    |def describe A -> String where A :: Describe {
+    |  |thing|
+    |  thing.describe()
+    |}
+    |
+    |let description = describe (Point (x: 1, y: 2))
+  "#,
+
+  trait_satisfied_by_constrained_param (true): r#"
+    |intrinsic_type String
+    |
+    |trait Named
+    |  . name :: String
+    |
+    |trait Describe
+    |  .. describe () -> String
+    |
+    |struct Person (name :: String)
+    |
+    |intrinsic_def Person .. describe () -> String
+    |
+    |def describe A -> String where A :: Describe {
+    |  |thing|
+    |  thing.describe()
+    |}
+    |
+    |def describe_named B -> String where B :: Describe, B :: Named {
+    |  |thing|
+    |  describe thing
+    |}
+    |
+    |let description = describe_named (Person (name: "Reid"))
+  "#,
+
+  trait_not_satisfied_by_constrained_param (false): r#"
+    |intrinsic_type String
+    |
+    |trait Named
+    |  . name :: String
+    |
+    |trait Describe
+    |  .. describe () -> String
+    |
+    |def describe A -> String where A :: Describe {
+    |  |thing|
+    |  thing.describe()
+    |}
+    |
+    |def describe_named B -> String where B :: Named {
+    |  |thing|
+    |  describe thing
+    |}
+  "#,
+
+  field_access_undefined_receiver (false): r#"
+    |let name = nope.name
+  "#,
+
+  field_access_inferred_receiver (false): r#"
+    |let get_name = { |p| p.name }
+  "#,
+
+  field_access_unlabeled_tuple (false): r#"
+    |intrinsic_type Int
+    |
+    |let pair = (1, 2)
+    |let name = pair.name
+  "#,
+
+  underscore_outside_pattern (false): r#"
+    |let nothing = _
+  "#,
 }
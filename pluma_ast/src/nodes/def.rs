@@ -1,6 +1,7 @@
 use super::*;
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct DefNode {
   pub pos: Position,
@@ -20,6 +21,18 @@ pub struct IntrinsicDefNode {
   pub generic_type_constraints: GenericTypeConstraints,
 }
 
+/// A function implemented in C, e.g. `extern def puts String -> Int32`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ExternDefNode {
+  pub pos: Position,
+  pub visibility: ExportVisibility,
+  pub kind: DefKind,
+  pub return_type: Option<TypeExprNode>,
+  /// The C symbol to call, if it's different from the def's name
+  pub link_name: Option<String>,
+}
+
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum DefKind {
   // def hi(A, B) -> Ret { ... }
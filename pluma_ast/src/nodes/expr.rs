@@ -2,6 +2,7 @@ use super::*;
 use crate::common::*;
 use crate::value_type::ValueType;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct ExprNode {
   pub pos: Position,
@@ -9,6 +10,26 @@ pub struct ExprNode {
   pub typ: ValueType,
 }
 
+impl ExprNode {
+  /// Whether this is `$self..return`, which is called to return early
+  /// from the block that it's in.
+  pub fn is_block_return(&self) -> bool {
+    match &self.kind {
+      ExprKind::MethodAccess {
+        receiver,
+        method_parts,
+      } => {
+        matches!(&receiver.kind, ExprKind::Identifier(ident) if ident.name == "$self")
+          && method_parts.len() == 1
+          && method_parts[0].name == "return"
+      }
+
+      _ => false,
+    }
+  }
+}
+
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum ExprKind {
   Assignment {
@@ -21,6 +42,7 @@ pub enum ExprKind {
     right: Box<ExprNode>,
   },
   Block(BlockNode),
+  Break,
   Call(CallNode),
   Dict(Vec<(ExprNode, ExprNode)>),
   EmptyTuple,
@@ -1,17 +1,25 @@
 use crate::common::*;
+use crate::value_type::SizedType;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct LiteralNode {
   pub pos: Position,
   pub kind: LiteralKind,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum LiteralKind {
   FloatDecimal(f64),
-  IntDecimal(i32),
-  IntOctal(i32),
-  IntHex(i32),
-  IntBinary(i32),
+  IntDecimal(i64),
+  IntOctal(i64),
+  IntHex(i64),
+  IntBinary(i64),
   Str(String),
+  /// An integer with a type suffix, like `255u8`. Its value is in range
+  /// for that type.
+  SizedInt(u64, SizedType),
+  /// A number with a float type suffix, like `1.5f32`.
+  SizedFloat(f64, SizedType),
 }
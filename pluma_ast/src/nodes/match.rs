@@ -1,6 +1,7 @@
 use super::*;
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct MatchNode {
   pub pos: Position,
@@ -1,5 +1,6 @@
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct OperatorNode {
   pub pos: Position,
@@ -1,12 +1,14 @@
 use super::*;
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct PatternNode {
   pub pos: Position,
   pub kind: PatternKind,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum PatternKind {
   // e.g. let x = / let mut x
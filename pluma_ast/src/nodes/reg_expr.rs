@@ -1,11 +1,13 @@
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct RegExprNode {
   pub pos: Position,
   pub kind: RegExprKind,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum RegExprKind {
   Literal(String),
@@ -1,12 +1,14 @@
 use super::*;
 use crate::common::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct StatementNode {
   pub pos: Position,
   pub kind: StatementKind,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum StatementKind {
   Let(LetNode),
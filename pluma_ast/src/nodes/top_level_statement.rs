@@ -15,6 +15,7 @@ pub enum TopLevelStatementKind {
   IntrinsicTypeDef(IntrinsicTypeDefNode),
   Def(DefNode),
   IntrinsicDef(IntrinsicDefNode),
+  ExternDef(ExternDefNode),
   Expr(ExprNode),
   VisibilityMarker(ExportVisibility),
 }
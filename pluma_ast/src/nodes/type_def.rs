@@ -14,7 +14,7 @@ pub struct TypeDefNode {
 pub struct IntrinsicTypeDefNode {
   pub pos: Position,
   pub visibility: ExportVisibility,
-  pub name: IdentifierNode,
+  pub name: TypeIdentifierNode,
   pub generic_type_constraints: GenericTypeConstraints,
 }
 
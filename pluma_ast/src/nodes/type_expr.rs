@@ -2,6 +2,7 @@ use super::*;
 use crate::common::*;
 use crate::value_type::ValueType;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct TypeExprNode {
   pub pos: Position,
@@ -9,6 +10,7 @@ pub struct TypeExprNode {
   pub typ: ValueType,
 }
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum TypeExprKind {
   // e.g. String or Dict<Int, String>
@@ -2,6 +2,7 @@ use super::*;
 use crate::common::*;
 use crate::value_type::*;
 
+#[derive(Clone)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct TypeIdentifierNode {
   pub pos: Position,
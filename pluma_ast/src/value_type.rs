@@ -6,16 +6,43 @@ pub enum ValueType {
   Int,
   Float,
   String,
+  Sized(SizedType),
+  /// An opaque pointer, which can only be passed to and from C.
+  Pointer,
   Named(String),
   Generic(String, Vec<ValueType>),
   Func(Vec<ValueType>, Box<ValueType>),
   UnlabeledTuple(Vec<ValueType>),
   LabeledTuple(Vec<(String, ValueType)>),
-  Constrained(TypeConstraint),
+  /// A def's generic param, e.g. the `A` in `where A :: Any`, which stands
+  /// for any type that satisfies its constraint.
+  Constrained(String, TypeConstraint),
+  /// A type that's being inferred, which the analyzer's unifier solves.
+  Variable(usize),
   Nothing,
+  /// The type of expressions that never produce a value, because they
+  /// jump somewhere else (e.g. `break`). It's compatible with every type.
+  Never,
   Unknown,
 }
 
+/// A fixed-width numeric type, for talking to C libraries and binary
+/// formats. `Int` is 64 bits wide too, but it's a distinct type from
+/// `Int64`, so converting between them is explicit.
+#[derive(Copy, Clone, PartialEq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum SizedType {
+  Int8,
+  Int16,
+  Int32,
+  Int64,
+  UInt8,
+  UInt16,
+  UInt32,
+  UInt64,
+  Float32,
+}
+
 #[derive(Clone, PartialEq, Hash)]
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum TypeConstraint {
@@ -43,6 +70,98 @@ impl ValueType {
   }
 }
 
+impl SizedType {
+  /// Looks up a sized type by name. `Byte` is another name for `UInt8`.
+  pub fn from_name(name: &str) -> Option<SizedType> {
+    match name {
+      "Int8" => Some(SizedType::Int8),
+      "Int16" => Some(SizedType::Int16),
+      "Int32" => Some(SizedType::Int32),
+      "Int64" => Some(SizedType::Int64),
+      "UInt8" | "Byte" => Some(SizedType::UInt8),
+      "UInt16" => Some(SizedType::UInt16),
+      "UInt32" => Some(SizedType::UInt32),
+      "UInt64" => Some(SizedType::UInt64),
+      "Float32" => Some(SizedType::Float32),
+      _ => None,
+    }
+  }
+
+  /// Looks up a sized type by the suffix that gives a numeric literal that
+  /// type, e.g. the `u8` in `255u8`.
+  pub fn from_suffix(suffix: &str) -> Option<SizedType> {
+    match suffix {
+      "i8" => Some(SizedType::Int8),
+      "i16" => Some(SizedType::Int16),
+      "i32" => Some(SizedType::Int32),
+      "i64" => Some(SizedType::Int64),
+      "u8" => Some(SizedType::UInt8),
+      "u16" => Some(SizedType::UInt16),
+      "u32" => Some(SizedType::UInt32),
+      "u64" => Some(SizedType::UInt64),
+      "f32" => Some(SizedType::Float32),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      SizedType::Int8 => "Int8",
+      SizedType::Int16 => "Int16",
+      SizedType::Int32 => "Int32",
+      SizedType::Int64 => "Int64",
+      SizedType::UInt8 => "UInt8",
+      SizedType::UInt16 => "UInt16",
+      SizedType::UInt32 => "UInt32",
+      SizedType::UInt64 => "UInt64",
+      SizedType::Float32 => "Float32",
+    }
+  }
+
+  pub fn suffix(self) -> &'static str {
+    match self {
+      SizedType::Int8 => "i8",
+      SizedType::Int16 => "i16",
+      SizedType::Int32 => "i32",
+      SizedType::Int64 => "i64",
+      SizedType::UInt8 => "u8",
+      SizedType::UInt16 => "u16",
+      SizedType::UInt32 => "u32",
+      SizedType::UInt64 => "u64",
+      SizedType::Float32 => "f32",
+    }
+  }
+
+  pub fn bits(self) -> u32 {
+    match self {
+      SizedType::Int8 | SizedType::UInt8 => 8,
+      SizedType::Int16 | SizedType::UInt16 => 16,
+      SizedType::Int32 | SizedType::UInt32 | SizedType::Float32 => 32,
+      SizedType::Int64 | SizedType::UInt64 => 64,
+    }
+  }
+
+  pub fn is_float(self) -> bool {
+    self == SizedType::Float32
+  }
+
+  pub fn is_signed(self) -> bool {
+    matches!(
+      self,
+      SizedType::Int8 | SizedType::Int16 | SizedType::Int32 | SizedType::Int64
+    )
+  }
+
+  /// The largest integer a literal of this type can hold.
+  pub fn max_int(self) -> u64 {
+    match self {
+      SizedType::Float32 => unreachable!(),
+      _ if self.is_signed() => (1 << (self.bits() - 1)) - 1,
+      _ => u64::MAX >> (64 - self.bits()),
+    }
+  }
+}
+
 impl std::cmp::Eq for ValueType {}
 
 impl fmt::Display for ValueType {
@@ -52,12 +171,18 @@ impl fmt::Display for ValueType {
 
       ValueType::Nothing => write!(f, "()"),
 
+      ValueType::Never => write!(f, "Never"),
+
       ValueType::Int => write!(f, "Int"),
 
       ValueType::Float => write!(f, "Float"),
 
       ValueType::String => write!(f, "String"),
 
+      ValueType::Sized(sized_type) => write!(f, "{}", sized_type.name()),
+
+      ValueType::Pointer => write!(f, "Pointer"),
+
       ValueType::Named(name) => write!(f, "{}", name),
 
       ValueType::Generic(name, generic_params) => write!(
@@ -102,42 +227,50 @@ impl fmt::Display for ValueType {
         return_type,
       ),
 
-      ValueType::Constrained(constraint) => match constraint {
-        TypeConstraint::NamedTrait(name) => write!(f, "{}", name),
-
-        TypeConstraint::GenericTrait(name, generic_params) => write!(
-          f,
-          "{}<{}>",
-          name,
-          generic_params
-            .iter()
-            .map(|p| format!("{}", p))
-            .collect::<Vec<String>>()
-            .join(", ")
-        ),
-
-        TypeConstraint::InlineTrait { fields, methods } => {
-          write!(f, "(")?;
-
-          for (field_name, field_type) in fields {
-            write!(f, ". {} :: {}, ", field_name, field_type)?;
-          }
+      ValueType::Constrained(name, _) => write!(f, "{}", name),
 
-          for (method_parts, return_type) in methods {
-            write!(f, ". ")?;
+      ValueType::Variable(id) => write!(f, "?{}", id),
+    }
+  }
+}
 
-            for (part_name, part_param_type) in method_parts {
-              write!(f, "{} {} ", part_name, part_param_type)?;
-            }
+impl fmt::Display for TypeConstraint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    match self {
+      TypeConstraint::NamedTrait(name) => write!(f, "{}", name),
 
-            write!(f, "-> {}, ", return_type)?;
-          }
+      TypeConstraint::GenericTrait(name, generic_params) => write!(
+        f,
+        "{}<{}>",
+        name,
+        generic_params
+          .iter()
+          .map(|p| format!("{}", p))
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
 
-          write!(f, ")")?;
+      TypeConstraint::InlineTrait { fields, methods } => {
+        write!(f, "(")?;
 
-          Ok(())
+        for (field_name, field_type) in fields {
+          write!(f, ". {} :: {}, ", field_name, field_type)?;
         }
-      },
+
+        for (method_parts, return_type) in methods {
+          write!(f, ". ")?;
+
+          for (part_name, part_param_type) in method_parts {
+            write!(f, "{} {} ", part_name, part_param_type)?;
+          }
+
+          write!(f, "-> {}, ", return_type)?;
+        }
+
+        write!(f, ")")?;
+
+        Ok(())
+      }
     }
   }
 }
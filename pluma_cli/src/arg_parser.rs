@@ -32,7 +32,6 @@ impl ParsedArgs {
     }
   }
 
-  #[allow(dead_code)]
   pub fn get_flag_values(&self, flag: &'static str) -> Vec<String> {
     match self.multi_value_flags.get(flag) {
       Some(vals) => vals.to_vec(),
@@ -99,7 +98,15 @@ pub fn parse_args_for_command(
     if arg.starts_with("-") {
       let is_long_flag = arg.len() > 1 && arg.bytes().nth(1).unwrap() == b'-';
       let name_start = if is_long_flag { 2 } else { 1 };
-      let flag_name = arg[name_start..].to_owned();
+
+      // Long flags may carry their value inline, e.g. `--emit=asm`
+      let (flag_name, inline_value) = match arg.find('=') {
+        Some(eq_index) if is_long_flag => (
+          arg[name_start..eq_index].to_owned(),
+          Some(arg[eq_index + 1..].to_owned()),
+        ),
+        _ => (arg[name_start..].to_owned(), None),
+      };
 
       let allowed_flag = allowed_flags.get(&flag_name[..]).or_else(|| {
         match allowed_flag_aliases.get(&flag_name[..]) {
@@ -119,6 +126,13 @@ pub fn parse_args_for_command(
       let flag_name = flag_info.long_name.to_owned();
 
       if flag_info.style == FlagStyle::Boolean {
+        if let Some(value) = inline_value {
+          return Err(CommandError {
+            command: cmd.name.to_owned(),
+            kind: CommandErrorKind::InvalidValueForFlag(flag_name, value),
+          });
+        }
+
         if parsed.bool_flags.contains(&flag_name) {
           return Err(CommandError {
             command: cmd.name.to_owned(),
@@ -131,16 +145,22 @@ pub fn parse_args_for_command(
         continue;
       }
 
-      let next_value = args.get(i + 1);
+      let (next_value, args_consumed) = match inline_value {
+        Some(value) => (value, 1),
 
-      if next_value.is_none() || next_value.unwrap().starts_with("-") {
-        return Err(CommandError {
-          command: cmd.name.to_owned(),
-          kind: CommandErrorKind::MissingValueForFlag(flag_name),
-        });
-      }
+        None => {
+          let next_value = args.get(i + 1);
 
-      let next_value = next_value.unwrap().clone();
+          if next_value.is_none() || next_value.unwrap().starts_with("-") {
+            return Err(CommandError {
+              command: cmd.name.to_owned(),
+              kind: CommandErrorKind::MissingValueForFlag(flag_name),
+            });
+          }
+
+          (next_value.unwrap().clone(), 2)
+        }
+      };
 
       if flag_info.style == FlagStyle::SingleValue {
         if parsed.single_value_flags.contains_key(&flag_name) {
@@ -161,7 +181,7 @@ pub fn parse_args_for_command(
           .single_value_flags
           .insert(flag_name, next_value.clone());
 
-        i += 2;
+        i += args_consumed;
         continue;
       }
 
@@ -174,7 +194,7 @@ pub fn parse_args_for_command(
             .insert(flag_name, vec![next_value.clone()]);
         }
 
-        i += 2;
+        i += args_consumed;
         continue;
       }
 
@@ -261,4 +281,28 @@ mod tests {
       vec!["lol".to_owned(), "wow".to_owned()]
     );
   }
+
+  #[test]
+  fn inline_flag_values() {
+    let cmd = CommandInfo::new("testcmd", "just for testing").flags(vec![
+      Flag::new("emit")
+        .single_value()
+        .possible_values(vec!["asm", "obj"]),
+      Flag::with_names("name", "n").multiple_values(),
+    ]);
+
+    let args = vec![
+      "--emit=asm".to_owned(),
+      "--name=lol".to_owned(),
+      "-n".to_owned(),
+      "wow".to_owned(),
+    ];
+    let parsed = parse_args_for_command(args, cmd).expect("should be valid");
+
+    assert_eq!(parsed.get_flag_value("emit").unwrap(), "asm".to_owned());
+    assert_eq!(
+      parsed.get_flag_values("name"),
+      vec!["lol".to_owned(), "wow".to_owned()]
+    );
+  }
 }
@@ -75,6 +75,18 @@ pub struct Flag {
 }
 
 impl Flag {
+  pub fn new(long_name: &'static str) -> Self {
+    Flag {
+      long_name,
+      short_name: None,
+      description: "",
+      value_name: None,
+      default: None,
+      possible_values: None,
+      style: FlagStyle::Boolean,
+    }
+  }
+
   pub fn with_names(long_name: &'static str, short_name: &'static str) -> Self {
     Flag {
       long_name,
@@ -112,7 +124,6 @@ impl Flag {
     self
   }
 
-  #[allow(dead_code)]
   pub fn multiple_values(mut self) -> Self {
     self.style = FlagStyle::MultipleValues;
     self
@@ -203,8 +214,9 @@ impl fmt::Display for CommandInfo {
       for flag in flags {
         write!(f, "\n  ")?;
 
-        if let Some(name) = flag.short_name {
-          write!(f, "-{}, ", name)?;
+        match flag.short_name {
+          Some(name) => write!(f, "-{}, ", name)?,
+          None => write!(f, "    ")?,
         }
 
         write!(
@@ -26,6 +26,52 @@ impl Command for BuildCommand {
           .value_name("path")
           .possible_values(vec!["release", "debug"])
           .default("debug"),
+        Flag::new("opt-level")
+          .description("Optimization level (default: 0 in debug mode, 2 in release mode)")
+          .single_value()
+          .value_name("level")
+          .possible_values(vec!["0", "1", "2", "3", "s", "z"]),
+        Flag::new("dump-ir")
+          .description("Directory to write LLVM IR to, before and after optimization")
+          .single_value()
+          .value_name("dir"),
+        Flag::new("debug-info")
+          .description("Whether to emit debug info (default: on in debug mode)")
+          .single_value()
+          .value_name("setting")
+          .possible_values(vec!["on", "off"]),
+        Flag::new("emit")
+          .description("Kind of output to write")
+          .single_value()
+          .value_name("kind")
+          .possible_values(vec!["llvm-ir", "llvm-bc", "asm", "obj", "exe"])
+          .default("exe"),
+        Flag::new("linker")
+          .description("System linker used to produce executables")
+          .single_value()
+          .value_name("program")
+          .default(DEFAULT_LINKER),
+        Flag::new("link-lib")
+          .description("Library to link against (e.g. m for libm)")
+          .multiple_values()
+          .value_name("name"),
+        Flag::new("link-search")
+          .description("Directory to search for libraries to link against")
+          .multiple_values()
+          .value_name("dir"),
+        Flag::new("target")
+          .description("Target triple to compile for (default: host)")
+          .single_value()
+          .value_name("triple"),
+        Flag::new("target-cpu")
+          .description("Target CPU to compile for")
+          .single_value()
+          .value_name("cpu"),
+        Flag::new("target-features")
+          .description("Comma-separated list of target features (e.g. +avx2,-sse4.1)")
+          .single_value()
+          .value_name("features"),
+        Flag::new("no-prelude").description("Don't import the prelude into every module"),
       ])
       .with_help()
   }
@@ -39,8 +85,37 @@ impl Command for BuildCommand {
         Some(val) if val == "release" => CompilerMode::Release,
         _ => CompilerMode::Debug,
       },
+      opt_level: match args.get_flag_value("opt-level") {
+        Some(val) if val == "0" => Some(OptLevel::O0),
+        Some(val) if val == "1" => Some(OptLevel::O1),
+        Some(val) if val == "2" => Some(OptLevel::O2),
+        Some(val) if val == "3" => Some(OptLevel::O3),
+        Some(val) if val == "s" => Some(OptLevel::Os),
+        Some(val) if val == "z" => Some(OptLevel::Oz),
+        _ => None,
+      },
+      dump_ir_dir: args.get_flag_value("dump-ir"),
+      debug_info: match args.get_flag_value("debug-info") {
+        Some(val) if val == "on" => Some(true),
+        Some(val) if val == "off" => Some(false),
+        _ => None,
+      },
       output_path: args.get_flag_value("out"),
+      emit_kind: match args.get_flag_value("emit") {
+        Some(val) if val == "llvm-ir" => EmitKind::LlvmIr,
+        Some(val) if val == "llvm-bc" => EmitKind::LlvmBc,
+        Some(val) if val == "asm" => EmitKind::Asm,
+        Some(val) if val == "obj" => EmitKind::Obj,
+        _ => EmitKind::Exe,
+      },
+      linker: args.get_flag_value("linker"),
+      link_libs: args.get_flag_values("link-lib"),
+      link_search_paths: args.get_flag_values("link-search"),
       collect_comments: false,
+      implicit_prelude: !args.is_flag_present("no-prelude"),
+      target_triple: args.get_flag_value("target"),
+      target_cpu: args.get_flag_value("target-cpu"),
+      target_features: args.get_flag_value("target-features"),
     };
 
     let mut compiler = match Compiler::from_options(compiler_options) {
@@ -16,7 +16,8 @@ impl Command for CheckCommand {
         Arg::new("entry", "Path to Pluma module or directory").default(DEFAULT_ENTRY_FILE)
       ])
       .flags(vec![
-        Flag::with_names("parse-only", "p").description("Skip type-checking of input files")
+        Flag::with_names("parse-only", "p").description("Skip type-checking of input files"),
+        Flag::new("no-prelude").description("Don't import the prelude into every module"),
       ])
       .with_help()
   }
@@ -27,8 +28,19 @@ impl Command for CheckCommand {
         .get_positional_arg(0)
         .unwrap_or(DEFAULT_ENTRY_FILE.to_owned()),
       mode: CompilerMode::Debug,
+      opt_level: None,
+      dump_ir_dir: None,
+      debug_info: None,
       output_path: None,
+      emit_kind: EmitKind::Exe,
+      linker: None,
+      link_libs: Vec::new(),
+      link_search_paths: Vec::new(),
       collect_comments: false,
+      implicit_prelude: !args.is_flag_present("no-prelude"),
+      target_triple: None,
+      target_cpu: None,
+      target_features: None,
     };
 
     let mut compiler = match Compiler::from_options(compiler_options) {
@@ -31,8 +31,19 @@ impl Command for DocCommand {
         .get_positional_arg(0)
         .unwrap_or(DEFAULT_ENTRY_FILE.to_owned()),
       mode: CompilerMode::Debug,
+      opt_level: None,
+      dump_ir_dir: None,
+      debug_info: None,
       output_path: None,
+      emit_kind: EmitKind::Exe,
+      linker: None,
+      link_libs: Vec::new(),
+      link_search_paths: Vec::new(),
       collect_comments: true,
+      implicit_prelude: true,
+      target_triple: None,
+      target_cpu: None,
+      target_features: None,
     };
 
     let mut compiler = match Compiler::from_options(compiler_options) {
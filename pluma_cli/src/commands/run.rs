@@ -15,12 +15,44 @@ impl Command for RunCommand {
       .args(vec![
         Arg::new("entry", "Path to Pluma module or directory").default(DEFAULT_ENTRY_FILE)
       ])
-      .flags(vec![Flag::with_names("mode", "m")
-        .description("Optimization mode")
-        .single_value()
-        .value_name("path")
-        .possible_values(vec!["release", "debug"])
-        .default("debug")])
+      .flags(vec![
+        Flag::with_names("mode", "m")
+          .description("Optimization mode")
+          .single_value()
+          .value_name("path")
+          .possible_values(vec!["release", "debug"])
+          .default("debug"),
+        Flag::new("opt-level")
+          .description("Optimization level (default: 0 in debug mode, 2 in release mode)")
+          .single_value()
+          .value_name("level")
+          .possible_values(vec!["0", "1", "2", "3", "s", "z"]),
+        Flag::new("dump-ir")
+          .description("Directory to write LLVM IR to, before and after optimization")
+          .single_value()
+          .value_name("dir"),
+        Flag::new("link-lib")
+          .description("Shared library to load before running (e.g. m for libm)")
+          .multiple_values()
+          .value_name("name"),
+        Flag::new("link-search")
+          .description("Directory to search for libraries to load")
+          .multiple_values()
+          .value_name("dir"),
+        Flag::new("target")
+          .description("Target triple to compile for, which must be the host's (default: host)")
+          .single_value()
+          .value_name("triple"),
+        Flag::new("target-cpu")
+          .description("Target CPU to compile for")
+          .single_value()
+          .value_name("cpu"),
+        Flag::new("target-features")
+          .description("Comma-separated list of target features (e.g. +avx2,-sse4.1)")
+          .single_value()
+          .value_name("features"),
+        Flag::new("no-prelude").description("Don't import the prelude into every module"),
+      ])
       .with_help()
   }
 
@@ -33,8 +65,27 @@ impl Command for RunCommand {
         Some(val) if val == "release" => CompilerMode::Release,
         _ => CompilerMode::Debug,
       },
+      opt_level: match args.get_flag_value("opt-level") {
+        Some(val) if val == "0" => Some(OptLevel::O0),
+        Some(val) if val == "1" => Some(OptLevel::O1),
+        Some(val) if val == "2" => Some(OptLevel::O2),
+        Some(val) if val == "3" => Some(OptLevel::O3),
+        Some(val) if val == "s" => Some(OptLevel::Os),
+        Some(val) if val == "z" => Some(OptLevel::Oz),
+        _ => None,
+      },
+      dump_ir_dir: args.get_flag_value("dump-ir"),
+      debug_info: Some(false),
       output_path: None,
+      emit_kind: EmitKind::Exe,
+      linker: None,
+      link_libs: args.get_flag_values("link-lib"),
+      link_search_paths: args.get_flag_values("link-search"),
       collect_comments: false,
+      implicit_prelude: !args.is_flag_present("no-prelude"),
+      target_triple: args.get_flag_value("target"),
+      target_cpu: args.get_flag_value("target-cpu"),
+      target_features: args.get_flag_value("target-features"),
     };
 
     let mut compiler = match Compiler::from_options(options) {
@@ -58,8 +58,6 @@ pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
     }
 
     if let Some((start, end)) = diagnostic.pos {
-      let mut col_index = 0;
-
       let bytes = match module_bytes.get(&module_path) {
         Some(bytes) => bytes,
         None => match fs::read(&module_path) {
@@ -71,39 +69,11 @@ pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
         },
       };
 
-      let mut frame_start = start;
-      let mut frame_end = end;
-
-      while frame_start > 0 {
-        if let Some(b'\n') = bytes.get(frame_start - 1) {
-          break;
-        }
-
-        col_index += 1;
-        frame_start -= 1
-      }
-
-      while let Some(byte) = bytes.get(frame_end) {
-        match byte {
-          b'\n' => break,
-          _ => frame_end += 1,
-        }
-      }
-
-      let frame = String::from_utf8(bytes[frame_start..frame_end].to_vec())
-        .unwrap()
-        .replace("\n", " ");
-
-      let mut line = 1;
-
-      frame_start = start;
-      while frame_start > 0 {
-        if let Some(b'\n') = bytes.get(frame_start - 1) {
-          line += 1;
-        }
-
-        frame_start -= 1;
-      }
+      let SourceFrame {
+        line,
+        column,
+        text: frame,
+      } = SourceFrame::new(bytes, (start, end));
 
       eprintln!(
         "\n{} {} {}",
@@ -121,7 +91,7 @@ pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
 
       eprintln!(
         "{}{}",
-        " ".repeat(prefix_width + col_index),
+        " ".repeat(prefix_width + column - 1),
         if is_error {
           colors::bold_red(&up_arrows)
         } else {
@@ -131,15 +101,7 @@ pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
 
       eprintln!(
         "{}",
-        colors::dim(
-          format!(
-            "{}:{}:{}",
-            module_path.to_str().unwrap(),
-            line,
-            col_index + 1
-          )
-          .as_str()
-        )
+        colors::dim(format!("{}:{}:{}", module_path.to_str().unwrap(), line, column).as_str())
       );
     }
 
@@ -46,3 +46,139 @@ fn integration_run_command_version() {
   assert_eq!(stderr, "".to_owned());
   assert_eq!(status, 0);
 }
+
+#[test]
+fn integration_run_command_with_prelude() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/prelude_print.pa"]);
+
+  assert_eq!(stdout, "hello from main's module\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_generic_def() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/generic_def.pa"]);
+
+  assert_eq!(stdout, "no\n-5\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_generic_enum() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/generic_enum.pa"]);
+
+  assert_eq!(stdout, "5\n7\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_inferred_generic_def() {
+  let (status, stdout, stderr) =
+    run_command!("pluma", &["run", "tests/fixtures/inferred_generic_def.pa"]);
+
+  assert_eq!(stdout, "42\nhi!\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_two_modules_same_def() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/two_bars.pa"]);
+
+  assert_eq!(stdout, "bar from main\nbar from other\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_only_entry_main() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/imported_main.pa"]);
+
+  assert_eq!(
+    stdout,
+    "top-level code from other\nmain from entry\n".to_owned()
+  );
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_after_loop_break() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/loop_break.pa"]);
+
+  assert_eq!(stdout, "3\nafter the loop\n".to_owned());
+  assert_eq!(stderr, "".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_std_io() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/std_io.pa"]);
+
+  assert_eq!(stdout, "hi\n".to_owned());
+  assert_eq!(stderr, "oops\n".to_owned());
+  assert_eq!(status, 0);
+}
+
+#[test]
+fn integration_run_command_main_returns_err() {
+  let (status, stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/main_err.pa"]);
+
+  assert_eq!(stdout, "".to_owned());
+  assert_eq!(stderr, "Error: something went wrong\n".to_owned());
+  assert_eq!(status, 1);
+}
+
+#[test]
+fn integration_run_command_release_division() {
+  let (status, stdout, stderr) = run_command!(
+    "pluma",
+    &[
+      "run",
+      "--mode",
+      "release",
+      "tests/fixtures/divide_by_zero.pa"
+    ]
+  );
+
+  assert_eq!(stdout, "0\n".to_owned());
+  assert!(stderr.starts_with("Error: division by zero\n"));
+  assert_eq!(status, 101);
+}
+
+#[test]
+fn integration_run_command_negate_overflow() {
+  let (status, stdout, stderr) =
+    run_command!("pluma", &["run", "tests/fixtures/negate_overflow.pa"]);
+
+  assert_eq!(stdout, "5\n".to_owned());
+  assert!(stderr.starts_with("Error: integer overflow\n"));
+  assert_eq!(status, 101);
+}
+
+#[test]
+fn integration_run_command_def_as_value() {
+  let (status, _stdout, stderr) = run_command!("pluma", &["run", "tests/fixtures/def_as_value.pa"]);
+
+  assert!(stderr.contains("Cannot use 'greet' as a value"));
+  assert_ne!(status, 0);
+}
+
+#[test]
+fn integration_run_command_missing_link_lib() {
+  let (status, _stdout, stderr) = run_command!(
+    "pluma",
+    &[
+      "run",
+      "--link-lib",
+      "pluma_missing_lib",
+      "tests/fixtures/prelude_print.pa"
+    ]
+  );
+
+  assert!(stderr.contains("Could not load library 'pluma_missing_lib'"));
+  assert_ne!(status, 0);
+}
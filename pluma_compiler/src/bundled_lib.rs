@@ -0,0 +1,46 @@
+use pluma_constants::*;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// The modules in pluma_lib are compiled into the binary, so that the
+// standard library is available wherever the compiler is installed.
+// Setting PLUMA_LIB to a pluma_lib directory reads its modules instead,
+// e.g. to work on the standard library without rebuilding the compiler.
+const BUNDLED_MODULES: &[(&str, &str)] = &[
+  ("std/ctrl", include_str!("../../pluma_lib/std/ctrl.pa")),
+  ("std/io", include_str!("../../pluma_lib/std/io.pa")),
+  (
+    "std/prelude",
+    include_str!("../../pluma_lib/std/prelude.pa"),
+  ),
+];
+
+/// Whether a module name (e.g. `std/io`) refers to the standard library
+/// rather than to a file next to the entry module.
+pub fn is_lib_module(module_name: &str) -> bool {
+  module_name.starts_with(STD_MODULE_PREFIX)
+}
+
+/// The path that a library module is shown with in diagnostics and debug
+/// info. Bundled modules are shown as if they were read from pluma_lib.
+pub fn lib_module_path(module_name: &str) -> PathBuf {
+  let lib_dir = match env::var_os(LIB_DIR_ENV_VAR) {
+    Some(lib_dir) => PathBuf::from(lib_dir),
+    None => PathBuf::from("pluma_lib"),
+  };
+
+  lib_dir.join(module_name).with_extension(FILE_EXTENSION)
+}
+
+/// Reads a library module's source, if there is such a module.
+pub fn read_lib_module(module_name: &str) -> Option<Vec<u8>> {
+  if env::var_os(LIB_DIR_ENV_VAR).is_some() {
+    return fs::read(lib_module_path(module_name)).ok();
+  }
+
+  BUNDLED_MODULES
+    .iter()
+    .find(|(name, _)| *name == module_name)
+    .map(|(_, source)| source.as_bytes().to_vec())
+}
@@ -1,14 +1,17 @@
-use crate::compiler_options::{CompilerMode, CompilerOptions};
+use crate::bundled_lib;
+use crate::compiler_options::{CompilerOptions, EmitKind, OptLevel};
 use crate::dependency_graph::{DependencyGraph, TopologicalSort};
 use crate::import_error::{ImportError, ImportErrorKind};
 use crate::usage_error::{UsageError, UsageErrorKind};
 use pluma_analyzer::*;
+use pluma_ast::UseNode;
 use pluma_constants::*;
 use pluma_diagnostics::*;
 use pluma_emitter::*;
 use pluma_module::*;
 use std::collections::HashMap;
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 #[cfg_attr(debug_assertions, derive(Debug))]
@@ -16,16 +19,32 @@ pub struct Compiler {
   pub root_dir: PathBuf,
   pub entry_module_name: String,
   pub modules: HashMap<String, Module>,
-  mode: CompilerMode,
+  opt_level: OptLevel,
+  dump_ir_dir: Option<String>,
+  debug_info: bool,
+  report_leaks: bool,
+  check_overflow: bool,
   output_path: Option<String>,
+  emit_kind: EmitKind,
+  linker: Linker,
   diagnostics: Vec<Diagnostic>,
   dependency_graph: DependencyGraph,
   collect_comments: bool,
+  implicit_prelude: bool,
+  target: TargetOptions,
 }
 
 impl Compiler {
   /// Creates a new Compiler instance from the given options
   pub fn from_options(options: CompilerOptions) -> Result<Self, Vec<Diagnostic>> {
+    let opt_level = options
+      .opt_level
+      .unwrap_or_else(|| options.mode.default_opt_level());
+    let debug_info = options
+      .debug_info
+      .unwrap_or_else(|| options.mode.default_debug_info());
+    let report_leaks = options.mode.reports_leaks();
+    let check_overflow = options.mode.checks_overflow();
     let (root_dir, entry_module_name) = resolve_entry(options.entry_path)?;
     let dependency_graph = DependencyGraph::new(entry_module_name.clone());
 
@@ -35,8 +54,22 @@ impl Compiler {
       modules: HashMap::new(),
       diagnostics: Vec::new(),
       output_path: options.output_path,
-      mode: options.mode,
+      emit_kind: options.emit_kind,
+      linker: Linker::new(options.linker.unwrap_or_else(|| DEFAULT_LINKER.to_owned()))
+        .libs(options.link_libs)
+        .search_paths(options.link_search_paths),
+      opt_level,
+      dump_ir_dir: options.dump_ir_dir,
+      debug_info,
+      report_leaks,
+      check_overflow,
       collect_comments: options.collect_comments,
+      implicit_prelude: options.implicit_prelude,
+      target: TargetOptions {
+        triple: options.target_triple,
+        cpu: options.target_cpu,
+        features: options.target_features,
+      },
       dependency_graph,
     })
   }
@@ -45,7 +78,7 @@ impl Compiler {
   pub fn parse(&mut self) -> Result<(), Vec<Diagnostic>> {
     self.parse_module(
       self.entry_module_name.clone(),
-      to_module_path(self.root_dir.clone(), self.entry_module_name.clone()),
+      self.module_path(&self.entry_module_name),
     );
 
     if !self.diagnostics.is_empty() {
@@ -72,25 +105,43 @@ impl Compiler {
       TopologicalSort::Sorted(names) => names,
     };
 
+    // Each module's exports, which are analyzed before the modules that use them
+    let mut module_exports: HashMap<String, ModuleExports> = HashMap::new();
+
     for module_name in sorted_names {
       let mut module_scope = Scope::new();
 
+      let module_to_analyze = self.modules.get_mut(module_name).unwrap();
+
+      // Imported names go in their own level, so that the module's own
+      // names can shadow them
       module_scope.enter();
 
-      let module_to_analyze = self.modules.get_mut(module_name).unwrap();
+      for import_node in module_to_analyze.get_imports() {
+        if let Some(exports) = module_exports.get(&import_node.module_name) {
+          let same_package = package_name(&import_node.module_name) == package_name(module_name);
+
+          module_scope.import(
+            exports.visible_to(same_package),
+            import_node.qualifier.map(|qualifier| qualifier.name),
+          );
+        }
+      }
+
+      module_scope.enter();
+
+      let module_path = module_to_analyze.module_path.clone();
 
       let mut analyzer = Analyzer::new(&mut module_scope);
       module_to_analyze.traverse_mut(&mut analyzer);
 
+      module_exports.insert(module_name.clone(), analyzer.exports);
+
       for diagnostic in analyzer.diagnostics {
-        self.diagnostics.push(diagnostic.with_module(
-          module_name.clone(),
-          to_module_path(self.root_dir.clone(), self.entry_module_name.clone()),
-        ))
+        self
+          .diagnostics
+          .push(diagnostic.with_module(module_name.clone(), module_path.clone()))
       }
-
-      // println!("module {:#?}", module_to_analyze);
-      println!("scope {:#?}", module_scope);
     }
 
     if !self.diagnostics.is_empty() {
@@ -105,23 +156,59 @@ impl Compiler {
     self.check()?;
 
     let llvm_context = Emitter::create_context();
-    let mut emitter = Emitter::new(&llvm_context);
+    let mut emitter = match Emitter::new(&llvm_context, &self.target, self.opt_level) {
+      Ok(emitter) => emitter,
+      Err(err) => {
+        self.diagnostics.push(err);
+        return Err(self.diagnostics.to_vec());
+      }
+    };
+
+    if self.report_leaks {
+      emitter.enable_leak_report();
+    }
+
+    if self.check_overflow {
+      emitter.enable_overflow_checks();
+    }
+
+    emitter.set_entry_module(&self.entry_module_name);
 
     for module_name in self.sorted_module_names() {
       let module_to_emit = self.modules.get_mut(&module_name).unwrap();
+
+      emitter.begin_module(
+        module_imports(module_to_emit),
+        &module_to_emit.module_path,
+        module_to_emit.source.clone().unwrap_or_default(),
+      );
+
+      if self.debug_info {
+        emitter.begin_debug_info(
+          &module_to_emit.module_path,
+          module_to_emit
+            .line_break_positions
+            .clone()
+            .unwrap_or_default(),
+        );
+      }
+
       module_to_emit.traverse(&mut emitter);
     }
 
-    if self.release_mode() {
-      emitter.optimize();
+    if let Err(mut errs) = emitter.finish() {
+      self.diagnostics.append(&mut errs);
+      return Err(self.diagnostics.to_vec());
     }
 
+    self.optimize(&emitter);
+
     if let Err(err) = emitter.verify() {
       self.diagnostics.push(err);
     }
 
     if let Some(path) = &self.output_path {
-      if let Err(err) = emitter.write_to_path(Path::new(&path)) {
+      if let Err(err) = emitter.write_to_path(Path::new(&path), self.emit_kind, &self.linker) {
         self.diagnostics.push(err);
       }
     }
@@ -138,13 +225,53 @@ impl Compiler {
     self.check()?;
 
     let llvm_context = Emitter::create_context();
-    let mut emitter = Emitter::new(&llvm_context);
+    let mut emitter = match Emitter::new(&llvm_context, &self.target, self.opt_level) {
+      Ok(emitter) => emitter,
+      Err(err) => {
+        self.diagnostics.push(err);
+        return Err(self.diagnostics.to_vec());
+      }
+    };
+
+    if self.report_leaks {
+      emitter.enable_leak_report();
+    }
+
+    if self.check_overflow {
+      emitter.enable_overflow_checks();
+    }
+
+    emitter.set_entry_module(&self.entry_module_name);
 
     for module_name in self.sorted_module_names() {
       let module_to_emit = self.modules.get_mut(&module_name).unwrap();
+
+      emitter.begin_module(
+        module_imports(module_to_emit),
+        &module_to_emit.module_path,
+        module_to_emit.source.clone().unwrap_or_default(),
+      );
+
+      if self.debug_info {
+        emitter.begin_debug_info(
+          &module_to_emit.module_path,
+          module_to_emit
+            .line_break_positions
+            .clone()
+            .unwrap_or_default(),
+        );
+      }
+
       module_to_emit.traverse(&mut emitter);
     }
 
+    if let Err(mut errs) = emitter.finish() {
+      self.diagnostics.append(&mut errs);
+      return Err(self.diagnostics.to_vec());
+    }
+
+    self.optimize(&emitter);
+
     if let Err(err) = emitter.verify() {
       self.diagnostics.push(err);
     }
@@ -153,9 +280,12 @@ impl Compiler {
       return Err(self.diagnostics.to_vec());
     }
 
-    let exit_code = emitter.execute();
+    if let Err(err) = self.linker.load_libs() {
+      self.diagnostics.push(err);
+      return Err(self.diagnostics.to_vec());
+    }
 
-    return Ok(exit_code);
+    emitter.execute().map_err(|err| vec![err])
   }
 
   fn parse_module(&mut self, module_name: String, module_path: PathBuf) {
@@ -169,7 +299,25 @@ impl Compiler {
       self.collect_comments,
     );
 
-    let result = new_module.parse();
+    let result = if bundled_lib::is_lib_module(&module_name) {
+      match bundled_lib::read_lib_module(&module_name) {
+        Some(bytes) => new_module.parse_source(bytes),
+        None => new_module.parse(),
+      }
+    } else {
+      new_module.parse()
+    };
+
+    // Every module can use the prelude without importing it, except for
+    // the prelude itself
+    if self.implicit_prelude && module_name != PRELUDE_MODULE_NAME {
+      new_module.add_import(UseNode {
+        pos: (0, 0),
+        module_name: PRELUDE_MODULE_NAME.to_owned(),
+        qualifier: None,
+      });
+    }
+
     let imports = new_module.get_imports();
     self.modules.insert(module_name.clone(), new_module);
 
@@ -181,7 +329,8 @@ impl Compiler {
           Diagnostic::error(ImportError {
             kind: ImportErrorKind::ModuleNotFound(
               import_node.module_name.clone(),
-              to_module_path(self.root_dir.clone(), import_node.module_name.clone())
+              self
+                .module_path(&import_node.module_name)
                 .to_str()
                 .unwrap()
                 .to_owned(),
@@ -198,10 +347,9 @@ impl Compiler {
         .dependency_graph
         .add_edge(import_node.module_name.clone(), module_name.clone());
 
-      self.parse_module(
-        import_node.module_name.clone(),
-        to_module_path(self.root_dir.clone(), import_node.module_name),
-      )
+      let imported_module_path = self.module_path(&import_node.module_name);
+
+      self.parse_module(import_node.module_name, imported_module_path)
     }
 
     if !result.is_ok() {
@@ -215,6 +363,10 @@ impl Compiler {
       return true;
     }
 
+    if bundled_lib::is_lib_module(&module_name) {
+      return bundled_lib::read_lib_module(&module_name).is_some();
+    }
+
     if Path::new(&to_module_path(self.root_dir.clone(), module_name.clone())).is_file() {
       return true;
     }
@@ -222,10 +374,37 @@ impl Compiler {
     return false;
   }
 
-  fn release_mode(&self) -> bool {
-    match self.mode {
-      CompilerMode::Release => true,
-      _ => false,
+  /// Where a module's source is. `use std/...` refers to the standard
+  /// library, and other modules are relative to the entry module.
+  fn module_path(&self, module_name: &str) -> PathBuf {
+    if bundled_lib::is_lib_module(module_name) {
+      bundled_lib::lib_module_path(module_name)
+    } else {
+      to_module_path(self.root_dir.clone(), module_name.to_owned())
+    }
+  }
+
+  fn optimize(&mut self, emitter: &Emitter) {
+    self.dump_ir(emitter, "before-opt");
+    emitter.optimize();
+    self.dump_ir(emitter, "after-opt");
+  }
+
+  /// Writes the emitted IR to `<dump_ir_dir>/<entry module>.<stage>.ll`,
+  /// if IR dumps were requested.
+  fn dump_ir(&mut self, emitter: &Emitter, stage: &str) {
+    let dir = match &self.dump_ir_dir {
+      Some(dir) => PathBuf::from(dir),
+      None => return,
+    };
+
+    let path = dir.join(format!("{}.{}.ll", self.entry_module_name, stage));
+
+    // If this fails, writing the file will fail too and report why
+    let _ = fs::create_dir_all(&dir);
+
+    if let Err(err) = emitter.write_to_path(&path, EmitKind::LlvmIr, &self.linker) {
+      self.diagnostics.push(err);
     }
   }
 
@@ -244,6 +423,35 @@ fn resolve_entry(entry_path: String) -> Result<(PathBuf, String), Vec<Diagnostic
   }
 }
 
+/// The package that a module belongs to, which can see the module's
+/// internal definitions. Modules under `std/` belong to the standard
+/// library, and every other module belongs to the program.
+fn package_name(module_name: &str) -> &str {
+  if bundled_lib::is_lib_module(module_name) {
+    "std"
+  } else {
+    ""
+  }
+}
+
+/// A module's imports, for resolving the names in it to the symbols of
+/// the defs they refer to.
+fn module_imports(module: &Module) -> ModuleImports {
+  ModuleImports {
+    module_name: module.module_name.clone(),
+    imports: module
+      .get_imports()
+      .into_iter()
+      .map(|import_node| {
+        (
+          import_node.module_name,
+          import_node.qualifier.map(|qualifier| qualifier.name),
+        )
+      })
+      .collect(),
+  }
+}
+
 fn to_module_path(root_dir: PathBuf, module_name: String) -> PathBuf {
   root_dir.join(module_name).with_extension("pa")
 }
@@ -1,13 +1,62 @@
+pub use pluma_emitter::{EmitKind, OptLevel};
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum CompilerMode {
   Debug,
   Release,
 }
 
+impl CompilerMode {
+  /// The opt level to use when one isn't given explicitly.
+  pub fn default_opt_level(&self) -> OptLevel {
+    match self {
+      CompilerMode::Debug => OptLevel::O0,
+      CompilerMode::Release => OptLevel::O2,
+    }
+  }
+
+  /// Whether to emit debug info when it isn't explicitly turned on or off.
+  pub fn default_debug_info(&self) -> bool {
+    match self {
+      CompilerMode::Debug => true,
+      CompilerMode::Release => false,
+    }
+  }
+
+  /// Whether compiled programs should report leaked heap objects on exit.
+  pub fn reports_leaks(&self) -> bool {
+    match self {
+      CompilerMode::Debug => true,
+      CompilerMode::Release => false,
+    }
+  }
+
+  /// Whether Int arithmetic should panic on overflow. Otherwise, it wraps.
+  pub fn checks_overflow(&self) -> bool {
+    match self {
+      CompilerMode::Debug => true,
+      CompilerMode::Release => false,
+    }
+  }
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct CompilerOptions {
   pub entry_path: String,
   pub mode: CompilerMode,
+  pub opt_level: Option<OptLevel>,
+  pub dump_ir_dir: Option<String>,
+  pub debug_info: Option<bool>,
   pub output_path: Option<String>,
+  pub emit_kind: EmitKind,
+  pub linker: Option<String>,
+  pub link_libs: Vec<String>,
+  pub link_search_paths: Vec<String>,
   pub collect_comments: bool,
+  /// Whether every module imports the prelude without asking for it.
+  /// Freestanding code can turn this off and define everything itself.
+  pub implicit_prelude: bool,
+  pub target_triple: Option<String>,
+  pub target_cpu: Option<String>,
+  pub target_features: Option<String>,
 }
@@ -1,6 +1,7 @@
 #[macro_use]
 mod macros;
 
+mod bundled_lib;
 mod compiler;
 mod compiler_options;
 mod dependency_graph;
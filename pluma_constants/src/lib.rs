@@ -1,5 +1,9 @@
 pub const BINARY_NAME: &str = "pluma";
 pub const DEFAULT_ENTRY_MODULE_NAME: &str = "main";
 pub const DEFAULT_ENTRY_FILE: &str = "main.pa";
+pub const DEFAULT_LINKER: &str = "cc";
 pub const FILE_EXTENSION: &str = "pa";
 pub const VERSION: &str = "0.1.0";
+pub const PRELUDE_MODULE_NAME: &str = "std/prelude";
+pub const STD_MODULE_PREFIX: &str = "std/";
+pub const LIB_DIR_ENV_VAR: &str = "PLUMA_LIB";
@@ -1,3 +1,5 @@
 mod diagnostics;
+mod source_frame;
 
 pub use diagnostics::*;
+pub use source_frame::*;
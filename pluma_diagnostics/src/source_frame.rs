@@ -0,0 +1,72 @@
+/// The source lines that a diagnostic points at, so they can be shown
+/// beneath its message.
+pub struct SourceFrame {
+  pub line: usize,
+  pub column: usize,
+  pub text: String,
+}
+
+impl SourceFrame {
+  /// Finds the lines around `(start, end)` in a module's source. Line
+  /// breaks inside the range are shown as spaces, so the frame is always
+  /// one line long.
+  pub fn new(bytes: &[u8], (start, end): (usize, usize)) -> SourceFrame {
+    let mut frame_start = start;
+    let mut frame_end = end;
+    let mut col_index = 0;
+
+    while frame_start > 0 {
+      if let Some(b'\n') = bytes.get(frame_start - 1) {
+        break;
+      }
+
+      col_index += 1;
+      frame_start -= 1
+    }
+
+    while let Some(byte) = bytes.get(frame_end) {
+      match byte {
+        b'\n' => break,
+        _ => frame_end += 1,
+      }
+    }
+
+    let text =
+      String::from_utf8_lossy(&bytes[frame_start..frame_end.min(bytes.len())]).replace("\n", " ");
+
+    let line = 1
+      + bytes[..start.min(bytes.len())]
+        .iter()
+        .filter(|byte| **byte == b'\n')
+        .count();
+
+    SourceFrame {
+      line,
+      column: col_index + 1,
+      text,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn frame_on_later_line() {
+    let frame = SourceFrame::new(b"let a = 1\nlet b = a / 0\n", (18, 23));
+
+    assert_eq!(frame.line, 2);
+    assert_eq!(frame.column, 9);
+    assert_eq!(frame.text, "let b = a / 0");
+  }
+
+  #[test]
+  fn frame_across_lines() {
+    let frame = SourceFrame::new(b"match x\n  | 1 => 2\n", (0, 10));
+
+    assert_eq!(frame.line, 1);
+    assert_eq!(frame.column, 1);
+    assert_eq!(frame.text, "match x   | 1 => 2");
+  }
+}
@@ -0,0 +1,254 @@
+use inkwell::basic_block::BasicBlock;
+use inkwell::context::Context;
+use inkwell::debug_info::*;
+use inkwell::module::Module;
+use inkwell::values::{FunctionValue, PointerValue};
+use pluma_ast::{Position, ValueType};
+use std::collections::HashMap;
+use std::path::Path;
+
+// DWARF base type encodings (DW_ATE_*)
+const ENCODING_FLOAT: u32 = 0x04;
+const ENCODING_SIGNED: u32 = 0x05;
+const ENCODING_UNSIGNED: u32 = 0x08;
+
+/// Builds the debug info for one Pluma module, which becomes its own
+/// DWARF compile unit.
+pub struct ModuleDebugInfo<'ctx> {
+  llvm_context: &'ctx Context,
+  builder: DebugInfoBuilder<'ctx>,
+  compile_unit: DICompileUnit<'ctx>,
+  line_breaks: Vec<Position>,
+  is_optimized: bool,
+  types: HashMap<String, DIType<'ctx>>,
+
+  /// The scope of each function being compiled (innermost last), with the
+  /// source offset that the builder last pointed at in that function.
+  scopes: Vec<(DIScope<'ctx>, usize)>,
+}
+
+impl<'ctx> ModuleDebugInfo<'ctx> {
+  pub fn new(
+    llvm_context: &'ctx Context,
+    llvm_module: &Module<'ctx>,
+    source_path: &Path,
+    line_breaks: Vec<Position>,
+    is_optimized: bool,
+  ) -> Self {
+    let file_name = source_path
+      .file_name()
+      .unwrap_or_default()
+      .to_string_lossy();
+    let directory = source_path
+      .parent()
+      .map(|dir| dir.to_string_lossy().to_string())
+      .unwrap_or_default();
+
+    // DWARF has no language code for Pluma. C is the closest match for how
+    // debuggers should treat our functions and values.
+    let (builder, compile_unit) = llvm_module.create_debug_info_builder(
+      true,
+      DWARFSourceLanguage::C,
+      &file_name,
+      &directory,
+      "pluma",
+      is_optimized,
+      "",
+      0,
+      "",
+      DWARFEmissionKind::Full,
+      0,
+      false,
+      false,
+    );
+
+    ModuleDebugInfo {
+      llvm_context,
+      builder,
+      compile_unit,
+      line_breaks,
+      is_optimized,
+      types: HashMap::new(),
+      scopes: Vec::new(),
+    }
+  }
+
+  /// Creates a subprogram for a function that's defined in this module.
+  pub fn create_subprogram(
+    &self,
+    function: FunctionValue<'ctx>,
+    pos: Position,
+  ) -> DISubprogram<'ctx> {
+    let name = function.get_name().to_string_lossy();
+    let (line, _) = self.line_and_column(pos.0);
+    let file = self.compile_unit.get_file();
+
+    let subroutine_type = self
+      .builder
+      .create_subroutine_type(file, None, &[], DIFlags::PUBLIC);
+
+    let subprogram = self.builder.create_function(
+      self.compile_unit.as_debug_info_scope(),
+      &name,
+      Some(&name),
+      file,
+      line,
+      subroutine_type,
+      true,
+      true,
+      line,
+      DIFlags::PUBLIC,
+      self.is_optimized,
+    );
+
+    function.set_subprogram(subprogram);
+
+    subprogram
+  }
+
+  /// A scope for code in this module that lives in a function defined by
+  /// another module (i.e. top-level code, which all goes in `main`).
+  pub fn create_nested_scope(&self, parent: DISubprogram<'ctx>) -> DIScope<'ctx> {
+    self
+      .builder
+      .create_lexical_block(
+        parent.as_debug_info_scope(),
+        self.compile_unit.get_file(),
+        1,
+        1,
+      )
+      .as_debug_info_scope()
+  }
+
+  pub fn enter_scope(&mut self, scope: DIScope<'ctx>, pos: Position) {
+    self.scopes.push((scope, pos.0));
+  }
+
+  /// Leaves the current function's scope. Returns the location that the
+  /// builder was at in the enclosing function, if there is one.
+  pub fn exit_scope(&mut self) -> Option<DILocation<'ctx>> {
+    self.scopes.pop();
+
+    let offset = self.scopes.last()?.1;
+
+    self.location(offset)
+  }
+
+  /// Converts a source offset to a location in the current scope, and
+  /// remembers it as the current function's latest location.
+  pub fn location(&mut self, offset: usize) -> Option<DILocation<'ctx>> {
+    let (line, column) = self.line_and_column(offset);
+    let (scope, last_offset) = self.scopes.last_mut()?;
+
+    *last_offset = offset;
+
+    Some(
+      self
+        .builder
+        .create_debug_location(self.llvm_context, line, column, *scope, None),
+    )
+  }
+
+  /// Describes a local variable, stored at `ptr`, to the debugger.
+  pub fn declare_variable(
+    &mut self,
+    name: &str,
+    ptr: PointerValue<'ctx>,
+    typ: &ValueType,
+    size_in_bits: u64,
+    pos: Position,
+    block: BasicBlock<'ctx>,
+  ) {
+    let scope = match self.scopes.last() {
+      Some((scope, _)) => *scope,
+      None => return,
+    };
+
+    let (line, _) = self.line_and_column(pos.0);
+    let debug_type = self.debug_type(typ, size_in_bits);
+
+    let variable = self.builder.create_auto_variable(
+      scope,
+      name,
+      self.compile_unit.get_file(),
+      line,
+      debug_type,
+      true,
+      DIFlags::ZERO,
+      0,
+    );
+
+    if let Some(location) = self.location(pos.0) {
+      self
+        .builder
+        .insert_declare_at_end(ptr, Some(variable), None, location, block);
+    }
+  }
+
+  pub fn finalize(&self) {
+    self.builder.finalize();
+  }
+
+  /// Numbers and strings get real base types. Other values are described
+  /// as opaque blobs of the right size, so at least their bytes can be
+  /// inspected.
+  fn debug_type(&mut self, typ: &ValueType, size_in_bits: u64) -> DIType<'ctx> {
+    let name = format!("{}", typ);
+
+    if let Some(debug_type) = self.types.get(&name) {
+      return *debug_type;
+    }
+
+    let encoding = match typ {
+      ValueType::Int => ENCODING_SIGNED,
+      ValueType::Float => ENCODING_FLOAT,
+      ValueType::Sized(sized_type) if sized_type.is_float() => ENCODING_FLOAT,
+      ValueType::Sized(sized_type) if sized_type.is_signed() => ENCODING_SIGNED,
+      _ => ENCODING_UNSIGNED,
+    };
+
+    let debug_type = self
+      .builder
+      .create_basic_type(&name, size_in_bits, encoding, DIFlags::PUBLIC)
+      .expect("basic type size should be non-zero")
+      .as_type();
+
+    self.types.insert(name, debug_type);
+
+    debug_type
+  }
+
+  fn line_and_column(&self, offset: usize) -> (u32, u32) {
+    line_and_column(&self.line_breaks, offset)
+  }
+}
+
+/// Converts a byte offset into a 1-based line and column, given the
+/// positions of the line breaks in the source.
+pub(crate) fn line_and_column(line_breaks: &[Position], offset: usize) -> (u32, u32) {
+  let line_index = line_breaks.partition_point(|(start, _)| *start < offset);
+
+  let line_start = match line_index {
+    0 => 0,
+    _ => line_breaks[line_index - 1].1,
+  };
+
+  ((line_index + 1) as u32, (offset - line_start + 1) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn line_and_column_from_offsets() {
+    // "let a = 1\nlet b = 2\n\nb"
+    let line_breaks = vec![(9, 10), (19, 20), (20, 21)];
+
+    assert_eq!(line_and_column(&line_breaks, 0), (1, 1));
+    assert_eq!(line_and_column(&line_breaks, 4), (1, 5));
+    assert_eq!(line_and_column(&line_breaks, 10), (2, 1));
+    assert_eq!(line_and_column(&line_breaks, 18), (2, 9));
+    assert_eq!(line_and_column(&line_breaks, 21), (4, 1));
+  }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct EmitError {
+  pub kind: EmitErrorKind,
+}
+
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum EmitErrorKind {
+  UnsupportedTarget(String, String),
+  TargetMachineUnavailable(String, String, String),
+  ExecutionEngineUnavailable(String),
+  CannotRunTarget(String, String),
+  UnsupportedType(String),
+  UnsupportedFunctionValue(String),
+  WriteFailed(String, String),
+  LinkerUnavailable(String, String),
+  LinkFailed(String, String),
+  LibraryUnavailable(String),
+}
+
+impl fmt::Display for EmitError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    use EmitErrorKind::*;
+
+    match &self.kind {
+      UnsupportedTarget(triple, reason) => {
+        write!(f, "Target '{}' is not supported: {}", triple, reason)
+      }
+      TargetMachineUnavailable(triple, cpu, features) => write!(
+        f,
+        "Could not create a target machine for '{}' (cpu: '{}', features: '{}').",
+        triple, cpu, features
+      ),
+      ExecutionEngineUnavailable(reason) => {
+        write!(f, "Could not create an execution engine: {}", reason)
+      }
+      CannotRunTarget(triple, host_triple) => write!(
+        f,
+        "Cannot run code built for '{}' on this machine ('{}'). Use `pluma build` to compile for other targets.",
+        triple, host_triple
+      ),
+      UnsupportedType(typ) => write!(
+        f,
+        "Cannot compile values of type '{}', because it isn't a concrete type.",
+        typ
+      ),
+      UnsupportedFunctionValue(name) => write!(
+        f,
+        "Cannot use '{}' as a value, because defs and methods can only be called so far.",
+        name
+      ),
+      WriteFailed(path, reason) => write!(f, "Could not write '{}': {}", path, reason),
+      LinkerUnavailable(linker, reason) => {
+        write!(f, "Could not run linker '{}': {}", linker, reason)
+      }
+      LinkFailed(linker, output) => write!(f, "Linker '{}' failed:\n{}", linker, output),
+      LibraryUnavailable(lib) => write!(
+        f,
+        "Could not load library '{}' to run the program. Check that it's a shared library in one of the --link-search directories, or a system one.",
+        lib
+      ),
+    }
+  }
+}
@@ -0,0 +1,10 @@
+/// The stage of compilation that the emitter writes to disk.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum EmitKind {
+  LlvmIr,
+  LlvmBc,
+  Asm,
+  Obj,
+  Exe,
+}
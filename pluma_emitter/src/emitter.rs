@@ -1,22 +1,129 @@
+use crate::debug_info::ModuleDebugInfo;
+use crate::emit_error::{EmitError, EmitErrorKind};
+use crate::emit_kind::EmitKind;
+use crate::free_variables;
+use crate::generics::{self, TypeArgs, TypeSubstitution};
+use crate::layout::*;
+use crate::linker::Linker;
+use crate::mangle;
+use crate::opt_level::OptLevel;
+use crate::runtime::{KeyFunctions, Runtime};
+use crate::scope::Scope;
+use crate::symbols::{ModuleImports, Symbols};
+use crate::target_options::TargetOptions;
+use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
-use inkwell::module::{Linkage, Module};
-use inkwell::passes::PassManager;
-use inkwell::targets::TargetTriple;
+use inkwell::debug_info::AsDIScope;
+use inkwell::module::{FlagBehavior, Linkage, Module};
+use inkwell::passes::{PassManager, PassManagerBuilder};
+use inkwell::targets::{
+  CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
+};
+use inkwell::types::*;
 use inkwell::values::*;
-use inkwell::{AddressSpace, OptimizationLevel};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
 use pluma_ast::*;
 use pluma_diagnostics::*;
+use pluma_match::*;
+use pluma_regex::compile_regex;
 use pluma_visitor::*;
-use std::convert::TryInto;
-use std::io::prelude::*;
-use std::process::{Command, Stdio};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::rc::Rc;
+use std::{env, fs, process};
+
+/// The parts of a match's subject that have been loaded so far, by path.
+type AccessValues<'ctx> = HashMap<Vec<AccessStep>, BasicValueEnum<'ctx>>;
+
+/// The storage for each case's bindings, which is shared by every leaf of
+/// the decision tree that leads to the case.
+type CaseSlots<'ctx> = Vec<(String, PointerValue<'ctx>, ValueType, bool)>;
+
+struct MatchState<'ctx> {
+  subject: BasicValueEnum<'ctx>,
+  case_blocks: Vec<Option<BasicBlock<'ctx>>>,
+  case_slots: Vec<Option<CaseSlots<'ctx>>>,
+  no_match_block: BasicBlock<'ctx>,
+  /// Where the runtime puts the bounds of each hole, for each string
+  /// pattern that a part of the subject is tested against.
+  hole_bounds: HashMap<(Vec<AccessStep>, Vec<String>), PointerValue<'ctx>>,
+}
+
+/// Where `$self..return` and `break` go from inside a block.
+#[derive(Clone, Copy)]
+enum BlockExit<'ctx> {
+  /// A def's or closure's body, which is left by returning from its
+  /// function. Defs also pop their backtrace frame.
+  Function { scope_depth: usize, has_frame: bool },
+  /// A block passed straight to `loop`, which is compiled inline.
+  Loop {
+    scope_depth: usize,
+    body_block: BasicBlock<'ctx>,
+    exit_block: BasicBlock<'ctx>,
+  },
+}
+
+/// A generic def, which is compiled once for each set of types that it's
+/// called with.
+struct GenericDef {
+  node: Rc<DefNode>,
+  /// The module that the def is in, so that panics in its instances
+  /// point at the right file, and names in it resolve like they do there.
+  source_file: Option<Rc<(String, Vec<u8>)>>,
+  module: Rc<ModuleImports>,
+}
 
 pub struct Emitter<'ctx> {
   llvm_context: &'ctx Context,
   llvm_builder: Builder<'ctx>,
   llvm_module: Module<'ctx>,
+  target_machine: TargetMachine,
+  opt_level: OptLevel,
   main_function: FunctionValue<'ctx>,
+
+  /// The block in main that top-level code continues from. It isn't
+  /// always main's last block, since e.g. a match's blocks come after
+  /// the block it ends in.
+  main_block: Option<BasicBlock<'ctx>>,
+
+  scope: Scope<'ctx>,
+  layouts: Layouts,
+  debug_info: Option<ModuleDebugInfo<'ctx>>,
+  runtime: Runtime<'ctx>,
+  report_leaks: bool,
+  check_overflow: bool,
+
+  /// The module that the program was compiled from, whose `main` def is
+  /// the only one that runs.
+  entry_module_name: String,
+
+  /// The path of the module being emitted, and its source, so that panics
+  /// can point at the code that caused them.
+  source_file: Option<Rc<(String, Vec<u8>)>>,
+
+  /// Drop functions that have been declared, but whose bodies haven't
+  /// been built yet, with the payload type that each one releases.
+  pending_drops: Vec<(FunctionValue<'ctx>, ValueType)>,
+
+  /// The blocks being compiled, innermost last.
+  block_exits: Vec<BlockExit<'ctx>>,
+
+  /// Generic defs, by the symbol they'd have if they weren't generic.
+  generic_defs: HashMap<String, GenericDef>,
+
+  /// Which module defines each def, for resolving names to symbols.
+  symbols: Symbols,
+
+  /// Types that got this far without being concrete, which are reported
+  /// once every module has been emitted.
+  unsupported_types: RefCell<Vec<ValueType>>,
+
+  /// Defs and methods that were used as values, which are reported along
+  /// with the unsupported types.
+  unsupported_function_values: RefCell<Vec<String>>,
 }
 
 impl<'ctx> Emitter<'ctx> {
@@ -24,21 +131,176 @@ impl<'ctx> Emitter<'ctx> {
     Context::create()
   }
 
-  pub fn new(llvm_context: &'ctx Context) -> Emitter<'ctx> {
+  pub fn new(
+    llvm_context: &'ctx Context,
+    target_options: &TargetOptions,
+    opt_level: OptLevel,
+  ) -> Result<Emitter<'ctx>, Diagnostic> {
     let llvm_builder = llvm_context.create_builder();
     let llvm_module = llvm_context.create_module("root_module");
 
-    llvm_module.set_triple(&TargetTriple::create("x86_64-apple-macosx10.15.0"));
+    let target_machine = create_target_machine(target_options, opt_level)?;
+
+    llvm_module.set_triple(&target_machine.get_triple());
+    llvm_module.set_data_layout(&target_machine.get_target_data().get_data_layout());
 
     let return_type = llvm_context.i32_type().fn_type(&Vec::new(), false);
     let main_function = llvm_module.add_function("main", return_type, None);
 
-    return Emitter {
+    let runtime = Runtime::build(llvm_context, &llvm_module);
+
+    let mut scope = Scope::new();
+    scope.enter();
+
+    Ok(Emitter {
       llvm_context,
       llvm_builder,
       llvm_module,
+      target_machine,
+      opt_level,
       main_function,
+      main_block: None,
+      scope,
+      layouts: Layouts::new(),
+      debug_info: None,
+      runtime,
+      report_leaks: false,
+      check_overflow: false,
+      entry_module_name: String::new(),
+      source_file: None,
+      pending_drops: Vec::new(),
+      block_exits: Vec::new(),
+      generic_defs: HashMap::new(),
+      symbols: Symbols::new(),
+      unsupported_types: RefCell::new(Vec::new()),
+      unsupported_function_values: RefCell::new(Vec::new()),
+    })
+  }
+
+  /// Makes the program report any heap objects that are still alive when
+  /// it exits.
+  pub fn enable_leak_report(&mut self) {
+    self.report_leaks = true;
+  }
+
+  /// Makes Int arithmetic panic when it overflows, instead of
+  /// wrapping around.
+  pub fn enable_overflow_checks(&mut self) {
+    self.check_overflow = true;
+  }
+
+  /// Sets the module that the program was compiled from.
+  pub fn set_entry_module(&mut self, module_name: &str) {
+    self.entry_module_name = module_name.to_owned();
+  }
+
+  /// Sets the source file for the next module to be emitted, and the
+  /// modules that it imports.
+  pub fn begin_module(&mut self, module: ModuleImports, source_path: &Path, source: Vec<u8>) {
+    self.symbols.enter_module(Rc::new(module));
+
+    // Paths are shown relative to the working directory, like they are
+    // in compile errors
+    let source_path = match env::current_dir() {
+      Ok(cwd) => source_path.strip_prefix(cwd).unwrap_or(source_path),
+      Err(_) => source_path,
+    };
+
+    self.source_file = Some(Rc::new((source_path.to_string_lossy().to_string(), source)));
+  }
+
+  /// Starts recording debug info for the next module to be emitted, so
+  /// that its code can be mapped back to the source file it came from.
+  pub fn begin_debug_info(&mut self, source_path: &Path, line_break_positions: Vec<Position>) {
+    let mut debug_info = ModuleDebugInfo::new(
+      self.llvm_context,
+      &self.llvm_module,
+      source_path,
+      line_break_positions,
+      self.opt_level != OptLevel::O0,
+    );
+
+    // Every module's top-level code goes in main, so main's subprogram
+    // belongs to whichever module asks for debug info first.
+    let main_scope = match self.main_function.get_subprogram() {
+      Some(subprogram) => debug_info.create_nested_scope(subprogram),
+
+      None => {
+        let debug_info_version = self.llvm_context.i32_type().const_int(3, false);
+
+        self.llvm_module.add_basic_value_flag(
+          "Debug Info Version",
+          FlagBehavior::Warning,
+          debug_info_version,
+        );
+
+        debug_info
+          .create_subprogram(self.main_function, (0, 0))
+          .as_debug_info_scope()
+      }
     };
+
+    debug_info.enter_scope(main_scope, (0, 0));
+
+    self.debug_info = Some(debug_info);
+  }
+
+  /// Goes back to where top-level code left off in main, since defs are
+  /// compiled in between top-level statements.
+  fn position_in_main(&self) {
+    let block = self
+      .main_block
+      .expect("modules should start with a block in main");
+
+    self.llvm_builder.position_at_end(block);
+  }
+
+  /// Records where top-level code has got to in main, which is wherever
+  /// the last top-level statement finished (e.g. after a match or loop).
+  fn leave_main(&mut self) {
+    self.main_block = self.llvm_builder.get_insert_block();
+  }
+
+  /// Ends main once every module has been emitted. Top-level variables
+  /// live until the end of the program, so they're only released here.
+  pub fn finish(&mut self) -> Result<(), Vec<Diagnostic>> {
+    self.position_in_main();
+    self.llvm_builder.unset_current_debug_location();
+
+    self.release_scope_variables();
+
+    if self.report_leaks {
+      self
+        .llvm_builder
+        .build_call(self.runtime.report_leaks, &[], "");
+    }
+
+    // Programs that don't call `exit` succeed once they reach the end
+    let success = self.llvm_context.i32_type().const_zero();
+
+    self.llvm_builder.build_return(Some(&success));
+
+    self.build_pending_drop_functions();
+
+    let unsupported_types = self.unsupported_types.borrow();
+    let unsupported_function_values = self.unsupported_function_values.borrow();
+
+    if unsupported_types.is_empty() && unsupported_function_values.is_empty() {
+      return Ok(());
+    }
+
+    Err(
+      unsupported_types
+        .iter()
+        .map(|typ| EmitErrorKind::UnsupportedType(typ.to_string()))
+        .chain(
+          unsupported_function_values
+            .iter()
+            .map(|name| EmitErrorKind::UnsupportedFunctionValue(name.clone())),
+        )
+        .map(|kind| Diagnostic::error(EmitError { kind }))
+        .collect(),
+    )
   }
 
   pub fn verify(&self) -> Result<(), Diagnostic> {
@@ -52,255 +314,3705 @@ impl<'ctx> Emitter<'ctx> {
     self.llvm_module.print_to_string().to_string()
   }
 
-  pub fn write_to_path(&self, path: &std::path::Path) -> Result<(), Diagnostic> {
-    let mut process = Command::new("clang")
-      .args(&["-x", "ir", "-", "-o", path.to_str().unwrap()])
-      .stdin(Stdio::piped())
-      .stdout(Stdio::piped())
-      .spawn()
-      .map_err(|_| Diagnostic::error("Failed to spawn process."))?;
+  pub fn write_to_path(
+    &self,
+    path: &Path,
+    emit_kind: EmitKind,
+    linker: &Linker,
+  ) -> Result<(), Diagnostic> {
+    match emit_kind {
+      EmitKind::LlvmIr => self
+        .llvm_module
+        .print_to_file(path)
+        .map_err(|err| write_failed(path, err.to_string())),
+
+      EmitKind::LlvmBc => {
+        if self.llvm_module.write_bitcode_to_path(path) {
+          Ok(())
+        } else {
+          Err(write_failed(
+            path,
+            "LLVM could not write bitcode.".to_owned(),
+          ))
+        }
+      }
+
+      EmitKind::Asm => self.write_machine_code(path, FileType::Assembly),
 
-    let child_stdin = process.stdin.as_mut().unwrap();
+      EmitKind::Obj => self.write_machine_code(path, FileType::Object),
 
-    child_stdin
-      .write_all(&self.write_to_string().as_bytes()[..])
-      .map_err(|_| Diagnostic::error("Failed to pipe input to process."))?;
+      EmitKind::Exe => {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let object_path = env::temp_dir().join(format!("{}-{}.o", file_name, process::id()));
 
-    let output = process
-      .wait_with_output()
-      .map_err(|_| Diagnostic::error("Problem waiting for process to exit."))?;
+        self.write_machine_code(&object_path, FileType::Object)?;
+        let result = linker.link(&object_path, path);
+        let _ = fs::remove_file(&object_path);
 
-    if !output.status.success() {
-      return Err(Diagnostic::error("Process did not terminate successfully."));
+        result
+      }
     }
+  }
 
-    Ok(())
+  fn write_machine_code(&self, path: &Path, file_type: FileType) -> Result<(), Diagnostic> {
+    self
+      .target_machine
+      .write_to_file(&self.llvm_module, file_type, path)
+      .map_err(|err| write_failed(path, err.to_string()))
   }
 
-  pub fn optimize(&mut self) {
-    let llvm_pass_manager = PassManager::create(&self.llvm_module);
-    // TODO: add passes!
-    llvm_pass_manager.initialize();
+  /// Runs LLVM's optimization pipeline for the emitter's opt level: the
+  /// function passes over every function, then the module passes over
+  /// the whole module.
+  pub fn optimize(&self) {
+    if self.opt_level == OptLevel::O0 {
+      return;
+    }
+
+    let pass_manager_builder = PassManagerBuilder::create();
+    pass_manager_builder.set_optimization_level(self.opt_level.llvm_level());
+    pass_manager_builder.set_size_level(self.opt_level.size_level());
+
+    if let Some(threshold) = self.opt_level.inline_threshold() {
+      pass_manager_builder.set_inliner_with_threshold(threshold);
+    }
+
+    let function_pass_manager = PassManager::create(&self.llvm_module);
+    pass_manager_builder.populate_function_pass_manager(&function_pass_manager);
+    function_pass_manager.initialize();
+
+    let mut next_function = self.llvm_module.get_first_function();
+
+    while let Some(function) = next_function {
+      function_pass_manager.run_on(&function);
+      next_function = function.get_next_function();
+    }
+
+    function_pass_manager.finalize();
 
-    llvm_pass_manager.run_on(&mut self.main_function);
+    let module_pass_manager = PassManager::create(());
+    pass_manager_builder.populate_module_pass_manager(&module_pass_manager);
+    module_pass_manager.run_on(&self.llvm_module);
   }
 
-  pub fn execute(&self) -> i32 {
+  pub fn execute(&self) -> Result<i32, Diagnostic> {
+    // The JIT runs code on this machine, so code built for any other
+    // target can't run correctly
+    let triple = TargetMachine::normalize_triple(&self.target_machine.get_triple());
+    let host_triple = TargetMachine::normalize_triple(&TargetMachine::get_default_triple());
+
+    if triple != host_triple {
+      return Err(Diagnostic::error(EmitError {
+        kind: EmitErrorKind::CannotRunTarget(triple_name(&triple), triple_name(&host_triple)),
+      }));
+    }
+
     let execution_engine = self
       .llvm_module
-      .create_jit_execution_engine(OptimizationLevel::None)
-      .unwrap();
+      .create_jit_execution_engine(self.opt_level.llvm_level())
+      .map_err(|err| {
+        Diagnostic::error(EmitError {
+          kind: EmitErrorKind::ExecutionEngineUnavailable(err.to_string()),
+        })
+      })?;
 
     let exit_code = unsafe { execution_engine.run_function_as_main(self.main_function, &[]) };
 
-    exit_code
+    Ok(exit_code)
   }
 
-  fn compile_call(&self, call: &CallNode) -> BasicValueEnum {
-    let callee_name = match &call.callee.kind {
-      ExprKind::Identifier(ident) => ident.name.clone(),
-      _ => todo!(),
-    };
+  fn compile_call(&mut self, call: &CallNode, typ: &ValueType) -> BasicValueEnum<'ctx> {
+    if call.callee.is_block_return() {
+      let value = self.compile_expr(&call.args[0]);
 
-    let func = self
-      .llvm_module
-      .get_function(&callee_name[..])
-      .expect("should have function defined");
+      return self.build_block_return(value);
+    }
+
+    let mut arg_values = Vec::with_capacity(call.args.len() + 1);
+    let mut arg_types = Vec::with_capacity(call.args.len() + 1);
+
+    let function_name = match &call.callee.kind {
+      // Names bound to variables hold closures, other names refer to defs
+      ExprKind::Identifier(ident) if self.scope.get_variable(&ident.name).is_none() => {
+        if let Some(constructor) = self.layouts.get_constructor(&ident.name).cloned() {
+          return self.compile_constructor_call(&constructor, &call.args[0]);
+        }
+
+        if ident.name == "loop" {
+          if let ExprKind::Block(block) = &call.args[0].kind {
+            return self.compile_loop(block);
+          }
+        }
+
+        self
+          .symbols
+          .resolve(&mangle::function_name(std::slice::from_ref(&ident.name)))
+      }
 
-    let mut compiled_args = Vec::with_capacity(1);
+      ExprKind::MultiPartIdentifier(idents) => self
+        .symbols
+        .resolve(&mangle::function_name(&identifier_names(idents))),
+
+      // A def from a module imported with a qualifier, e.g. `f.bar`
+      ExprKind::FieldAccess { receiver, field } if self.is_qualifier(receiver) => {
+        self.resolve_qualified(receiver, std::slice::from_ref(field))
+      }
+
+      ExprKind::MethodAccess {
+        receiver,
+        method_parts,
+      } if self.is_qualifier(receiver) => self.resolve_qualified(receiver, method_parts),
+
+      ExprKind::MethodAccess {
+        receiver,
+        method_parts,
+      } => {
+        match &receiver.typ {
+          ValueType::Generic(name, _) if name == "Regex" => {
+            return self.compile_regex_method(receiver, method_parts, &call.args);
+          }
+
+          ValueType::Generic(..) if self.layouts.get_type(&receiver.typ).is_none() => {
+            return self.compile_collection_method(receiver, method_parts, &call.args, call.pos);
+          }
+
+          _ => {}
+        }
+
+        // The receiver is always passed as a method's first argument
+        arg_values.push(self.compile_expr(receiver));
+        arg_types.push(&receiver.typ);
+
+        self.symbols.resolve(&mangle::method_name(
+          &receiver.typ,
+          &identifier_names(method_parts),
+        ))
+      }
+
+      _ => {
+        let closure = self.compile_expr(&call.callee);
+
+        return self.build_closure_call(closure, &call.callee.typ, &call.args);
+      }
+    };
 
     for arg in &call.args {
-      compiled_args.push(self.compile_expr(&arg));
+      arg_values.push(self.compile_expr(arg));
+      arg_types.push(&arg.typ);
+    }
+
+    let function_name = if self.generic_defs.contains_key(&function_name) {
+      self.instantiate_generic_def(&function_name, &arg_types, typ, call.pos)
+    } else {
+      function_name
+    };
+
+    let result = self.build_call_by_name(&function_name, &arg_values);
+
+    // Functions only borrow their arguments
+    for (value, typ) in arg_values.into_iter().zip(arg_types) {
+      self.release_value(value, typ);
     }
 
-    let argsv: Vec<BasicValueEnum> = compiled_args
+    result
+  }
+
+  /// Whether an expression is the qualifier of an imported module (e.g.
+  /// `f` in `f.bar`), rather than a value.
+  fn is_qualifier(&self, expr: &ExprNode) -> bool {
+    match &expr.kind {
+      ExprKind::Identifier(ident) => {
+        self.scope.get_variable(&ident.name).is_none() && self.symbols.is_qualifier(&ident.name)
+      }
+      _ => false,
+    }
+  }
+
+  fn resolve_qualified(&self, qualifier: &ExprNode, name_parts: &[IdentifierNode]) -> String {
+    let qualifier = match &qualifier.kind {
+      ExprKind::Identifier(ident) => &ident.name,
+      _ => unreachable!("qualifiers should be identifiers"),
+    };
+
+    self.symbols.resolve_qualified(
+      qualifier,
+      &mangle::function_name(&identifier_names(name_parts)),
+    )
+  }
+
+  fn compile_constructor_call(
+    &mut self,
+    constructor: &ConstructorLayout,
+    arg: &ExprNode,
+  ) -> BasicValueEnum<'ctx> {
+    let value = self.compile_expr(arg);
+
+    match constructor {
+      ConstructorLayout::Variant { tag, .. } => {
+        self.build_enum_value(*tag, Some((value, &arg.typ)))
+      }
+
+      ConstructorLayout::Struct { type_name } => {
+        let inner_type = self.struct_inner_type(&ValueType::Named(type_name.clone()));
+
+        self.build_struct_value(&inner_type, value)
+      }
+    }
+  }
+
+  fn build_closure_call(
+    &mut self,
+    closure: BasicValueEnum<'ctx>,
+    closure_type: &ValueType,
+    args: &[ExprNode],
+  ) -> BasicValueEnum<'ctx> {
+    let arg_values = args
       .iter()
-      .by_ref()
-      .map(|&val| val.into())
-      .collect();
+      .map(|arg| self.compile_expr(arg))
+      .collect::<Vec<BasicValueEnum>>();
+
+    let result = self.call_closure(closure, &arg_values);
+
+    for (value, arg) in arg_values.into_iter().zip(args) {
+      self.release_value(value, &arg.typ);
+    }
+
+    self.release_value(closure, closure_type);
+
+    result
+  }
+
+  /// Calls a closure, which borrows its arguments.
+  fn call_closure(
+    &self,
+    closure: BasicValueEnum<'ctx>,
+    args: &[BasicValueEnum<'ctx>],
+  ) -> BasicValueEnum<'ctx> {
+    let fn_ptr = self.extract_tuple_entry(closure, 0).into_pointer_value();
+    let env_ptr = self.extract_tuple_entry(closure, 1);
+
+    // The environment is always passed as a closure's first argument
+    let mut arg_values = Vec::with_capacity(args.len() + 1);
+    arg_values.push(env_ptr);
+    arg_values.extend_from_slice(args);
+
+    let function = CallableValue::try_from(fn_ptr).expect("closure should hold a function pointer");
+
+    self
+      .llvm_builder
+      .build_call(function, &arg_values, "call")
+      .try_as_basic_value()
+      .left()
+      .expect("call did not return a basic value")
+  }
 
-    let call_site_value = self.llvm_builder.build_call(func, argsv.as_slice(), "tmp");
+  fn build_call_by_name(
+    &self,
+    function_name: &str,
+    args: &[BasicValueEnum<'ctx>],
+  ) -> BasicValueEnum<'ctx> {
+    let function = self
+      .llvm_module
+      .get_function(&self.symbols.resolve(function_name))
+      .expect("should have function defined");
 
-    call_site_value
+    self
+      .llvm_builder
+      .build_call(function, args, "call")
       .try_as_basic_value()
       .left()
       .expect("call did not return a basic value")
   }
 
-  fn compile_expr(&self, expr: &ExprNode) -> BasicValueEnum {
+  fn compile_expr(&mut self, expr: &ExprNode) -> BasicValueEnum<'ctx> {
+    self.set_debug_location(expr.pos);
+
     match &expr.kind {
-      ExprKind::Literal(lit) => self.compile_literal(lit),
+      ExprKind::Assignment { left, right } => {
+        let value = self.compile_expr(right);
 
-      ExprKind::Call(call) => self.compile_call(call),
+        let variable = self
+          .scope
+          .get_variable(&left.name)
+          .expect("assigned variable should be in scope");
 
-      _other => todo!("compile expr kind"),
-    }
-  }
+        let (ptr, typ) = (variable.ptr, variable.typ.clone());
 
-  fn compile_literal(&self, lit: &LiteralNode) -> BasicValueEnum {
-    match &lit.kind {
-      LiteralKind::IntDecimal(value) => self
-        .llvm_context
-        .i32_type()
-        .const_int((*value).try_into().unwrap(), true)
-        .into(),
+        let old_value = self.llvm_builder.build_load(ptr, &left.name);
+        self.release_value(old_value, &typ);
 
-      LiteralKind::FloatDecimal(value) => self.llvm_context.f64_type().const_float(*value).into(),
+        self.llvm_builder.build_store(ptr, value);
 
-      LiteralKind::Str(value) => {
-        // hmm
-        let global_value = self
-          .llvm_builder
-          .build_global_string_ptr(value.as_str(), "str");
+        self.nothing_value()
+      }
 
-        global_value.as_pointer_value().into()
+      ExprKind::BinaryOperation { left, op, right } => {
+        let function_name = mangle::binary_operator_name(&left.typ, &op.name, &right.typ);
+        let args = [self.compile_expr(left), self.compile_expr(right)];
+
+        let checked_result = match number_class(&left.typ) {
+          Some(NumberClass::Int { signed }) if left.typ == right.typ => match op.name.as_str() {
+            "/" | "%" => Some(self.build_int_division(&op.name, &args, signed, expr.pos)),
+            _ if self.check_overflow => {
+              self.build_checked_int_operation(&op.name, &args, signed, expr.pos)
+            }
+            _ => None,
+          },
+          _ => None,
+        };
+
+        let result =
+          checked_result.unwrap_or_else(|| self.build_call_by_name(&function_name, &args));
+
+        self.release_value(args[0], &left.typ);
+        self.release_value(args[1], &right.typ);
+
+        result
       }
 
-      _other => todo!("compile literal kind"),
-    }
-  }
+      ExprKind::Block(block) => self.compile_closure(block, &expr.typ),
 
-  fn build_intrinsic_function(&mut self, name: String) {
-    match &name[..] {
-      "exit_with" => {
-        {
-          // add extern "exit" definition
-          let param_types = vec![self.llvm_context.i32_type().into()];
-          let param_types = param_types.as_slice();
-          let fn_type = self.llvm_context.void_type().fn_type(&param_types, false);
+      ExprKind::Break => {
+        let (tag, _) = self
+          .layouts
+          .get_variant(&loop_result_type(), "LoopBreak")
+          .expect("analyzer should only allow break in a loop's block");
 
-          self
-            .llvm_module
-            .add_function("exit", fn_type, Some(Linkage::External));
+        let value = self.build_enum_value(tag, None);
+
+        self.build_block_return(value)
+      }
+
+      ExprKind::Literal(lit) => self.compile_literal(lit),
+
+      ExprKind::Call(call) => self.compile_call(call, &expr.typ),
+
+      ExprKind::EmptyTuple => self.nothing_value(),
+
+      ExprKind::Grouping(inner) => self.compile_expr(inner),
+
+      ExprKind::Interpolation(parts) => self.compile_interpolation(parts),
+
+      ExprKind::Identifier(ident) => match self.scope.get_variable(&ident.name) {
+        Some(variable) => {
+          let value = self.llvm_builder.build_load(variable.ptr, &ident.name);
+
+          // The variable keeps its reference, and the caller gets another
+          self.retain_value(value, &expr.typ);
+
+          value
         }
 
-        // add wrapping func definition
-        let param_types = vec![self.llvm_context.i32_type().into()];
-        let param_types = param_types.as_slice();
+        // Any other name is an enum variant without a payload, or a def
+        None => match self.layouts.get_variant(&expr.typ, &ident.name) {
+          Some((tag, _)) => self.build_enum_value(tag, None),
+          None => self.unsupported_function_value(std::slice::from_ref(&ident.name), &expr.typ),
+        },
+      },
+
+      ExprKind::FieldAccess { receiver, field } => {
+        self.compile_field_access(receiver, &field.name, &expr.typ)
+      }
 
-        let fn_type = self
-          .llvm_context
-          .struct_type(&[], false)
-          .fn_type(&param_types, false);
+      // A field that's accessed without a call is parsed like a method
+      ExprKind::MethodAccess {
+        receiver,
+        method_parts,
+      } if self.is_field_access(receiver, method_parts) => {
+        self.compile_field_access(receiver, &method_parts[0].name, &expr.typ)
+      }
 
-        let function = self.llvm_module.add_function(&name[..], fn_type, None);
+      ExprKind::MethodAccess { method_parts, .. } => {
+        self.unsupported_function_value(&identifier_names(method_parts), &expr.typ)
+      }
 
-        let entry = self.llvm_context.append_basic_block(function, "entry");
-        self.llvm_builder.position_at_end(entry);
+      ExprKind::MultiPartIdentifier(idents) => {
+        self.unsupported_function_value(&identifier_names(idents), &expr.typ)
+      }
 
-        let first_param = function.get_first_param().unwrap();
-        first_param.set_name("a");
+      ExprKind::Match(match_node) => self.compile_match(match_node, &expr.typ),
 
-        // call extern function
-        let args = vec![first_param];
-        let func = self
-          .llvm_module
-          .get_function("exit")
-          .expect("should have function defined");
-        self.llvm_builder.build_call(func, args.as_slice(), "");
+      ExprKind::UnlabeledTuple(entries) => {
+        let entry_values = entries
+          .iter()
+          .map(|entry| self.compile_expr(entry))
+          .collect();
 
-        // return ()
-        let return_value = self.llvm_context.const_struct(&[], false);
-        self.llvm_builder.position_at_end(entry);
-        self.llvm_builder.build_return(Some(&return_value));
+        self.compile_tuple(&expr.typ, entry_values)
       }
 
-      "print" => {
-        {
-          // add extern "puts" definition
-          let param_types = vec![self
-            .llvm_context
-            .i8_type()
-            .ptr_type(AddressSpace::Generic)
-            .into()];
-          let param_types = param_types.as_slice();
-          let fn_type = self.llvm_context.i32_type().fn_type(&param_types, false);
+      ExprKind::LabeledTuple(entries) => {
+        let entry_values = entries
+          .iter()
+          .map(|(_label, entry)| self.compile_expr(entry))
+          .collect();
 
-          self
-            .llvm_module
-            .add_function("puts", fn_type, Some(Linkage::External));
-        }
+        self.compile_tuple(&expr.typ, entry_values)
+      }
 
-        // add definition for wrapping "print" func
-        let param_types = vec![self
-          .llvm_context
-          .i8_type()
-          .ptr_type(AddressSpace::Generic)
-          .into()];
-        let param_types = param_types.as_slice();
+      ExprKind::List(elements) => {
+        let element_type = collection_element_type(&expr.typ);
 
-        let fn_type = self
-          .llvm_context
-          .struct_type(&[], false)
-          .fn_type(&param_types, false);
+        self.compile_list(elements, &element_type)
+      }
 
-        let function = self.llvm_module.add_function(&name[..], fn_type, None);
+      ExprKind::Dict(entries) => {
+        let entry_type = collection_element_type(&expr.typ);
 
-        let entry = self.llvm_context.append_basic_block(function, "entry");
-        self.llvm_builder.position_at_end(entry);
+        self.compile_dict(entries, &entry_type)
+      }
 
-        let first_param = function.get_first_param().unwrap();
-        first_param.set_name("a");
+      ExprKind::RegExpr(reg_expr) => self.build_static_regex(reg_expr).into(),
 
-        let args = vec![first_param];
+      ExprKind::TypeAssertion { expr, .. } => self.compile_expr(expr),
 
-        let func = self
-          .llvm_module
-          .get_function("puts")
-          .expect("should have function defined");
+      ExprKind::UnaryOperation { op, right } => {
+        let function_name = mangle::unary_operator_name(&op.name, &right.typ);
+        let arg = self.compile_expr(right);
+
+        // Negation overflows like subtracting from zero does
+        let checked_result = match number_class(&right.typ) {
+          Some(NumberClass::Int { signed }) if self.check_overflow && op.name == "-" => {
+            let zero = arg.into_int_value().get_type().const_zero().into();
+
+            self.build_checked_int_operation("-", &[zero, arg], signed, expr.pos)
+          }
+          _ => None,
+        };
 
-        self.llvm_builder.build_call(func, args.as_slice(), "");
+        let result =
+          checked_result.unwrap_or_else(|| self.build_call_by_name(&function_name, &[arg]));
 
-        let return_value = self.llvm_context.const_struct(&[], false);
-        self.llvm_builder.position_at_end(entry);
-        self.llvm_builder.build_return(Some(&return_value));
+        self.release_value(arg, &right.typ);
+
+        result
       }
 
-      _ => unreachable!(),
+      ExprKind::Underscore => unreachable!("analyzer should only allow _ in patterns"),
     }
   }
-}
 
-impl<'ctx> Visitor for Emitter<'ctx> {
-  fn enter_module(&mut self, _node: &ModuleNode) {
-    let entry_block = self
-      .llvm_context
-      .append_basic_block(self.main_function, "entry");
+  /// Joins the parts of an interpolated string into a new string. Parts
+  /// that aren't strings are converted with their `to_string` method.
+  fn compile_interpolation(&mut self, parts: &[ExprNode]) -> BasicValueEnum<'ctx> {
+    let mut strings = Vec::with_capacity(parts.len());
+
+    for part in parts {
+      // The parser adds empty literals around holes at either end
+      if let ExprKind::Literal(LiteralNode {
+        kind: LiteralKind::Str(value),
+        ..
+      }) = &part.kind
+      {
+        if value.is_empty() {
+          continue;
+        }
+      }
 
-    self.llvm_builder.position_at_end(entry_block);
+      let value = self.compile_expr(part);
+
+      let string = if part.typ == ValueType::String {
+        value
+      } else {
+        let function_name = mangle::method_name(&part.typ, &["to_string".to_owned()]);
+        let string = self.build_call_by_name(&function_name, &[value, self.nothing_value()]);
+
+        self.release_value(value, &part.typ);
+
+        string
+      };
+
+      strings.push(string.into_pointer_value());
+    }
+
+    let i64_type = self.llvm_context.i64_type();
+    let mut length = i64_type.const_zero();
+
+    for string in &strings {
+      let part_length = self
+        .runtime
+        .build_string_length(&self.llvm_builder, *string);
+
+      length = self
+        .llvm_builder
+        .build_int_add(length, part_length, "length");
+    }
+
+    let result = self
+      .llvm_builder
+      .build_call(self.runtime.string_alloc, &[length.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let bytes = self.runtime.build_string_bytes(&self.llvm_builder, result);
+
+    let memcpy = self.get_or_declare_c_function(
+      "memcpy",
+      Some(self.opaque_ptr_type().into()),
+      &[
+        self.opaque_ptr_type().into(),
+        self.opaque_ptr_type().into(),
+        i64_type.into(),
+      ],
+    );
+
+    let mut offset = i64_type.const_zero();
+
+    for string in strings {
+      let part_length = self.runtime.build_string_length(&self.llvm_builder, string);
+      let part_bytes = self.runtime.build_string_bytes(&self.llvm_builder, string);
+      let destination = unsafe { self.llvm_builder.build_gep(bytes, &[offset], "part") };
+
+      self.llvm_builder.build_call(
+        memcpy,
+        &[destination.into(), part_bytes.into(), part_length.into()],
+        "",
+      );
+
+      offset = self
+        .llvm_builder
+        .build_int_add(offset, part_length, "offset");
+
+      self.release_value(string.into(), &ValueType::String);
+    }
+
+    result.into()
   }
 
-  fn leave_module(&mut self, _node: &ModuleNode) {
-    let main_block = self.main_function.get_last_basic_block().unwrap();
+  /// Builds a new list, and moves each element's value into it.
+  fn compile_list(
+    &mut self,
+    elements: &[ExprNode],
+    element_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let element_size = self.element_size(element_type, elements.is_empty());
+    let element_drop = self.drop_function(element_type);
+
+    let list = self.build_runtime_call(
+      self.runtime.list_new,
+      &[element_size.into(), element_drop.into()],
+      "list",
+    );
+
+    for element in elements {
+      let value = self.compile_expr(element);
+      let slot = self.build_runtime_call(self.runtime.list_push_slot, &[list], "slot");
+
+      self.store_element(slot, value);
+    }
 
-    self.llvm_builder.position_at_end(main_block);
+    list
+  }
 
-    let default_return_value = self.llvm_context.i32_type().const_int(47, true);
+  /// Builds a new dict, and moves each entry's key and value into it.
+  fn compile_dict(
+    &mut self,
+    entries: &[(ExprNode, ExprNode)],
+    entry_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let key_functions = self.key_functions(&collection_key_type(entry_type));
+
+    let entry_size = self.element_size(entry_type, entries.is_empty());
+    let entry_drop = self.drop_function(entry_type);
+
+    let dict = self.build_runtime_call(
+      self.runtime.dict_new,
+      &[
+        entry_size.into(),
+        entry_drop.into(),
+        key_functions
+          .hash
+          .as_global_value()
+          .as_pointer_value()
+          .into(),
+        key_functions
+          .equals
+          .as_global_value()
+          .as_pointer_value()
+          .into(),
+      ],
+      "dict",
+    );
+
+    for (key, value) in entries {
+      let key = self.compile_expr(key);
+      let value = self.compile_expr(value);
+
+      self.build_dict_insert(dict, key, value, entry_type);
+    }
 
-    self.llvm_builder.build_return(Some(&default_return_value));
+    dict
   }
 
-  fn enter_top_level_statement(&mut self, node: &TopLevelStatementNode) {
-    match &node.kind {
-      TopLevelStatementKind::IntrinsicDef(def) => match &def.kind {
-        DefKind::Function { signature } => {
-          let name = signature.first().unwrap().0.name.clone();
-          self.build_intrinsic_function(name);
-        }
+  /// Lists and dicts are generic, so their methods are built at each call,
+  /// for the receiver's element types.
+  fn compile_collection_method(
+    &mut self,
+    receiver: &ExprNode,
+    method_parts: &[IdentifierNode],
+    args: &[ExprNode],
+    pos: Position,
+  ) -> BasicValueEnum<'ctx> {
+    let collection = self.compile_expr(receiver);
+    let element_type = collection_element_type(&receiver.typ);
+
+    let type_name = match &receiver.typ {
+      ValueType::Generic(name, _) => name.as_str(),
+      _ => unreachable!(),
+    };
 
-        _ => {}
-      },
+    let name_parts = identifier_names(method_parts);
+    let name_parts = name_parts.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    let result = match (type_name, &name_parts[..]) {
+      (_, ["length"]) => self
+        .runtime
+        .build_length(&self.llvm_builder, collection.into_pointer_value())
+        .into(),
+
+      ("List", ["get"]) => {
+        let index = self.compile_expr(&args[0]).into_int_value();
+
+        let slot =
+          self.build_runtime_call(self.runtime.list_get, &[collection, index.into()], "slot");
+
+        let is_out_of_bounds = self
+          .llvm_builder
+          .build_is_null(slot.into_pointer_value(), "is_out_of_bounds");
+        self.build_panic_if(is_out_of_bounds, "list index out of bounds", pos);
+
+        let element = self.load_element(slot, &element_type);
+
+        // The list keeps its reference, and the caller gets another
+        self.retain_value(element, &element_type);
+
+        element
+      }
+
+      ("List", ["push"]) => {
+        let value = self.compile_expr(&args[0]);
+        let slot = self.build_runtime_call(self.runtime.list_push_slot, &[collection], "slot");
+
+        // The list takes the value's reference
+        self.store_element(slot, value);
+
+        self.nothing_value()
+      }
+
+      ("Dict", ["get"]) => {
+        let key_type = collection_key_type(&element_type);
+        let value_type = collection_value_type(&element_type);
+
+        let key = self.compile_expr(&args[0]);
+        let key_ptr = self.build_key_ptr(key);
+
+        let entry = self.build_runtime_call(self.runtime.dict_get, &[collection, key_ptr], "entry");
+
+        let is_missing = self
+          .llvm_builder
+          .build_is_null(entry.into_pointer_value(), "is_missing");
+        self.build_panic_if(is_missing, "key not found in dict", pos);
+
+        let entry = self.load_element(entry, &element_type);
+        let value = self.extract_tuple_entry(entry, 1);
+
+        self.retain_value(value, &value_type);
+        self.release_value(key, &key_type);
+
+        value
+      }
+
+      ("Dict", ["insert", "value"]) => {
+        let key = self.compile_expr(&args[0]);
+        let value = self.compile_expr(&args[1]);
+
+        self.build_dict_insert(collection, key, value, &element_type);
+
+        self.nothing_value()
+      }
+
+      (_, ["each"]) => {
+        let closure = self.compile_expr(&args[0]);
+
+        self.build_each(collection.into_pointer_value(), &element_type, closure);
+        self.release_value(closure, &args[0].typ);
+
+        self.nothing_value()
+      }
+
+      _ => unreachable!("analyzer should reject undefined methods"),
+    };
+
+    self.release_value(collection, &receiver.typ);
+
+    result
+  }
+
+  /// Regex methods are also built at each call, since the type that
+  /// `captures` returns depends on the regex's named captures.
+  fn compile_regex_method(
+    &mut self,
+    receiver: &ExprNode,
+    method_parts: &[IdentifierNode],
+    args: &[ExprNode],
+  ) -> BasicValueEnum<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let regex = self.compile_expr(receiver);
+    let string = self.compile_expr(&args[0]);
+
+    let captures_type = match &receiver.typ {
+      ValueType::Generic(_, type_args) => type_args[0].clone(),
+      _ => unreachable!(),
+    };
+
+    let capture_count = match &captures_type {
+      ValueType::LabeledTuple(entries) => entries.len(),
+      _ => 0,
+    };
+
+    // The start and end of the whole match, then of each capture
+    let slot_count = 2 * (capture_count + 1);
+    let slots = self.allocate("slots", i64_type.array_type(slot_count as u32).into());
+    let slots = self.llvm_builder.build_pointer_cast(
+      slots,
+      i64_type.ptr_type(AddressSpace::Generic),
+      "slots",
+    );
+
+    let name_parts = identifier_names(method_parts);
+    let name_parts = name_parts.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    // Only `matches` has to match the whole string
+    let anchored = self
+      .llvm_context
+      .bool_type()
+      .const_int((name_parts == ["matches"]) as u64, false);
+
+    let found = self
+      .build_runtime_call(
+        self.runtime.regex_search,
+        &[regex, string, anchored.into(), slots.into()],
+        "found",
+      )
+      .into_int_value();
+
+    let result = match &name_parts[..] {
+      ["matches"] => self.build_bool(found),
+
+      ["find"] => self.build_optional_list(found, &ValueType::String, |emitter| {
+        emitter.build_capture_string(string, slots, 0)
+      }),
+
+      ["captures"] => self.build_optional_list(found, &captures_type, |emitter| {
+        let captures = (0..capture_count)
+          .map(|i| emitter.build_capture_string(string, slots, i + 1))
+          .collect();
+
+        emitter.compile_tuple(&captures_type, captures)
+      }),
+
+      _ => unreachable!("analyzer should reject undefined methods"),
+    };
+
+    self.release_value(string, &ValueType::String);
+    self.release_value(regex, &receiver.typ);
+
+    result
+  }
+
+  /// The part of a string that a regex match (index 0) or one of its
+  /// captures matched, as a new string. Captures that didn't take part in
+  /// the match are empty. Also used for the holes in string patterns,
+  /// whose bounds are laid out like captures.
+  fn build_capture_string(
+    &self,
+    string: BasicValueEnum<'ctx>,
+    slots: PointerValue<'ctx>,
+    index: usize,
+  ) -> BasicValueEnum<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let load_slot = |slot: usize| {
+      let slot_ptr = unsafe {
+        self
+          .llvm_builder
+          .build_gep(slots, &[i64_type.const_int(slot as u64, false)], "slot")
+      };
+
+      self
+        .llvm_builder
+        .build_load(slot_ptr, "position")
+        .into_int_value()
+    };
+
+    let start = load_slot(2 * index);
+    let end = load_slot(2 * index + 1);
+
+    let is_unmatched = self.llvm_builder.build_int_compare(
+      IntPredicate::SLT,
+      start,
+      i64_type.const_zero(),
+      "is_unmatched",
+    );
+
+    let start = self
+      .llvm_builder
+      .build_select(is_unmatched, i64_type.const_zero(), start, "start");
+    let end = self
+      .llvm_builder
+      .build_select(is_unmatched, i64_type.const_zero(), end, "end");
+
+    self.build_runtime_call(self.runtime.string_slice, &[string, start, end], "capture")
+  }
+
+  /// Converts an `i1` into a value of the prelude's Bool enum.
+  fn build_bool(&self, value: IntValue<'ctx>) -> BasicValueEnum<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let bool_type = ValueType::Named("Bool".to_owned());
+
+    let tag_of = |name| {
+      let (tag, _) = self
+        .layouts
+        .get_variant(&bool_type, name)
+        .expect("Bool should be defined by the prelude");
+
+      i32_type.const_int(tag as u64, false)
+    };
+
+    let tag = self
+      .llvm_builder
+      .build_select(value, tag_of("True"), tag_of("False"), "tag");
+
+    self.build_struct(
+      self.enum_type(),
+      vec![tag, self.opaque_ptr_type().const_null().into()],
+    )
+  }
+
+  /// Builds a list that holds the value built by `element` if `is_present`
+  /// is true, and is empty otherwise.
+  fn build_optional_list(
+    &mut self,
+    is_present: IntValue<'ctx>,
+    element_type: &ValueType,
+    element: impl FnOnce(&mut Self) -> BasicValueEnum<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    let function = self.current_function();
+    let present_block = self.llvm_context.append_basic_block(function, "present");
+    let done_block = self
+      .llvm_context
+      .append_basic_block(function, "present_done");
+
+    let element_size = self.element_size(element_type, false);
+    let element_drop = self.drop_function(element_type);
+
+    let list = self.build_runtime_call(
+      self.runtime.list_new,
+      &[element_size.into(), element_drop.into()],
+      "list",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_present, present_block, done_block);
+
+    self.llvm_builder.position_at_end(present_block);
+
+    let value = element(self);
+    let slot = self.build_runtime_call(self.runtime.list_push_slot, &[list], "slot");
+
+    self.store_element(slot, value);
+    self.llvm_builder.build_unconditional_branch(done_block);
+
+    self.llvm_builder.position_at_end(done_block);
+
+    list
+  }
+
+  /// Inserts an entry into a dict, which takes the key's and the value's
+  /// references. An existing entry with the same key is replaced.
+  fn build_dict_insert(
+    &self,
+    dict: BasicValueEnum<'ctx>,
+    key: BasicValueEnum<'ctx>,
+    value: BasicValueEnum<'ctx>,
+    entry_type: &ValueType,
+  ) {
+    let key_ptr = self.build_key_ptr(key);
+
+    let slot = self.build_runtime_call(self.runtime.dict_insert_slot, &[dict, key_ptr], "slot");
+    let entry = self.compile_tuple(entry_type, vec![key, value]);
+
+    self.store_element(slot, entry);
+  }
+
+  /// Calls a closure with each element of a list (or entry of a dict), in
+  /// order. The closure borrows them.
+  fn build_each(
+    &self,
+    collection: PointerValue<'ctx>,
+    element_type: &ValueType,
+    closure: BasicValueEnum<'ctx>,
+  ) {
+    let i64_type = self.llvm_context.i64_type();
+    let function = self.current_function();
+
+    let check_block = self.llvm_context.append_basic_block(function, "each_check");
+    let body_block = self.llvm_context.append_basic_block(function, "each_body");
+    let done_block = self.llvm_context.append_basic_block(function, "each_done");
+
+    let index_ptr = self.allocate("index", i64_type.into());
+
+    self
+      .llvm_builder
+      .build_store(index_ptr, i64_type.const_zero());
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(check_block);
+
+    let index = self
+      .llvm_builder
+      .build_load(index_ptr, "index")
+      .into_int_value();
+
+    // The closure might add elements, so check the length every time
+    let length = self.runtime.build_length(&self.llvm_builder, collection);
+
+    let is_in_range =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULT, index, length, "is_in_range");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_in_range, body_block, done_block);
+
+    self.llvm_builder.position_at_end(body_block);
+
+    // A dict's entries are stored just like a list's elements
+    let slot = self.build_runtime_call(
+      self.runtime.list_get,
+      &[collection.into(), index.into()],
+      "slot",
+    );
+
+    let element = self.load_element(slot, element_type);
+    self.call_closure(closure, &[element]);
+
+    let index = self
+      .llvm_builder
+      .build_int_add(index, i64_type.const_int(1, false), "index");
+
+    self.llvm_builder.build_store(index_ptr, index);
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(done_block);
+  }
+
+  /// The size of each element in a collection's buffer. Empty literals
+  /// have unknown element types, but they don't store anything either.
+  fn element_size(&self, element_type: &ValueType, is_empty: bool) -> IntValue<'ctx> {
+    if is_empty {
+      return self.llvm_context.i64_type().const_zero();
+    }
+
+    self
+      .llvm_type(element_type)
+      .size_of()
+      .expect("element type should be sized")
+  }
+
+  fn key_functions(&self, key_type: &ValueType) -> KeyFunctions<'ctx> {
+    match key_type {
+      ValueType::Float => self.runtime.float_key,
+      ValueType::String => self.runtime.string_key,
+      ValueType::Sized(sized_type) => match sized_type.bits() {
+        8 => self.runtime.bits8_key,
+        16 => self.runtime.bits16_key,
+        32 => self.runtime.bits32_key,
+        _ => self.runtime.int_key,
+      },
+      _ => self.runtime.int_key,
+    }
+  }
+
+  /// Dict keys are passed to the runtime by pointer.
+  fn build_key_ptr(&self, key: BasicValueEnum<'ctx>) -> BasicValueEnum<'ctx> {
+    let key_ptr = self.allocate("key", key.get_type());
+    self.llvm_builder.build_store(key_ptr, key);
+
+    self
+      .llvm_builder
+      .build_pointer_cast(key_ptr, self.opaque_ptr_type(), "key")
+      .into()
+  }
+
+  fn store_element(&self, slot: BasicValueEnum<'ctx>, value: BasicValueEnum<'ctx>) {
+    let slot = self.llvm_builder.build_pointer_cast(
+      slot.into_pointer_value(),
+      value.get_type().ptr_type(AddressSpace::Generic),
+      "slot",
+    );
+
+    self.llvm_builder.build_store(slot, value);
+  }
+
+  fn load_element(
+    &self,
+    slot: BasicValueEnum<'ctx>,
+    element_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let slot = self.llvm_builder.build_pointer_cast(
+      slot.into_pointer_value(),
+      self.llvm_type(element_type).ptr_type(AddressSpace::Generic),
+      "slot",
+    );
+
+    self.llvm_builder.build_load(slot, "element")
+  }
+
+  fn build_runtime_call(
+    &self,
+    function: FunctionValue<'ctx>,
+    args: &[BasicValueEnum<'ctx>],
+    name: &str,
+  ) -> BasicValueEnum<'ctx> {
+    self
+      .llvm_builder
+      .build_call(function, args, name)
+      .try_as_basic_value()
+      .left()
+      .expect("runtime function should return a value")
+  }
+
+  fn compile_tuple(
+    &self,
+    typ: &ValueType,
+    entry_values: Vec<BasicValueEnum<'ctx>>,
+  ) -> BasicValueEnum<'ctx> {
+    self.build_struct(self.llvm_type(typ).into_struct_type(), entry_values)
+  }
+
+  fn build_struct(
+    &self,
+    struct_type: StructType<'ctx>,
+    field_values: Vec<BasicValueEnum<'ctx>>,
+  ) -> BasicValueEnum<'ctx> {
+    let mut value: AggregateValueEnum = struct_type.get_undef().into();
+
+    for (i, field_value) in field_values.into_iter().enumerate() {
+      value = self
+        .llvm_builder
+        .build_insert_value(value, field_value, i as u32, "struct")
+        .expect("field index should be in bounds");
+    }
+
+    value.into_struct_value().into()
+  }
+
+  fn build_enum_value(
+    &mut self,
+    tag: u32,
+    payload: Option<(BasicValueEnum<'ctx>, &ValueType)>,
+  ) -> BasicValueEnum<'ctx> {
+    let payload_ptr = match payload {
+      Some((payload, payload_type)) => self.build_object(payload, payload_type),
+
+      None => self.opaque_ptr_type().const_null(),
+    };
+
+    let tag = self.llvm_context.i32_type().const_int(tag as u64, false);
+
+    self.build_struct(self.enum_type(), vec![tag.into(), payload_ptr.into()])
+  }
+
+  fn enum_tag(&self, value: BasicValueEnum<'ctx>) -> IntValue<'ctx> {
+    self.extract_tuple_entry(value, 0).into_int_value()
+  }
+
+  fn enum_payload(
+    &self,
+    value: BasicValueEnum<'ctx>,
+    payload_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let payload_ptr = self.extract_tuple_entry(value, 1).into_pointer_value();
+
+    let payload_ptr = self.llvm_builder.build_pointer_cast(
+      payload_ptr,
+      self.llvm_type(payload_type).ptr_type(AddressSpace::Generic),
+      "payload",
+    );
+
+    self.llvm_builder.build_load(payload_ptr, "payload")
+  }
+
+  /// Allocates a record for a struct and initializes it from the value
+  /// passed to the struct's constructor.
+  fn build_struct_value(
+    &mut self,
+    inner_type: &ValueType,
+    value: BasicValueEnum<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    let record_type = self.record_type(inner_type);
+
+    let (record, record_payload_type) = if is_tuple_struct(inner_type) {
+      (value, inner_type.clone())
+    } else {
+      (
+        self.build_struct(record_type, vec![value]),
+        ValueType::UnlabeledTuple(vec![inner_type.clone()]),
+      )
+    };
+
+    let record_ptr = self.build_object(record, &record_payload_type);
+
+    self
+      .llvm_builder
+      .build_pointer_cast(
+        record_ptr,
+        record_type.ptr_type(AddressSpace::Generic),
+        "record",
+      )
+      .into()
+  }
+
+  /// Allocates a heap object, and moves `payload` into it. Returns a
+  /// pointer to the object's payload.
+  fn build_object(
+    &mut self,
+    payload: BasicValueEnum<'ctx>,
+    payload_type: &ValueType,
+  ) -> PointerValue<'ctx> {
+    let size = self
+      .target_machine
+      .get_target_data()
+      .get_store_size(&payload.get_type());
+
+    let size = self.llvm_context.i64_type().const_int(size, false);
+    let drop_fn = self.drop_function(payload_type);
+
+    let object = self
+      .llvm_builder
+      .build_call(self.runtime.alloc, &[size.into(), drop_fn.into()], "object")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let payload_ptr = self.llvm_builder.build_pointer_cast(
+      object,
+      payload.get_type().ptr_type(AddressSpace::Generic),
+      "payload",
+    );
+
+    self.llvm_builder.build_store(payload_ptr, payload);
+
+    object
+  }
+
+  /// Gets a pointer to the function that releases the values inside an
+  /// object with this payload type. Payloads that don't hold any heap
+  /// objects have no drop function, so this is null for them.
+  fn drop_function(&mut self, payload_type: &ValueType) -> PointerValue<'ctx> {
+    let drop_fn_type = self.runtime.drop_fn_type();
+
+    if !self.is_managed(payload_type) {
+      return drop_fn_type.ptr_type(AddressSpace::Generic).const_null();
+    }
+
+    let name = mangle::drop_name(payload_type);
+
+    let function = match self.llvm_module.get_function(&name) {
+      Some(function) => function,
+
+      None => {
+        let function = self
+          .llvm_module
+          .add_function(&name, drop_fn_type, Some(Linkage::Internal));
+
+        self.pending_drops.push((function, payload_type.clone()));
+
+        function
+      }
+    };
+
+    function.as_global_value().as_pointer_value()
+  }
+
+  fn build_pending_drop_functions(&mut self) {
+    while let Some((function, payload_type)) = self.pending_drops.pop() {
+      let entry = self.llvm_context.append_basic_block(function, "entry");
+      self.llvm_builder.position_at_end(entry);
+
+      let payload_ptr = self.llvm_builder.build_pointer_cast(
+        function.get_first_param().unwrap().into_pointer_value(),
+        self
+          .llvm_type(&payload_type)
+          .ptr_type(AddressSpace::Generic),
+        "payload",
+      );
+
+      let payload = self.llvm_builder.build_load(payload_ptr, "payload");
+
+      self.release_value(payload, &payload_type);
+      self.llvm_builder.build_return(None);
+    }
+  }
+
+  /// Whether values of this type refer to heap objects, which have to be
+  /// retained and released.
+  fn is_managed(&self, typ: &ValueType) -> bool {
+    match typ {
+      ValueType::String | ValueType::Func(..) => true,
+
+      ValueType::UnlabeledTuple(entry_types) => entry_types
+        .iter()
+        .any(|entry_type| self.is_managed(entry_type)),
+
+      ValueType::LabeledTuple(entries) => entries
+        .iter()
+        .any(|(_, entry_type)| self.is_managed(entry_type)),
+
+      ValueType::Named(..) | ValueType::Generic(..) => match self.layouts.get_type(typ) {
+        Some(TypeLayout::Enum { variants }) => variants
+          .iter()
+          .any(|variant| variant.payload_type.is_some()),
+
+        Some(TypeLayout::Struct { .. }) => true,
+
+        // Lists and dicts are objects
+        None => matches!(typ, ValueType::Generic(..)),
+      },
+
+      _ => false,
+    }
+  }
+
+  fn retain_value(&self, value: BasicValueEnum<'ctx>, typ: &ValueType) {
+    self.call_on_objects(self.runtime.retain, value, typ);
+  }
+
+  fn release_value(&self, value: BasicValueEnum<'ctx>, typ: &ValueType) {
+    self.call_on_objects(self.runtime.release, value, typ);
+  }
+
+  /// Calls a runtime function with each heap object that a value refers
+  /// to directly (i.e. not through another heap object).
+  fn call_on_objects(
+    &self,
+    function: FunctionValue<'ctx>,
+    value: BasicValueEnum<'ctx>,
+    typ: &ValueType,
+  ) {
+    if !self.is_managed(typ) {
+      return;
+    }
+
+    let object = match typ {
+      ValueType::String => value.into_pointer_value(),
+
+      // Closures own their environment
+      ValueType::Func(..) => self.extract_tuple_entry(value, 1).into_pointer_value(),
+
+      ValueType::UnlabeledTuple(entry_types) => {
+        for (i, entry_type) in entry_types.iter().enumerate() {
+          let entry = self.extract_tuple_entry(value, i);
+          self.call_on_objects(function, entry, entry_type);
+        }
+
+        return;
+      }
+
+      ValueType::LabeledTuple(entries) => {
+        for (i, (_, entry_type)) in entries.iter().enumerate() {
+          let entry = self.extract_tuple_entry(value, i);
+          self.call_on_objects(function, entry, entry_type);
+        }
+
+        return;
+      }
+
+      _ => match self.layouts.get_type(typ) {
+        Some(TypeLayout::Enum { .. }) => self.extract_tuple_entry(value, 1).into_pointer_value(),
+
+        _ => self.llvm_builder.build_pointer_cast(
+          value.into_pointer_value(),
+          self.opaque_ptr_type(),
+          "object",
+        ),
+      },
+    };
+
+    self.llvm_builder.build_call(function, &[object.into()], "");
+  }
+
+  /// Releases the variables in the current scope, before it's exited.
+  fn release_scope_variables(&self) {
+    for (ptr, typ) in self.scope.current_variables() {
+      let value = self.llvm_builder.build_load(ptr, "var");
+
+      self.release_value(value, &typ);
+    }
+  }
+
+  /// The inverse of `build_struct_value`: gets the value that a struct was
+  /// constructed from.
+  fn struct_payload(
+    &self,
+    value: BasicValueEnum<'ctx>,
+    inner_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let record = self
+      .llvm_builder
+      .build_load(value.into_pointer_value(), "record");
+
+    if is_tuple_struct(inner_type) {
+      record
+    } else {
+      self.extract_tuple_entry(record, 0)
+    }
+  }
+
+  fn compile_field_access(
+    &mut self,
+    receiver: &ExprNode,
+    field_name: &str,
+    typ: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let inner_type = self.struct_inner_type(&receiver.typ);
+
+    let index = struct_fields(&inner_type)
+      .iter()
+      .position(|(name, _)| name == field_name)
+      .expect("analyzer should reject unknown fields");
+
+    let record = self.compile_expr(receiver);
+
+    let field_ptr = self
+      .llvm_builder
+      .build_struct_gep(record.into_pointer_value(), index as u32, field_name)
+      .expect("field index should be in bounds");
+
+    let value = self.llvm_builder.build_load(field_ptr, field_name);
+
+    self.retain_value(value, typ);
+    self.release_value(record, &receiver.typ);
+
+    value
+  }
+
+  /// Whether a method access (e.g. `person.name`) is really a field of a
+  /// struct, which is how an uncalled field access is parsed.
+  fn is_field_access(&self, receiver: &ExprNode, method_parts: &[IdentifierNode]) -> bool {
+    match (self.layouts.get_type(&receiver.typ), method_parts) {
+      (Some(TypeLayout::Struct { inner_type }), [part]) => struct_fields(&inner_type)
+        .iter()
+        .any(|(name, _)| *name == part.name),
+      _ => false,
+    }
+  }
+
+  /// Defs and methods can only be called, so one that's used as a value
+  /// (e.g. `let f = person.greet`) is reported once every module has been
+  /// emitted. Its value is a placeholder until then.
+  fn unsupported_function_value(
+    &self,
+    name_parts: &[String],
+    typ: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let name = name_parts.join(" ");
+    let mut unsupported_function_values = self.unsupported_function_values.borrow_mut();
+
+    if !unsupported_function_values.contains(&name) {
+      unsupported_function_values.push(name);
+    }
+
+    const_zero(self.llvm_type(typ))
+  }
+
+  fn struct_inner_type(&self, typ: &ValueType) -> ValueType {
+    match self.layouts.get_type(typ) {
+      Some(TypeLayout::Struct { inner_type }) => inner_type,
+      _ => unreachable!("analyzer should only allow struct values here"),
+    }
+  }
+
+  /// Compiles a match expression by walking the decision tree built by
+  /// the match compiler. Each part of the subject is tested at most once,
+  /// and each case's body is compiled once, however many leaves lead to it.
+  fn compile_match(&mut self, node: &MatchNode, typ: &ValueType) -> BasicValueEnum<'ctx> {
+    let subject = self.compile_expr(&node.subject);
+
+    let compiled = MatchCompiler::new(&self.layouts).compile(&node.cases, &node.subject.typ);
+
+    let function = self.current_function();
+
+    // Unreachable cases get no block, since no leaf leads to them
+    let mut case_blocks = node.cases.iter().map(|_| None).collect::<Vec<_>>();
+
+    compiled.tree.for_each_case(&mut |case_index| {
+      if case_blocks[case_index].is_none() {
+        case_blocks[case_index] =
+          Some(self.llvm_context.append_basic_block(function, "match.case"));
+      }
+    });
+
+    let no_match_block = self.llvm_context.append_basic_block(function, "match.none");
+    let end_block = self.llvm_context.append_basic_block(function, "match.end");
+
+    // Bindings live on the stack, even in a top-level match
+    self.scope.enter();
+
+    let mut state = MatchState {
+      subject,
+      case_blocks,
+      case_slots: node.cases.iter().map(|_| None).collect(),
+      no_match_block,
+      hole_bounds: HashMap::new(),
+    };
+
+    self.compile_decision(&compiled.tree, &mut state, HashMap::new());
+
+    let mut incoming = Vec::with_capacity(node.cases.len());
+
+    for (i, case) in node.cases.iter().enumerate() {
+      let case_block = match state.case_blocks[i] {
+        Some(case_block) => case_block,
+        None => continue,
+      };
+
+      self.llvm_builder.position_at_end(case_block);
+
+      self.scope.enter();
+
+      // Most slots hold parts of the subject, which the bindings share.
+      // Others hold new values, which the bindings own.
+      for (name, ptr, typ, is_owned) in state.case_slots[i].take().unwrap_or_default() {
+        if !is_owned {
+          let value = self.llvm_builder.build_load(ptr, &name);
+          self.retain_value(value, &typ);
+        }
+
+        self.scope.add_variable(name, ptr, typ);
+      }
+
+      let value = self.compile_expr(&case.body);
+      let value = self.never_to_value(value, &case.body.typ, typ);
+
+      self.release_scope_variables();
+      self.scope.exit();
+
+      let case_end_block = self.llvm_builder.get_insert_block().unwrap();
+      self.llvm_builder.build_unconditional_branch(end_block);
+
+      incoming.push((value, case_end_block));
+    }
+
+    self.scope.exit();
+
+    self.llvm_builder.position_at_end(no_match_block);
+    self.build_panic("no case matched this value", node.pos);
+
+    self.llvm_builder.position_at_end(end_block);
+
+    let phi = self.llvm_builder.build_phi(self.llvm_type(typ), "match");
+
+    for (value, block) in &incoming {
+      phi.add_incoming(&[(value, *block)]);
+    }
+
+    self.release_value(subject, &node.subject.typ);
+
+    phi.as_basic_value()
+  }
+
+  /// Emits the code for one node of a match's decision tree, starting in
+  /// the builder's current block. `values` holds the parts of the subject
+  /// that have already been loaded on the way to this node.
+  fn compile_decision(
+    &mut self,
+    decision: &Decision,
+    state: &mut MatchState<'ctx>,
+    mut values: AccessValues<'ctx>,
+  ) {
+    match decision {
+      Decision::Success {
+        case_index,
+        bindings,
+      } => {
+        // Every leaf for a case binds the same names, so they share slots
+        if state.case_slots[*case_index].is_none() {
+          let slots = bindings
+            .iter()
+            .map(|binding| {
+              let ptr = self.allocate(&binding.name, self.llvm_type(&binding.access.typ));
+
+              // Holes in string patterns are bound to new strings
+              let is_owned = matches!(
+                binding.access.path.last(),
+                Some(AccessStep::InterpolationHole { .. })
+              );
+
+              (
+                binding.name.clone(),
+                ptr,
+                binding.access.typ.clone(),
+                is_owned,
+              )
+            })
+            .collect();
+
+          state.case_slots[*case_index] = Some(slots);
+        }
+
+        let slots = state.case_slots[*case_index].as_ref().unwrap().clone();
+
+        for (binding, (_, ptr, _, _)) in bindings.iter().zip(slots) {
+          let value = self.access_value(&binding.access.path, state, &mut values);
+
+          self.llvm_builder.build_store(ptr, value);
+        }
+
+        let case_block = state.case_blocks[*case_index].expect("success leaf should have a block");
+
+        self.llvm_builder.build_unconditional_branch(case_block);
+      }
+
+      Decision::Failure => {
+        self
+          .llvm_builder
+          .build_unconditional_branch(state.no_match_block);
+      }
+
+      Decision::Switch {
+        access,
+        branches,
+        fallback,
+      } => {
+        let function = self.current_function();
+        let value = self.access_value(&access.path, state, &mut values);
+
+        let fallback_block = match fallback {
+          Some(_) => self
+            .llvm_context
+            .append_basic_block(function, "match.fallback"),
+          None => state.no_match_block,
+        };
+
+        let branch_blocks = branches
+          .iter()
+          .map(|_| {
+            self
+              .llvm_context
+              .append_basic_block(function, "match.branch")
+          })
+          .collect::<Vec<BasicBlock>>();
+
+        match value {
+          BasicValueEnum::StructValue(..) | BasicValueEnum::IntValue(..) => {
+            // Variant tests switch on the tag, integer literals on the value
+            let (scrutinee, int_type) = match value {
+              BasicValueEnum::IntValue(int_value) => (int_value, int_value.get_type()),
+              _ => {
+                let tag = self.enum_tag(value);
+                (tag, tag.get_type())
+              }
+            };
+
+            let cases = branches
+              .iter()
+              .zip(&branch_blocks)
+              .map(|((test, _), block)| {
+                let case_value = match test {
+                  Test::Variant { tag, .. } => int_type.const_int(*tag as u64, false),
+                  Test::Literal(LiteralValue::Int(n)) => int_type.const_int(*n as u64, true),
+                  Test::Literal(..) | Test::Interpolation(..) => {
+                    unreachable!("analyzer should reject literal patterns of the wrong type")
+                  }
+                };
+
+                (case_value, *block)
+              })
+              .collect::<Vec<_>>();
+
+            self
+              .llvm_builder
+              .build_switch(scrutinee, fallback_block, &cases);
+          }
+
+          _ => {
+            // Other literals can't be switched on, so test them one by one
+            for (i, (test, _)) in branches.iter().enumerate() {
+              let matches = match test {
+                Test::Literal(literal) => self.build_literal_comparison(literal, value),
+                Test::Interpolation(literals) => {
+                  self.build_string_pattern_test(literals, &access.path, value, state)
+                }
+                Test::Variant { .. } => {
+                  unreachable!("analyzer should only allow variants of enums")
+                }
+              };
+
+              let next_block = if i + 1 < branches.len() {
+                self.llvm_context.append_basic_block(function, "match.test")
+              } else {
+                fallback_block
+              };
+
+              self
+                .llvm_builder
+                .build_conditional_branch(matches, branch_blocks[i], next_block);
+
+              self.llvm_builder.position_at_end(next_block);
+            }
+          }
+        }
+
+        // Values loaded before the switch can be reused in every branch,
+        // but values loaded in one branch aren't available in the others
+        for ((_, branch), block) in branches.iter().zip(branch_blocks) {
+          self.llvm_builder.position_at_end(block);
+          self.compile_decision(branch, state, values.clone());
+        }
+
+        if let Some(fallback) = fallback {
+          self.llvm_builder.position_at_end(fallback_block);
+          self.compile_decision(fallback, state, values);
+        }
+      }
+    }
+  }
+
+  /// Gets the part of a match's subject at the end of `path`, loading it
+  /// (and any parts of the subject on the way to it) if needed.
+  fn access_value(
+    &self,
+    path: &[AccessStep],
+    state: &MatchState<'ctx>,
+    values: &mut AccessValues<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    let (last_step, parent_path) = match path.split_last() {
+      Some(split) => split,
+      None => return state.subject,
+    };
+
+    if let Some(value) = values.get(path) {
+      return *value;
+    }
+
+    let parent = self.access_value(parent_path, state, values);
+
+    let value = match last_step {
+      AccessStep::TupleEntry(i) => self.extract_tuple_entry(parent, *i),
+      AccessStep::VariantPayload(payload_type) => self.enum_payload(parent, payload_type),
+      AccessStep::StructPayload(inner_type) => self.struct_payload(parent, inner_type),
+      AccessStep::InterpolationHole { literals, index } => {
+        let bounds = state.hole_bounds[&(parent_path.to_vec(), literals.clone())];
+
+        self.build_capture_string(parent, bounds, *index)
+      }
+    };
+
+    values.insert(path.to_vec(), value);
+
+    value
+  }
+
+  /// Tests a string against the literals of a string pattern. If it
+  /// matches, the bounds of the pattern's holes are kept for its bindings.
+  fn build_string_pattern_test(
+    &self,
+    literals: &[String],
+    path: &[AccessStep],
+    value: BasicValueEnum<'ctx>,
+    state: &mut MatchState<'ctx>,
+  ) -> IntValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let literal_strings = literals
+      .iter()
+      .map(|literal| self.build_static_string(literal))
+      .collect::<Vec<_>>();
+
+    let pattern = self.build_static_object(
+      self.runtime.const_string_pattern_payload(&literal_strings),
+      "pattern",
+    );
+
+    let bounds = *state
+      .hole_bounds
+      .entry((path.to_vec(), literals.to_vec()))
+      .or_insert_with(|| {
+        let bound_count = 2 * (literals.len() - 1);
+        let bounds = self.allocate("bounds", i64_type.array_type(bound_count as u32).into());
+
+        self.llvm_builder.build_pointer_cast(
+          bounds,
+          i64_type.ptr_type(AddressSpace::Generic),
+          "bounds",
+        )
+      });
+
+    self
+      .build_runtime_call(
+        self.runtime.string_pattern_match,
+        &[pattern.into(), value, bounds.into()],
+        "matches",
+      )
+      .into_int_value()
+  }
+
+  fn build_literal_comparison(
+    &mut self,
+    literal: &LiteralValue,
+    value: BasicValueEnum<'ctx>,
+  ) -> IntValue<'ctx> {
+    match (literal, value) {
+      (LiteralValue::Int(expected), BasicValueEnum::IntValue(actual)) => {
+        let expected = actual.get_type().const_int(*expected as u64, true);
+
+        self
+          .llvm_builder
+          .build_int_compare(IntPredicate::EQ, actual, expected, "literal")
+      }
+
+      (LiteralValue::Float(expected), BasicValueEnum::FloatValue(actual)) => {
+        let expected = actual.get_type().const_float(*expected);
+
+        self
+          .llvm_builder
+          .build_float_compare(FloatPredicate::OEQ, actual, expected, "literal")
+      }
+
+      (LiteralValue::String(expected), BasicValueEnum::PointerValue(actual)) => {
+        let expected = self.build_static_string(expected);
+
+        self
+          .llvm_builder
+          .build_call(
+            self.runtime.string_equals,
+            &[actual.into(), expected.into()],
+            "literal",
+          )
+          .try_as_basic_value()
+          .left()
+          .unwrap()
+          .into_int_value()
+      }
+
+      _ => unreachable!("analyzer should reject literal patterns of the wrong type"),
+    }
+  }
+
+  fn compile_let(&mut self, node: &LetNode) {
+    self.set_debug_location(node.pos);
+
+    let value = self.compile_expr(&node.value);
+
+    self.bind_pattern(&node.pattern, value, &node.value.typ);
+    self.release_value(value, &node.value.typ);
+  }
+
+  fn bind_pattern(&mut self, pattern: &PatternNode, value: BasicValueEnum<'ctx>, typ: &ValueType) {
+    match &pattern.kind {
+      PatternKind::Identifier(ident, _is_mutable) => {
+        // Enum variants don't bind anything
+        if self.layouts.get_variant(typ, &ident.name).is_none() {
+          let ptr = self.bind_variable(ident.name.clone(), value, typ);
+
+          self.declare_debug_variable(&ident.name, ptr, typ, ident.pos);
+        }
+      }
+
+      PatternKind::UnlabeledTuple(..) | PatternKind::LabeledTuple(..) => {
+        for (i, element_pattern, element_type) in tuple_element_patterns(pattern, typ) {
+          let element_value = self.extract_tuple_entry(value, i);
+
+          self.bind_pattern(element_pattern, element_value, element_type);
+        }
+      }
+
+      PatternKind::Constructor(ident, inner_pattern) => {
+        match self.layouts.get_variant(typ, &ident.name) {
+          Some((_, variant)) => {
+            let payload_type = variant
+              .payload_type
+              .clone()
+              .expect("analyzer should reject constructor patterns without a payload");
+
+            let payload = self.enum_payload(value, &payload_type);
+            self.bind_pattern(inner_pattern, payload, &payload_type);
+          }
+
+          None => {
+            let inner_type = self.struct_inner_type(typ);
+            let payload = self.struct_payload(value, &inner_type);
+
+            self.bind_pattern(inner_pattern, payload, &inner_type);
+          }
+        }
+      }
+
+      PatternKind::Underscore | PatternKind::Literal(..) | PatternKind::Interpolation(..) => {}
+    }
+  }
+
+  fn bind_variable(
+    &mut self,
+    name: String,
+    value: BasicValueEnum<'ctx>,
+    typ: &ValueType,
+  ) -> PointerValue<'ctx> {
+    let ptr = self.allocate(&name, self.llvm_type(typ));
+
+    // Variables own a reference to their value, until their scope exits
+    self.retain_value(value, typ);
+
+    self.llvm_builder.build_store(ptr, value);
+    self.scope.add_variable(name, ptr, typ.clone());
+
+    ptr
+  }
+
+  fn bind_params(
+    &mut self,
+    params: &[PatternNode],
+    values: &[BasicValueEnum<'ctx>],
+    param_types: &[ValueType],
+  ) {
+    for (i, param_type) in param_types.iter().enumerate() {
+      let value = values[i];
+
+      if params.is_empty() {
+        // Blocks without explicit params refer to them as $0, $1, etc.
+        self.bind_variable(format!("${}", i), value, param_type);
+      } else if let Some(pattern) = params.get(i) {
+        self.bind_pattern(pattern, value, param_type);
+      }
+    }
+  }
+
+  fn compile_block_body(
+    &mut self,
+    body: &[StatementNode],
+    return_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let mut result = (self.nothing_value(), &ValueType::Nothing);
+
+    for stmt in body {
+      match &stmt.kind {
+        StatementKind::Let(let_node) => self.compile_let(let_node),
+
+        StatementKind::Expr(expr) => {
+          // Only the last expression's value is used
+          self.release_value(result.0, result.1);
+
+          result = (self.compile_expr(expr), &expr.typ);
+        }
+      }
+    }
+
+    self.never_to_value(result.0, result.1, return_type)
+  }
+
+  /// Expressions of type Never leave the builder in a block that can't be
+  /// reached, where their "value" may still flow somewhere that expects a
+  /// value of another type. This gives it that type.
+  fn never_to_value(
+    &self,
+    value: BasicValueEnum<'ctx>,
+    typ: &ValueType,
+    expected_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    match typ {
+      ValueType::Never => const_zero(self.llvm_type(expected_type)),
+      _ => value,
+    }
+  }
+
+  /// Leaves the innermost block early with `value`, which it owns. Every
+  /// variable declared inside the block is released first.
+  fn build_block_return(&mut self, value: BasicValueEnum<'ctx>) -> BasicValueEnum<'ctx> {
+    let block_exit = *self
+      .block_exits
+      .last()
+      .expect("analyzer should only allow returns inside blocks");
+
+    let scope_depth = match block_exit {
+      BlockExit::Function { scope_depth, .. } | BlockExit::Loop { scope_depth, .. } => scope_depth,
+    };
+
+    for (ptr, typ) in self.scope.variables_since(scope_depth) {
+      let value = self.llvm_builder.build_load(ptr, "var");
+
+      self.release_value(value, &typ);
+    }
+
+    match block_exit {
+      BlockExit::Function { has_frame, .. } => {
+        if has_frame {
+          self
+            .llvm_builder
+            .build_call(self.runtime.frame_pop, &[], "");
+        }
+
+        self.llvm_builder.build_return(Some(&value));
+      }
+
+      BlockExit::Loop {
+        body_block,
+        exit_block,
+        ..
+      } => self.build_loop_branch(value, body_block, exit_block),
+    }
+
+    // Whatever comes next can't be reached, but it still needs a block
+    let function = self.current_function();
+    let after_block = self
+      .llvm_context
+      .append_basic_block(function, "return.after");
+    self.llvm_builder.position_at_end(after_block);
+
+    self.nothing_value()
+  }
+
+  /// Compiles a call to `loop` whose block is written inline. The block's
+  /// body is compiled into the loop itself rather than into a closure, so
+  /// it can assign the surrounding function's variables.
+  fn compile_loop(&mut self, block: &BlockNode) -> BasicValueEnum<'ctx> {
+    let function = self.current_function();
+    let body_block = self.llvm_context.append_basic_block(function, "loop.body");
+    let exit_block = self.llvm_context.append_basic_block(function, "loop.exit");
+
+    self.llvm_builder.build_unconditional_branch(body_block);
+    self.llvm_builder.position_at_end(body_block);
+
+    self.block_exits.push(BlockExit::Loop {
+      scope_depth: self.scope.depth(),
+      body_block,
+      exit_block,
+    });
+
+    self.scope.enter();
+    self.bind_params(
+      &block.params,
+      &[self.nothing_value()],
+      &[ValueType::Nothing],
+    );
+
+    let result = self.compile_block_body(&block.body, &loop_result_type());
+
+    self.release_scope_variables();
+    self.scope.exit();
+    self.block_exits.pop();
+
+    self.build_loop_branch(result, body_block, exit_block);
+    self.llvm_builder.position_at_end(exit_block);
+
+    self.nothing_value()
+  }
+
+  /// Ends an iteration of a loop, given the `LoopResult` that its block
+  /// returned. `LoopBreak` leaves the loop, and `LoopContinue` goes back
+  /// to the start of its body.
+  fn build_loop_branch(
+    &self,
+    result: BasicValueEnum<'ctx>,
+    body_block: BasicBlock<'ctx>,
+    exit_block: BasicBlock<'ctx>,
+  ) {
+    let (break_tag, _) = self
+      .layouts
+      .get_variant(&loop_result_type(), "LoopBreak")
+      .expect("loop's block should return a LoopResult");
+
+    let is_break = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      self.enum_tag(result),
+      self
+        .llvm_context
+        .i32_type()
+        .const_int(break_tag as u64, false),
+      "is_break",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_break, exit_block, body_block);
+  }
+
+  fn declare_def(&self, kind: &DefKind, return_type: &Option<TypeExprNode>) -> FunctionValue<'ctx> {
+    let param_types = def_param_types(kind);
+
+    self.declare_function(&mangle::def_name(kind, &param_types), kind, return_type)
+  }
+
+  fn declare_function(
+    &self,
+    name: &str,
+    kind: &DefKind,
+    return_type: &Option<TypeExprNode>,
+  ) -> FunctionValue<'ctx> {
+    let param_types = def_param_types(kind);
+    let return_type = def_return_type(return_type);
+
+    let llvm_param_types = param_types
+      .iter()
+      .map(|param_type| self.llvm_type(param_type))
+      .collect::<Vec<BasicTypeEnum>>();
+
+    let fn_type = self
+      .llvm_type(&return_type)
+      .fn_type(&llvm_param_types, false);
+
+    self.llvm_module.add_function(name, fn_type, None)
+  }
+
+  fn is_declared(&self, kind: &DefKind) -> bool {
+    let param_types = def_param_types(kind);
+
+    self
+      .llvm_module
+      .get_function(&mangle::def_name(kind, &param_types))
+      .is_some()
+  }
+
+  fn compile_def(&mut self, node: &DefNode) {
+    let param_types = def_param_types(&node.kind);
+    let name = mangle::def_name(&node.kind, &param_types);
+
+    let function = self
+      .llvm_module
+      .get_function(&self.symbols.resolve(&name))
+      .expect("def should have been declared");
+
+    self.compile_def_body(node, function);
+  }
+
+  fn compile_def_body(&mut self, node: &DefNode, function: FunctionValue<'ctx>) {
+    let param_types = def_param_types(&node.kind);
+
+    let entry = self.llvm_context.append_basic_block(function, "entry");
+    self.llvm_builder.position_at_end(entry);
+
+    self.block_exits.push(BlockExit::Function {
+      scope_depth: self.scope.depth(),
+      has_frame: true,
+    });
+
+    self.scope.enter();
+    self.enter_debug_function(function, node.pos);
+
+    // Calls are recorded so that panics can print a backtrace
+    let frame_name = self
+      .llvm_builder
+      .build_global_string_ptr(&def_display_name(&node.kind), "frame_name");
+
+    self.llvm_builder.build_call(
+      self.runtime.frame_push,
+      &[frame_name.as_pointer_value().into()],
+      "",
+    );
+
+    self.bind_params(&node.block.params, &function.get_params(), &param_types);
+
+    let return_type = def_return_type(&node.return_type);
+    let return_value = self.compile_block_body(&node.block.body, &return_type);
+
+    self.block_exits.pop();
+    self.release_scope_variables();
+    self
+      .llvm_builder
+      .build_call(self.runtime.frame_pop, &[], "");
+    self.llvm_builder.build_return(Some(&return_value));
+
+    self.exit_debug_function();
+    self.scope.exit();
+  }
+
+  /// Compiles a generic def for the types that it's called with, unless
+  /// it's already been compiled for them. Returns the instance's name.
+  fn instantiate_generic_def(
+    &mut self,
+    name: &str,
+    arg_types: &[&ValueType],
+    return_type: &ValueType,
+    pos: Position,
+  ) -> String {
+    let generic_def = &self.generic_defs[name];
+    let (node, source_file, module) = (
+      generic_def.node.clone(),
+      generic_def.source_file.clone(),
+      generic_def.module.clone(),
+    );
+
+    let param_types = def_param_types(&node.kind);
+    let generic_return_type = def_return_type(&node.return_type);
+
+    let param_names = generics::generic_param_names(
+      &node.generic_type_constraints,
+      param_types.iter().chain(Some(&generic_return_type)),
+    );
+
+    let mut type_args = TypeArgs::new();
+
+    for (param_type, arg_type) in param_types.iter().zip(arg_types) {
+      generics::match_type_args(&param_names, param_type, arg_type, &mut type_args);
+    }
+
+    generics::match_type_args(
+      &param_names,
+      &generic_return_type,
+      return_type,
+      &mut type_args,
+    );
+
+    let instance_types = param_names
+      .iter()
+      .map(|param_name| match type_args.get(param_name) {
+        Some(type_arg) => type_arg.clone(),
+        None => ValueType::Named(param_name.clone()),
+      })
+      .collect::<Vec<ValueType>>();
+
+    let instance_name = mangle::instance_name(name, &instance_types);
+
+    if self.llvm_module.get_function(&instance_name).is_some() {
+      return instance_name;
+    }
+
+    let mut instance = (*node).clone();
+    instance.traverse_mut(&mut TypeSubstitution::new(&type_args));
+
+    // Declared first, so that the def can call itself
+    let function = self.declare_function(&instance_name, &instance.kind, &instance.return_type);
+
+    let outer_block = self
+      .llvm_builder
+      .get_insert_block()
+      .expect("builder should be positioned in a function");
+
+    // Like a closure's body, the instance only sees globals and its params.
+    // It can be compiled while another module's debug info is being
+    // recorded, so it doesn't get any.
+    let outer_locals = self.scope.take_locals();
+    let outer_source_file = std::mem::replace(&mut self.source_file, source_file);
+    let outer_module = self.symbols.enter_module(module);
+    let outer_debug_info = self.debug_info.take();
+
+    self.llvm_builder.unset_current_debug_location();
+    self.compile_def_body(&instance, function);
+
+    self.debug_info = outer_debug_info;
+    self.symbols.enter_module(outer_module);
+    self.source_file = outer_source_file;
+    self.scope.restore_locals(outer_locals);
+    self.llvm_builder.position_at_end(outer_block);
+    self.set_debug_location(pos);
+
+    instance_name
+  }
+
+  /// Compiles a block expression to a closure, i.e. a `{fn_ptr, env_ptr}`
+  /// pair. The local variables that the block refers to are copied into a
+  /// heap object (the environment), which is passed to the closure's
+  /// function as its first argument.
+  fn compile_closure(&mut self, block: &BlockNode, typ: &ValueType) -> BasicValueEnum<'ctx> {
+    let (param_types, return_type) = match typ {
+      ValueType::Func(param_types, return_type) => (param_types, return_type),
+      _ => unreachable!("analyzer should give blocks a function type"),
+    };
+
+    // Top-level variables are globals, so there's no need to capture them.
+    let captures = free_variables::referenced_names(block)
+      .into_iter()
+      .filter_map(|name| {
+        self
+          .scope
+          .get_local_variable(&name)
+          .map(|variable| (name, variable.ptr, variable.typ.clone()))
+      })
+      .collect::<Vec<(String, PointerValue<'ctx>, ValueType)>>();
+
+    let capture_types = captures
+      .iter()
+      .map(|(_, _, capture_type)| self.llvm_type(capture_type))
+      .collect::<Vec<BasicTypeEnum>>();
+
+    let env_type = self.llvm_context.struct_type(&capture_types, false);
+    let env_ptr = self.build_environment(env_type, &captures);
+
+    let function = self.llvm_module.add_function(
+      &mangle::closure_name(),
+      self.closure_fn_type(param_types, return_type),
+      Some(Linkage::Internal),
+    );
+
+    let outer_block = self
+      .llvm_builder
+      .get_insert_block()
+      .expect("builder should be positioned in a function");
+
+    // The closure's body only sees globals, its captures and its params.
+    let outer_locals = self.scope.take_locals();
+
+    self.block_exits.push(BlockExit::Function {
+      scope_depth: self.scope.depth(),
+      has_frame: false,
+    });
+
+    self.scope.enter();
+
+    let entry = self.llvm_context.append_basic_block(function, "entry");
+    self.llvm_builder.position_at_end(entry);
+
+    self.enter_debug_function(function, block.pos);
+
+    let params = function.get_params();
+
+    if !captures.is_empty() {
+      let env = self.llvm_builder.build_pointer_cast(
+        params[0].into_pointer_value(),
+        env_type.ptr_type(AddressSpace::Generic),
+        "env",
+      );
+
+      for (i, (name, _, capture_type)) in captures.iter().enumerate() {
+        let capture_ptr = self
+          .llvm_builder
+          .build_struct_gep(env, i as u32, name)
+          .expect("capture index should be in bounds");
+
+        let value = self.llvm_builder.build_load(capture_ptr, name);
+
+        self.bind_variable(name.clone(), value, capture_type);
+      }
+    }
+
+    self.bind_params(&block.params, &params[1..], param_types);
+
+    let return_value = self.compile_block_body(&block.body, return_type);
+
+    self.block_exits.pop();
+    self.release_scope_variables();
+    self.llvm_builder.build_return(Some(&return_value));
+
+    self.exit_debug_function();
+    self.scope.exit();
+    self.scope.restore_locals(outer_locals);
+    self.llvm_builder.position_at_end(outer_block);
+
+    let fn_ptr = function.as_global_value().as_pointer_value();
+
+    self.compile_tuple(typ, vec![fn_ptr.into(), env_ptr.into()])
+  }
+
+  /// Copies captured variables into a new environment object. Closures
+  /// that don't capture anything get a null environment.
+  fn build_environment(
+    &mut self,
+    env_type: StructType<'ctx>,
+    captures: &[(String, PointerValue<'ctx>, ValueType)],
+  ) -> PointerValue<'ctx> {
+    if captures.is_empty() {
+      return self.opaque_ptr_type().const_null();
+    }
+
+    let mut capture_values = Vec::with_capacity(captures.len());
+
+    for (name, ptr, capture_type) in captures {
+      let value = self.llvm_builder.build_load(*ptr, name);
+      self.retain_value(value, capture_type);
+
+      capture_values.push(value);
+    }
+
+    let env = self.build_struct(env_type, capture_values);
+
+    let env_payload_type = ValueType::UnlabeledTuple(
+      captures
+        .iter()
+        .map(|(_, _, capture_type)| capture_type.clone())
+        .collect(),
+    );
+
+    self.build_object(env, &env_payload_type)
+  }
+
+  fn closure_fn_type(
+    &self,
+    param_types: &[ValueType],
+    return_type: &ValueType,
+  ) -> FunctionType<'ctx> {
+    let mut llvm_param_types: Vec<BasicTypeEnum> = vec![self.opaque_ptr_type().into()];
+
+    for param_type in param_types {
+      llvm_param_types.push(self.llvm_type(param_type));
+    }
+
+    self
+      .llvm_type(return_type)
+      .fn_type(&llvm_param_types, false)
+  }
+
+  fn opaque_ptr_type(&self) -> PointerType<'ctx> {
+    self.llvm_context.i8_type().ptr_type(AddressSpace::Generic)
+  }
+
+  fn extract_tuple_entry(&self, tuple: BasicValueEnum<'ctx>, index: usize) -> BasicValueEnum<'ctx> {
+    self
+      .llvm_builder
+      .build_extract_value(tuple.into_struct_value(), index as u32, "entry")
+      .expect("tuple entry index should be in bounds")
+  }
+
+  /// Creates storage for a binding. Top-level bindings become globals so
+  /// that they outlive main's stack frame, others are allocated on the stack.
+  fn allocate(&self, name: &str, typ: BasicTypeEnum<'ctx>) -> PointerValue<'ctx> {
+    if self.scope.is_top_level() {
+      let global = self.llvm_module.add_global(typ, None, name);
+
+      global.set_linkage(Linkage::Internal);
+      global.set_initializer(&const_zero(typ));
+
+      return global.as_pointer_value();
+    }
+
+    // Allocas go at the start of the entry block so that LLVM can promote
+    // them to registers.
+    let entry = self.current_function().get_first_basic_block().unwrap();
+    let entry_builder = self.llvm_context.create_builder();
+
+    match entry.get_first_instruction() {
+      Some(instruction) => entry_builder.position_before(&instruction),
+      None => entry_builder.position_at_end(entry),
+    }
+
+    entry_builder.build_alloca(typ, name)
+  }
+
+  fn set_debug_location(&mut self, pos: Position) {
+    if let Some(location) = self
+      .debug_info
+      .as_mut()
+      .and_then(|debug_info| debug_info.location(pos.0))
+    {
+      self
+        .llvm_builder
+        .set_current_debug_location(self.llvm_context, location);
+    }
+  }
+
+  fn enter_debug_function(&mut self, function: FunctionValue<'ctx>, pos: Position) {
+    if let Some(debug_info) = &mut self.debug_info {
+      let subprogram = debug_info.create_subprogram(function, pos);
+
+      debug_info.enter_scope(subprogram.as_debug_info_scope(), pos);
+    }
+
+    self.set_debug_location(pos);
+  }
+
+  /// Leaves a function's debug scope, and puts the builder back at its
+  /// last location in the enclosing function.
+  fn exit_debug_function(&mut self) {
+    let location = match &mut self.debug_info {
+      Some(debug_info) => debug_info.exit_scope(),
+      None => return,
+    };
+
+    match location {
+      Some(location) => self
+        .llvm_builder
+        .set_current_debug_location(self.llvm_context, location),
+
+      None => self.llvm_builder.unset_current_debug_location(),
+    }
+  }
+
+  fn declare_debug_variable(
+    &mut self,
+    name: &str,
+    ptr: PointerValue<'ctx>,
+    typ: &ValueType,
+    pos: Position,
+  ) {
+    // Top-level bindings are globals, not local variables
+    if self.scope.is_top_level() {
+      return;
+    }
+
+    let size_in_bits = self
+      .target_machine
+      .get_target_data()
+      .get_bit_size(&self.llvm_type(typ));
+
+    let block = self.llvm_builder.get_insert_block().unwrap();
+
+    if let Some(debug_info) = &mut self.debug_info {
+      debug_info.declare_variable(name, ptr, typ, size_in_bits, pos, block);
+    }
+  }
+
+  fn current_function(&self) -> FunctionValue<'ctx> {
+    self
+      .llvm_builder
+      .get_insert_block()
+      .and_then(|block| block.get_parent())
+      .expect("builder should be positioned in a function")
+  }
+
+  fn llvm_type(&self, typ: &ValueType) -> BasicTypeEnum<'ctx> {
+    match typ {
+      ValueType::Int => self.llvm_context.i64_type().into(),
+
+      ValueType::Float => self.llvm_context.f64_type().into(),
+
+      ValueType::Sized(SizedType::Float32) => self.llvm_context.f32_type().into(),
+
+      ValueType::Sized(sized_type) => self
+        .llvm_context
+        .custom_width_int_type(sized_type.bits())
+        .into(),
+
+      ValueType::String => self
+        .llvm_context
+        .i8_type()
+        .ptr_type(AddressSpace::Generic)
+        .into(),
+
+      ValueType::Pointer => self.opaque_ptr_type().into(),
+
+      ValueType::Nothing | ValueType::Never => self.llvm_context.struct_type(&[], false).into(),
+
+      ValueType::UnlabeledTuple(entry_types) => {
+        let entry_types = entry_types
+          .iter()
+          .map(|entry_type| self.llvm_type(entry_type))
+          .collect::<Vec<BasicTypeEnum>>();
+
+        self.llvm_context.struct_type(&entry_types, false).into()
+      }
+
+      ValueType::LabeledTuple(entries) => {
+        let entry_types = entries
+          .iter()
+          .map(|(_label, entry_type)| self.llvm_type(entry_type))
+          .collect::<Vec<BasicTypeEnum>>();
+
+        self.llvm_context.struct_type(&entry_types, false).into()
+      }
+
+      ValueType::Func(param_types, return_type) => {
+        let fn_ptr_type = self
+          .closure_fn_type(param_types, return_type)
+          .ptr_type(AddressSpace::Generic);
+
+        self
+          .llvm_context
+          .struct_type(&[fn_ptr_type.into(), self.opaque_ptr_type().into()], false)
+          .into()
+      }
+
+      ValueType::Named(..) | ValueType::Generic(..) => match self.layouts.get_type(typ) {
+        Some(TypeLayout::Enum { .. }) => self.enum_type().into(),
+
+        Some(TypeLayout::Struct { inner_type }) => self
+          .record_type(&inner_type)
+          .ptr_type(AddressSpace::Generic)
+          .into(),
+
+        // Lists and dicts are objects
+        None if matches!(typ, ValueType::Generic(..)) => self.opaque_ptr_type().into(),
+
+        None => self.unsupported_type(typ),
+      },
+
+      // The analyzer solves every variable, and generic defs are compiled
+      // once per set of concrete params, so neither should be left here
+      ValueType::Variable(_) | ValueType::Constrained(..) => self.unsupported_type(typ),
+
+      other => self.unsupported_type(other),
+    }
+  }
+
+  /// Records a type that can't be compiled, e.g. a generic param that's
+  /// left over, and stands in for it so that emitting can carry on.
+  fn unsupported_type(&self, typ: &ValueType) -> BasicTypeEnum<'ctx> {
+    let mut unsupported_types = self.unsupported_types.borrow_mut();
+
+    if !unsupported_types.contains(typ) {
+      unsupported_types.push(typ.clone());
+    }
+
+    self.opaque_ptr_type().into()
+  }
+
+  fn enum_type(&self) -> StructType<'ctx> {
+    self.llvm_context.struct_type(
+      &[
+        self.llvm_context.i32_type().into(),
+        self.opaque_ptr_type().into(),
+      ],
+      false,
+    )
+  }
+
+  fn record_type(&self, inner_type: &ValueType) -> StructType<'ctx> {
+    let field_types = struct_fields(inner_type)
+      .iter()
+      .map(|(_, field_type)| self.llvm_type(field_type))
+      .collect::<Vec<BasicTypeEnum>>();
+
+    self.llvm_context.struct_type(&field_types, false)
+  }
+
+  fn nothing_value(&self) -> BasicValueEnum<'ctx> {
+    self.llvm_context.const_struct(&[], false).into()
+  }
+
+  fn compile_literal(&mut self, lit: &LiteralNode) -> BasicValueEnum<'ctx> {
+    match &lit.kind {
+      LiteralKind::IntDecimal(value)
+      | LiteralKind::IntOctal(value)
+      | LiteralKind::IntHex(value)
+      | LiteralKind::IntBinary(value) => self
+        .llvm_context
+        .i64_type()
+        .const_int(*value as u64, true)
+        .into(),
+
+      LiteralKind::FloatDecimal(value) => self.llvm_context.f64_type().const_float(*value).into(),
+
+      LiteralKind::SizedInt(value, sized_type) => self
+        .llvm_type(&ValueType::Sized(*sized_type))
+        .into_int_type()
+        .const_int(*value, false)
+        .into(),
+
+      LiteralKind::SizedFloat(value, sized_type) => self
+        .llvm_type(&ValueType::Sized(*sized_type))
+        .into_float_type()
+        .const_float(*value)
+        .into(),
+
+      LiteralKind::Str(value) => self.build_static_string(value).into(),
+    }
+  }
+
+  /// String literals are immortal objects in constant memory, so they
+  /// can be passed around like any other string without being copied.
+  fn build_static_string(&self, value: &str) -> PointerValue<'ctx> {
+    self.build_static_object(self.runtime.const_string_payload(value), "str")
+  }
+
+  /// Regex literals are compiled to a program for the runtime's matcher,
+  /// which is stored like a string literal.
+  fn build_static_regex(&self, node: &RegExprNode) -> PointerValue<'ctx> {
+    let program = compile_regex(node);
+
+    self.build_static_object(self.runtime.const_regex_payload(&program), "regex")
+  }
+
+  /// Puts an immortal object in constant memory. Returns a pointer to its
+  /// payload.
+  fn build_static_object(&self, payload: StructValue<'ctx>, name: &str) -> PointerValue<'ctx> {
+    let object = self.llvm_context.const_struct(
+      &[self.runtime.immortal_header().into(), payload.into()],
+      false,
+    );
+
+    let global = self.llvm_module.add_global(object.get_type(), None, name);
+    global.set_linkage(Linkage::Private);
+    global.set_constant(true);
+    global.set_initializer(&object);
+
+    let i32_type = self.llvm_context.i32_type();
+
+    let payload = unsafe {
+      global
+        .as_pointer_value()
+        .const_in_bounds_gep(&[i32_type.const_zero(), i32_type.const_int(1, false)])
+    };
+
+    payload.const_cast(self.opaque_ptr_type())
+  }
+
+  fn compile_intrinsic_def(&mut self, node: &IntrinsicDefNode) {
+    let param_types = def_param_types(&node.kind);
+
+    let function = self
+      .llvm_module
+      .get_function(&mangle::def_name(&node.kind, &param_types))
+      .expect("intrinsic def should have been declared");
+
+    // Another module has already compiled this intrinsic
+    if function.count_basic_blocks() > 0 {
+      return;
+    }
+
+    // Intrinsics have no Pluma source to step through
+    self.llvm_builder.unset_current_debug_location();
+
+    let entry = self.llvm_context.append_basic_block(function, "entry");
+    self.llvm_builder.position_at_end(entry);
+
+    let params = function.get_params();
+
+    let return_value = match &node.kind {
+      DefKind::Function { signature } => {
+        self.build_intrinsic_function(&signature_names(signature), &params)
+      }
+
+      DefKind::Method { signature, .. } => {
+        self.build_intrinsic_method(&param_types[0], &signature_names(signature), &params)
+      }
+
+      DefKind::BinaryOperator { op, .. } | DefKind::UnaryOperator { op, .. } => {
+        self.build_intrinsic_operator(&op.name, &param_types, &params)
+      }
+    };
+
+    match return_value {
+      Some(value) => {
+        self.llvm_builder.build_return(Some(&value));
+      }
+
+      // We don't know how to implement this intrinsic, so leave it as an
+      // external declaration for the linker to resolve.
+      None => {
+        let _ = entry.remove_from_function();
+      }
+    }
+  }
+
+  /// Extern defs are compiled to a wrapper that converts its params to C
+  /// values, calls the C function, and converts the result back. Numbers
+  /// and pointers are passed as they are, and strings are passed as a
+  /// pointer to their NUL-terminated bytes.
+  fn compile_extern_def(&mut self, node: &ExternDefNode) {
+    let signature = match &node.kind {
+      DefKind::Function { signature } => signature,
+      _ => unreachable!("analyzer should only allow functions to be extern"),
+    };
+
+    let param_types = def_param_types(&node.kind);
+    let return_type = def_return_type(&node.return_type);
+
+    let function = self
+      .llvm_module
+      .get_function(&mangle::def_name(&node.kind, &param_types))
+      .expect("extern def should have been declared");
+
+    let link_name = match &node.link_name {
+      Some(link_name) => link_name.clone(),
+      None => signature_names(signature).join("_"),
+    };
+
+    // C functions that take nothing are declared with no params at all
+    let c_params = param_types
+      .iter()
+      .zip(function.get_params())
+      .filter(|(param_type, _)| **param_type != ValueType::Nothing)
+      .collect::<Vec<(&ValueType, BasicValueEnum)>>();
+
+    let c_param_types = c_params
+      .iter()
+      .map(|(param_type, _)| self.llvm_type(param_type))
+      .collect::<Vec<BasicTypeEnum>>();
+
+    let c_return_type = match return_type {
+      ValueType::Nothing => None,
+      _ => Some(self.llvm_type(&return_type)),
+    };
+
+    let c_function = self.get_or_declare_c_function(&link_name, c_return_type, &c_param_types);
+
+    self.llvm_builder.unset_current_debug_location();
+
+    let entry = self.llvm_context.append_basic_block(function, "entry");
+    self.llvm_builder.position_at_end(entry);
+
+    let args = c_params
+      .iter()
+      .map(|(param_type, value)| match param_type {
+        ValueType::String => self
+          .runtime
+          .build_string_bytes(&self.llvm_builder, value.into_pointer_value())
+          .into(),
+        _ => *value,
+      })
+      .collect::<Vec<BasicValueEnum>>();
+
+    let result = self
+      .llvm_builder
+      .build_call(c_function, &args, "")
+      .try_as_basic_value()
+      .left();
+
+    let return_value = match (return_type, result) {
+      (ValueType::String, Some(bytes)) => self
+        .llvm_builder
+        .build_call(self.runtime.string_from_c, &[bytes], "string")
+        .try_as_basic_value()
+        .left()
+        .unwrap(),
+      (_, Some(value)) => value,
+      (_, None) => self.nothing_value(),
+    };
+
+    self.llvm_builder.build_return(Some(&return_value));
+  }
+
+  fn build_intrinsic_function(
+    &self,
+    name_parts: &[&str],
+    params: &[BasicValueEnum<'ctx>],
+  ) -> Option<BasicValueEnum<'ctx>> {
+    match name_parts {
+      ["exit"] => {
+        let i32_type = self.llvm_context.i32_type();
+        let exit = self.get_or_declare_c_function("exit", None, &[i32_type.into()]);
+
+        // Exit statuses are truncated to 8 bits by the OS anyway
+        let status =
+          self
+            .llvm_builder
+            .build_int_truncate(params[0].into_int_value(), i32_type, "status");
+
+        self.llvm_builder.build_call(exit, &[status.into()], "");
+
+        Some(self.nothing_value())
+      }
+
+      ["loop"] => {
+        let function = self.current_function();
+        let body_block = self.llvm_context.append_basic_block(function, "loop.body");
+        let exit_block = self.llvm_context.append_basic_block(function, "loop.exit");
+
+        self.llvm_builder.build_unconditional_branch(body_block);
+        self.llvm_builder.position_at_end(body_block);
+
+        let result = self.call_closure(params[0], &[self.nothing_value()]);
+
+        self.build_loop_branch(result, body_block, exit_block);
+        self.llvm_builder.position_at_end(exit_block);
+
+        Some(self.nothing_value())
+      }
+
+      ["print"] => {
+        let stdout = self.llvm_context.i32_type().const_int(1, false);
+
+        self
+          .llvm_builder
+          .build_call(self.runtime.write_line, &[stdout.into(), params[0]], "");
+
+        Some(self.nothing_value())
+      }
+
+      // std/io's `out` and `err` write to stdout and stderr
+      ["out"] | ["err"] => {
+        let fd = match name_parts {
+          ["out"] => 1,
+          _ => 2,
+        };
+        let fd = self.llvm_context.i32_type().const_int(fd, false);
+
+        self
+          .llvm_builder
+          .build_call(self.runtime.write_line, &[fd.into(), params[0]], "");
+
+        // The string is returned too, and the caller gets its own reference
+        self.retain_value(params[0], &ValueType::String);
+
+        Some(params[0])
+      }
+
+      _ => None,
+    }
+  }
+
+  fn build_intrinsic_method(
+    &self,
+    receiver_type: &ValueType,
+    name_parts: &[&str],
+    params: &[BasicValueEnum<'ctx>],
+  ) -> Option<BasicValueEnum<'ctx>> {
+    let receiver = params[0];
+
+    let value = match (receiver_type, name_parts) {
+      (ValueType::Int, ["to_string"]) => self.build_to_string(self.runtime.int_to_string, receiver),
+      (ValueType::Float, ["to_string"]) => {
+        self.build_to_string(self.runtime.float_to_string, receiver)
+      }
+
+      // e.g. `Int .. to_uint8`
+      (ValueType::Int, [method_name]) | (ValueType::Float, [method_name]) => {
+        let sized_type = sized_conversion_target(method_name)?;
+        self.build_number_conversion(receiver, receiver_type, &ValueType::Sized(sized_type))
+      }
+
+      (ValueType::Sized(_), ["to_int"]) => {
+        self.build_number_conversion(receiver, receiver_type, &ValueType::Int)
+      }
+      (ValueType::Sized(_), ["to_float"]) => {
+        self.build_number_conversion(receiver, receiver_type, &ValueType::Float)
+      }
+
+      // Sized numbers are widened first, so they share Int's and Float's
+      // runtime functions
+      (ValueType::Sized(sized_type), ["to_string"]) => {
+        let (wide_type, function) = if sized_type.is_float() {
+          (ValueType::Float, self.runtime.float_to_string)
+        } else if sized_type.is_signed() {
+          (ValueType::Int, self.runtime.int_to_string)
+        } else {
+          (ValueType::Int, self.runtime.uint_to_string)
+        };
+
+        let wide = self.build_number_conversion(receiver, receiver_type, &wide_type);
+        self.build_to_string(function, wide)
+      }
+
+      _ => return None,
+    };
+
+    Some(value)
+  }
+
+  fn build_to_string(
+    &self,
+    function: FunctionValue<'ctx>,
+    value: BasicValueEnum<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    self
+      .llvm_builder
+      .build_call(function, &[value], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+  }
+
+  /// Converts a number to another numeric type, like a cast in C: integers
+  /// are truncated or extended (according to their own signedness), and
+  /// floats are rounded to the new width.
+  fn build_number_conversion(
+    &self,
+    value: BasicValueEnum<'ctx>,
+    from_type: &ValueType,
+    to_type: &ValueType,
+  ) -> BasicValueEnum<'ctx> {
+    let builder = &self.llvm_builder;
+    let target_type = self.llvm_type(to_type);
+
+    match (number_class(from_type), value) {
+      (Some(NumberClass::Int { signed }), BasicValueEnum::IntValue(value)) => {
+        let target_type = target_type.into_int_type();
+        let (from_bits, to_bits) = (
+          value.get_type().get_bit_width(),
+          target_type.get_bit_width(),
+        );
+
+        if from_bits > to_bits {
+          builder
+            .build_int_truncate(value, target_type, "convert")
+            .into()
+        } else if from_bits == to_bits {
+          value.into()
+        } else if signed {
+          builder
+            .build_int_s_extend(value, target_type, "convert")
+            .into()
+        } else {
+          builder
+            .build_int_z_extend(value, target_type, "convert")
+            .into()
+        }
+      }
+
+      // Float32 is the only float type narrower than Float
+      (Some(NumberClass::Float), BasicValueEnum::FloatValue(value)) => match to_type {
+        ValueType::Float => builder
+          .build_float_ext(value, target_type.into_float_type(), "convert")
+          .into(),
+        _ => builder
+          .build_float_trunc(value, target_type.into_float_type(), "convert")
+          .into(),
+      },
+
+      _ => unreachable!("{} can't be converted to {}", from_type, to_type),
+    }
+  }
+
+  fn build_intrinsic_operator(
+    &self,
+    op: &str,
+    param_types: &[ValueType],
+    params: &[BasicValueEnum<'ctx>],
+  ) -> Option<BasicValueEnum<'ctx>> {
+    let builder = &self.llvm_builder;
+
+    if let ("+", [ValueType::String, ValueType::String], [l, r]) = (op, param_types, params) {
+      return builder
+        .build_call(self.runtime.string_concat, &[*l, *r], "concat")
+        .try_as_basic_value()
+        .left();
+    }
+
+    // Integer arithmetic wraps around on overflow. (Calls to these
+    // operators are replaced with checked versions when overflow checks are
+    // on, and always for division, to check for a zero divisor.)
+    let value = match (op, number_class(param_types.first()?)?, params) {
+      ("+", NumberClass::Int { .. }, [l, r]) => builder
+        .build_int_add(l.into_int_value(), r.into_int_value(), "add")
+        .into(),
+      ("-", NumberClass::Int { .. }, [l, r]) => builder
+        .build_int_sub(l.into_int_value(), r.into_int_value(), "sub")
+        .into(),
+      ("*", NumberClass::Int { .. }, [l, r]) => builder
+        .build_int_mul(l.into_int_value(), r.into_int_value(), "mul")
+        .into(),
+      ("/", NumberClass::Int { signed: true }, [l, r])
+      | ("%", NumberClass::Int { signed: true }, [l, r]) => self
+        .build_wrapping_signed_division(op, l.into_int_value(), r.into_int_value())
+        .into(),
+      ("/", NumberClass::Int { signed: false }, [l, r]) => builder
+        .build_int_unsigned_div(l.into_int_value(), r.into_int_value(), "div")
+        .into(),
+      ("%", NumberClass::Int { signed: false }, [l, r]) => builder
+        .build_int_unsigned_rem(l.into_int_value(), r.into_int_value(), "rem")
+        .into(),
+      ("-", NumberClass::Int { .. }, [r]) => {
+        builder.build_int_neg(r.into_int_value(), "neg").into()
+      }
+
+      ("+", NumberClass::Float, [l, r]) => builder
+        .build_float_add(l.into_float_value(), r.into_float_value(), "add")
+        .into(),
+      ("-", NumberClass::Float, [l, r]) => builder
+        .build_float_sub(l.into_float_value(), r.into_float_value(), "sub")
+        .into(),
+      ("*", NumberClass::Float, [l, r]) => builder
+        .build_float_mul(l.into_float_value(), r.into_float_value(), "mul")
+        .into(),
+      ("/", NumberClass::Float, [l, r]) => builder
+        .build_float_div(l.into_float_value(), r.into_float_value(), "div")
+        .into(),
+      ("-", NumberClass::Float, [r]) => builder.build_float_neg(r.into_float_value(), "neg").into(),
+
+      _ => return None,
+    };
+
+    Some(value)
+  }
+
+  /// Integer division (`/`) or remainder (`%`), which panics when the
+  /// divisor is zero, whether or not overflow checks are on. Dividing the
+  /// smallest signed integer by -1 overflows, so it panics when they are,
+  /// and wraps when they aren't.
+  fn build_int_division(
+    &self,
+    op: &str,
+    args: &[BasicValueEnum<'ctx>],
+    signed: bool,
+    pos: Position,
+  ) -> BasicValueEnum<'ctx> {
+    let (l, r) = (args[0].into_int_value(), args[1].into_int_value());
+    let builder = &self.llvm_builder;
+
+    let is_zero =
+      builder.build_int_compare(IntPredicate::EQ, r, r.get_type().const_zero(), "is_zero");
+    self.build_panic_if(is_zero, "division by zero", pos);
+
+    let value = match (op, signed) {
+      ("/", false) => builder.build_int_unsigned_div(l, r, "div"),
+      (_, false) => builder.build_int_unsigned_rem(l, r, "rem"),
+
+      (_, true) if !self.check_overflow => self.build_wrapping_signed_division(op, l, r),
+
+      (_, true) => {
+        let overflows = self.build_division_overflows(l, r);
+        self.build_panic_if(overflows, "integer overflow", pos);
+
+        match op {
+          "/" => builder.build_int_signed_div(l, r, "div"),
+          _ => builder.build_int_signed_rem(l, r, "rem"),
+        }
+      }
+    };
+
+    value.into()
+  }
+
+  /// Signed division (`/`) or remainder (`%`) that wraps when it overflows.
+  /// The only division that does is the smallest integer by -1, which LLVM
+  /// leaves undefined, so that divides by 1 instead. That gives the
+  /// wrapped results: the smallest integer, with a remainder of 0.
+  fn build_wrapping_signed_division(
+    &self,
+    op: &str,
+    l: IntValue<'ctx>,
+    r: IntValue<'ctx>,
+  ) -> IntValue<'ctx> {
+    let overflows = self.build_division_overflows(l, r);
+    let safe_r = self
+      .llvm_builder
+      .build_select(overflows, r.get_type().const_int(1, false), r, "divisor")
+      .into_int_value();
+
+    match op {
+      "/" => self.llvm_builder.build_int_signed_div(l, safe_r, "div"),
+      _ => self.llvm_builder.build_int_signed_rem(l, safe_r, "rem"),
+    }
+  }
+
+  /// Integer arithmetic that panics when it overflows, pointing at the
+  /// operation in the source. Returns None for operators that can't
+  /// overflow.
+  fn build_checked_int_operation(
+    &self,
+    op: &str,
+    args: &[BasicValueEnum<'ctx>],
+    signed: bool,
+    pos: Position,
+  ) -> Option<BasicValueEnum<'ctx>> {
+    let (l, r) = (args[0].into_int_value(), args[1].into_int_value());
+    let int_type = l.get_type();
+
+    let operation = match op {
+      "+" => "add",
+      "-" => "sub",
+      "*" => "mul",
+      _ => return None,
+    };
+
+    let intrinsic_name = format!(
+      "llvm.{}{}.with.overflow.i{}",
+      if signed { "s" } else { "u" },
+      operation,
+      int_type.get_bit_width()
+    );
+
+    let result_type = self.llvm_context.struct_type(
+      &[int_type.into(), self.llvm_context.bool_type().into()],
+      false,
+    );
+
+    let intrinsic = self
+      .llvm_module
+      .get_function(&intrinsic_name)
+      .unwrap_or_else(|| {
+        self.llvm_module.add_function(
+          &intrinsic_name,
+          result_type.fn_type(&[int_type.into(), int_type.into()], false),
+          None,
+        )
+      });
+
+    let result = self
+      .llvm_builder
+      .build_call(intrinsic, &[l.into(), r.into()], "checked")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_struct_value();
+
+    let overflows = self
+      .llvm_builder
+      .build_extract_value(result, 1, "overflows")
+      .unwrap()
+      .into_int_value();
+    self.build_panic_if(overflows, "integer overflow", pos);
+
+    self.llvm_builder.build_extract_value(result, 0, "value")
+  }
+
+  /// Whether dividing `l` by `r` overflows, which only happens when the
+  /// smallest signed integer is divided by -1.
+  fn build_division_overflows(&self, l: IntValue<'ctx>, r: IntValue<'ctx>) -> IntValue<'ctx> {
+    let int_type = l.get_type();
+
+    let is_min =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::EQ, l, min_signed_int(int_type), "is_min");
+    let is_minus_one = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      r,
+      int_type.const_all_ones(),
+      "is_minus_one",
+    );
+
+    self
+      .llvm_builder
+      .build_and(is_min, is_minus_one, "overflows")
+  }
+
+  /// Panics if `condition` is true, and otherwise continues in a new block.
+  fn build_panic_if(&self, condition: IntValue<'ctx>, problem: &str, pos: Position) {
+    let function = self.current_function();
+    let panic_block = self.llvm_context.append_basic_block(function, "panic");
+    let continue_block = self.llvm_context.append_basic_block(function, "no_panic");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(condition, panic_block, continue_block);
+
+    self.llvm_builder.position_at_end(panic_block);
+    self.build_panic(problem, pos);
+
+    self.llvm_builder.position_at_end(continue_block);
+  }
+
+  /// Ends the current block with a call to the runtime's panic function,
+  /// which reports the problem and exits the program.
+  fn build_panic(&self, problem: &str, pos: Position) {
+    let message = self.build_static_string(&self.panic_message(problem, pos));
+
+    self
+      .llvm_builder
+      .build_call(self.runtime.panic, &[message.into()], "");
+    self.llvm_builder.build_unreachable();
+  }
+
+  /// Exits the program with status 1 if `value` is a `Result` that holds an
+  /// `Err`, after writing the error to stderr. Any other value is left alone.
+  fn build_exit_if_err(&mut self, value: BasicValueEnum<'ctx>, typ: &ValueType) {
+    if !matches!(typ, ValueType::Generic(name, _) if name == "Result") {
+      return;
+    }
+
+    let (tag, payload_type) = match self.layouts.get_variant(typ, "Err") {
+      Some((tag, variant)) => (tag, variant.payload_type.clone()),
+      None => return,
+    };
+
+    let function = self.current_function();
+    let err_block = self.llvm_context.append_basic_block(function, "err");
+    let ok_block = self.llvm_context.append_basic_block(function, "ok");
+
+    let is_err = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      self.enum_tag(value),
+      self.llvm_context.i32_type().const_int(tag as u64, false),
+      "is_err",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_err, err_block, ok_block);
+
+    self.llvm_builder.position_at_end(err_block);
+
+    // Errors are described with their `to_string` method, if they have one
+    let description = match &payload_type {
+      Some(ValueType::String) => Some(self.enum_payload(value, &ValueType::String)),
+
+      Some(payload_type) => {
+        let function_name = mangle::method_name(payload_type, &["to_string".to_owned()]);
+
+        match self
+          .llvm_module
+          .get_function(&self.symbols.resolve(&function_name))
+        {
+          Some(_) => {
+            let payload = self.enum_payload(value, payload_type);
+            Some(self.build_call_by_name(&function_name, &[payload, self.nothing_value()]))
+          }
+
+          None => None,
+        }
+      }
+
+      None => None,
+    };
+
+    let message = match description {
+      Some(description) => self
+        .llvm_builder
+        .build_call(
+          self.runtime.string_concat,
+          &[self.build_static_string("Error: ").into(), description],
+          "message",
+        )
+        .try_as_basic_value()
+        .left()
+        .unwrap(),
+
+      None => self.build_static_string("Error").into(),
+    };
+
+    let stderr = self.llvm_context.i32_type().const_int(2, false);
+
+    self
+      .llvm_builder
+      .build_call(self.runtime.write_line, &[stderr.into(), message], "");
+
+    let i32_type = self.llvm_context.i32_type();
+    let exit = self.get_or_declare_c_function("exit", None, &[i32_type.into()]);
+
+    self
+      .llvm_builder
+      .build_call(exit, &[i32_type.const_int(1, false).into()], "");
+    self.llvm_builder.build_unreachable();
+
+    self.llvm_builder.position_at_end(ok_block);
+  }
+
+  /// Describes a runtime error in the same format as compile errors: the
+  /// problem, the line of code that caused it, and its location (e.g.
+  /// `main.pa:3:9`).
+  fn panic_message(&self, problem: &str, pos: Position) -> String {
+    let (path, source) = match &self.source_file {
+      Some(source_file) => &**source_file,
+      None => return format!("Error: {}", problem),
+    };
+
+    let SourceFrame { line, column, text } = SourceFrame::new(source, pos);
+    let prefix_width = 4 + line.to_string().len();
+
+    format!(
+      "Error: {}\n\n> {}| {}\n{}{}\n{}:{}:{}",
+      problem,
+      line,
+      text,
+      " ".repeat(prefix_width + column - 1),
+      "^".repeat(pos.1 - pos.0),
+      path,
+      line,
+      column
+    )
+  }
+
+  fn get_or_declare_c_function(
+    &self,
+    name: &str,
+    return_type: Option<BasicTypeEnum<'ctx>>,
+    param_types: &[BasicTypeEnum<'ctx>],
+  ) -> FunctionValue<'ctx> {
+    if let Some(function) = self.llvm_module.get_function(name) {
+      return function;
+    }
+
+    let fn_type = match return_type {
+      Some(return_type) => return_type.fn_type(param_types, false),
+      None => self.llvm_context.void_type().fn_type(param_types, false),
+    };
+
+    self
+      .llvm_module
+      .add_function(name, fn_type, Some(Linkage::External))
+  }
+}
+
+fn create_target_machine(
+  target_options: &TargetOptions,
+  opt_level: OptLevel,
+) -> Result<TargetMachine, Diagnostic> {
+  // When no triple is given we target the host, so the host CPU and its
+  // features are safe defaults. For any other triple we can't assume
+  // anything about the CPU.
+  let (triple, default_cpu, default_features) = match &target_options.triple {
+    Some(triple) => (
+      TargetTriple::create(triple),
+      "generic".to_owned(),
+      "".to_owned(),
+    ),
+
+    None => (
+      TargetMachine::get_default_triple(),
+      TargetMachine::get_host_cpu_name().to_string(),
+      TargetMachine::get_host_cpu_features().to_string(),
+    ),
+  };
+
+  let cpu = target_options.cpu.clone().unwrap_or(default_cpu);
+  let features = target_options.features.clone().unwrap_or(default_features);
+
+  Target::initialize_all(&InitializationConfig::default());
+
+  let target = Target::from_triple(&triple).map_err(|err| {
+    Diagnostic::error(EmitError {
+      kind: EmitErrorKind::UnsupportedTarget(triple_name(&triple), err.to_string()),
+    })
+  })?;
+
+  target
+    .create_target_machine(
+      &triple,
+      &cpu,
+      &features,
+      opt_level.llvm_level(),
+      RelocMode::PIC,
+      CodeModel::Default,
+    )
+    .ok_or_else(|| {
+      Diagnostic::error(EmitError {
+        kind: EmitErrorKind::TargetMachineUnavailable(triple_name(&triple), cpu, features),
+      })
+    })
+}
+
+fn def_param_types(kind: &DefKind) -> Vec<ValueType> {
+  let signature_types = |signature: &Signature| {
+    signature
+      .iter()
+      .map(|(_, part_type)| part_type.typ.clone())
+      .collect::<Vec<ValueType>>()
+  };
+
+  match kind {
+    DefKind::Function { signature } => signature_types(signature),
+
+    DefKind::Method {
+      receiver,
+      signature,
+    } => {
+      let mut param_types = vec![type_identifier_value_type(receiver)];
+      param_types.append(&mut signature_types(signature));
+      param_types
+    }
+
+    DefKind::BinaryOperator { left, right, .. } => vec![
+      type_identifier_value_type(left),
+      type_identifier_value_type(right),
+    ],
+
+    DefKind::UnaryOperator { right, .. } => vec![type_identifier_value_type(right)],
+  }
+}
+
+/// How a def is named in backtraces, e.g. `Int .. to_string` or `Int + Int`.
+fn def_display_name(kind: &DefKind) -> String {
+  match kind {
+    DefKind::Function { signature } => signature_names(signature).join(" _ ") + " _",
+
+    DefKind::Method {
+      receiver,
+      signature,
+    } => format!(
+      "{} .. {} _",
+      receiver.name,
+      signature_names(signature).join(" _ ")
+    ),
+
+    DefKind::BinaryOperator { left, op, right } => {
+      format!("{} {} {}", left.name, op.name, right.name)
+    }
+
+    DefKind::UnaryOperator { op, right } => format!("{}{}", op.name, right.name),
+  }
+}
+
+/// Whether a def is the program's entry point, i.e. `def main () -> ...`.
+fn is_main_def(kind: &DefKind) -> bool {
+  match kind {
+    DefKind::Function { signature } => match &signature[..] {
+      [(name, param_type)] => name.name == "main" && param_type.typ == ValueType::Nothing,
+      _ => false,
+    },
+    _ => false,
+  }
+}
+
+/// Whether a def has generic params, either declared in its `where` clause
+/// or inferred.
+fn is_generic_def(def: &DefNode) -> bool {
+  let param_types = def_param_types(&def.kind);
+  let return_type = def_return_type(&def.return_type);
+
+  !generics::generic_param_names(
+    &def.generic_type_constraints,
+    param_types.iter().chain(Some(&return_type)),
+  )
+  .is_empty()
+}
+
+/// Methods of generic intrinsic types (i.e. List, Dict and Regex) aren't
+/// compiled as functions. They're built at each call instead.
+fn has_generic_receiver(kind: &DefKind) -> bool {
+  matches!(kind, DefKind::Method { receiver, .. } if !receiver.generics.is_empty())
+}
+
+/// A list's element type, or a `(key, value)` tuple for a dict's entries.
+fn collection_element_type(typ: &ValueType) -> ValueType {
+  match typ {
+    ValueType::Generic(name, type_args) if name == "Dict" => {
+      ValueType::UnlabeledTuple(type_args.clone())
+    }
+    ValueType::Generic(_, type_args) => type_args[0].clone(),
+    other => unreachable!("{} is not a collection type", other),
+  }
+}
+
+fn collection_key_type(entry_type: &ValueType) -> ValueType {
+  match entry_type {
+    ValueType::UnlabeledTuple(entry_types) => entry_types[0].clone(),
+    other => unreachable!("{} is not a dict entry type", other),
+  }
+}
+
+fn collection_value_type(entry_type: &ValueType) -> ValueType {
+  match entry_type {
+    ValueType::UnlabeledTuple(entry_types) => entry_types[1].clone(),
+    other => unreachable!("{} is not a dict entry type", other),
+  }
+}
+
+fn def_return_type(return_type: &Option<TypeExprNode>) -> ValueType {
+  match return_type {
+    Some(type_expr) => type_expr.typ.clone(),
+    None => ValueType::Nothing,
+  }
+}
+
+fn loop_result_type() -> ValueType {
+  ValueType::Named("LoopResult".to_owned())
+}
+
+fn type_identifier_value_type(node: &TypeIdentifierNode) -> ValueType {
+  match &node.name[..] {
+    "Int" => ValueType::Int,
+    "Float" => ValueType::Float,
+    "String" => ValueType::String,
+    "Pointer" => ValueType::Pointer,
+    "Never" => ValueType::Never,
+    name => match SizedType::from_name(name) {
+      Some(sized_type) => ValueType::Sized(sized_type),
+      None => ValueType::Named(node.name.clone()),
+    },
+  }
+}
+
+/// How arithmetic is done on a numeric type.
+#[derive(Clone, Copy, PartialEq)]
+enum NumberClass {
+  Int { signed: bool },
+  Float,
+}
+
+fn number_class(typ: &ValueType) -> Option<NumberClass> {
+  match typ {
+    ValueType::Int => Some(NumberClass::Int { signed: true }),
+    ValueType::Float => Some(NumberClass::Float),
+    ValueType::Sized(sized_type) if sized_type.is_float() => Some(NumberClass::Float),
+    ValueType::Sized(sized_type) => Some(NumberClass::Int {
+      signed: sized_type.is_signed(),
+    }),
+    _ => None,
+  }
+}
+
+/// The sized type that a conversion method on Int or Float (like
+/// `to_uint8`) converts to.
+fn sized_conversion_target(method_name: &str) -> Option<SizedType> {
+  match method_name {
+    "to_int8" => Some(SizedType::Int8),
+    "to_int16" => Some(SizedType::Int16),
+    "to_int32" => Some(SizedType::Int32),
+    "to_int64" => Some(SizedType::Int64),
+    "to_uint8" => Some(SizedType::UInt8),
+    "to_uint16" => Some(SizedType::UInt16),
+    "to_uint32" => Some(SizedType::UInt32),
+    "to_uint64" => Some(SizedType::UInt64),
+    "to_float32" => Some(SizedType::Float32),
+    _ => None,
+  }
+}
+
+fn min_signed_int(int_type: IntType) -> IntValue {
+  int_type.const_int(1 << (int_type.get_bit_width() - 1), false)
+}
+
+/// Pairs each element of a tuple pattern with its position and type in
+/// the tuple being destructured. Labeled patterns may list fields in any
+/// order, so each field is looked up in the tuple's type.
+fn tuple_element_patterns<'a>(
+  pattern: &'a PatternNode,
+  typ: &'a ValueType,
+) -> Vec<(usize, &'a PatternNode, &'a ValueType)> {
+  match (&pattern.kind, typ) {
+    (PatternKind::UnlabeledTuple(element_patterns), ValueType::UnlabeledTuple(element_types)) => {
+      element_patterns
+        .iter()
+        .zip(element_types)
+        .enumerate()
+        .map(|(i, (element_pattern, element_type))| (i, element_pattern, element_type))
+        .collect()
+    }
+
+    (PatternKind::LabeledTuple(element_patterns), ValueType::LabeledTuple(entries)) => {
+      element_patterns
+        .iter()
+        .map(|(label, element_pattern)| {
+          let i = entries
+            .iter()
+            .position(|(entry_label, _)| *entry_label == label.name)
+            .expect("analyzer should reject unknown fields");
+
+          (i, element_pattern, &entries[i].1)
+        })
+        .collect()
+    }
+
+    _ => unreachable!("analyzer should reject non-tuple values"),
+  }
+}
+
+fn signature_names(signature: &Signature) -> Vec<&str> {
+  signature
+    .iter()
+    .map(|(part_name, _)| &part_name.name[..])
+    .collect()
+}
+
+fn identifier_names(idents: &[IdentifierNode]) -> Vec<String> {
+  idents.iter().map(|ident| ident.name.clone()).collect()
+}
+
+fn const_zero(typ: BasicTypeEnum) -> BasicValueEnum {
+  match typ {
+    BasicTypeEnum::ArrayType(t) => t.const_zero().into(),
+    BasicTypeEnum::FloatType(t) => t.const_zero().into(),
+    BasicTypeEnum::IntType(t) => t.const_zero().into(),
+    BasicTypeEnum::PointerType(t) => t.const_null().into(),
+    BasicTypeEnum::StructType(t) => t.const_zero().into(),
+    BasicTypeEnum::VectorType(t) => t.const_zero().into(),
+  }
+}
+
+fn write_failed(path: &Path, reason: String) -> Diagnostic {
+  Diagnostic::error(EmitError {
+    kind: EmitErrorKind::WriteFailed(path.to_string_lossy().into_owned(), reason),
+  })
+}
+
+fn triple_name(triple: &TargetTriple) -> String {
+  triple.as_str().to_string_lossy().into_owned()
+}
+
+impl<'ctx> Visitor for Emitter<'ctx> {
+  fn enter_module(&mut self, node: &ModuleNode) {
+    let entry_block = self
+      .llvm_context
+      .append_basic_block(self.main_function, "entry");
+
+    // Each module's top-level code runs after the code of the modules
+    // that were emitted before it
+    if let Some(previous_block) = self.main_block.replace(entry_block) {
+      self.llvm_builder.position_at_end(previous_block);
+      self.llvm_builder.build_unconditional_branch(entry_block);
+    }
+
+    // Types and definitions may be used before they are defined, so
+    // collect all of them before compiling anything else.
+    for statement in &node.body {
+      if let TopLevelStatementKind::TypeDef(type_def) = &statement.kind {
+        self.layouts.add_type_def(type_def);
+      }
+    }
+
+    for statement in &node.body {
+      match &statement.kind {
+        // Generic defs are compiled when they're called, once for each
+        // set of types they're called with
+        TopLevelStatementKind::Def(def) if is_generic_def(def) => {
+          let name = mangle::def_name(&def.kind, &def_param_types(&def.kind));
+
+          self.generic_defs.insert(
+            self.symbols.add_def(&name),
+            GenericDef {
+              node: Rc::new(def.clone()),
+              source_file: self.source_file.clone(),
+              module: self.symbols.current_module(),
+            },
+          );
+        }
+
+        TopLevelStatementKind::Def(def) => {
+          let name = mangle::def_name(&def.kind, &def_param_types(&def.kind));
+          let symbol = self.symbols.add_def(&name);
+
+          self.declare_function(&symbol, &def.kind, &def.return_type);
+        }
+
+        // Intrinsics are the same wherever they're declared, so a module
+        // can declare one that another module (e.g. the prelude) also has
+        TopLevelStatementKind::IntrinsicDef(def)
+          if !has_generic_receiver(&def.kind) && !self.is_declared(&def.kind) =>
+        {
+          self.declare_def(&def.kind, &def.return_type);
+        }
+
+        TopLevelStatementKind::ExternDef(def) => {
+          self.declare_def(&def.kind, &def.return_type);
+        }
+
+        _ => {}
+      }
+    }
+
+    self.llvm_builder.position_at_end(entry_block);
+  }
+
+  fn leave_module(&mut self, node: &ModuleNode) {
+    self.position_in_main();
+
+    // The entry module's `main` def runs after the rest of its top-level
+    // code. Imported modules may declare a `main` too, but it isn't called.
+    let is_entry_module = self.symbols.current_module().module_name == self.entry_module_name;
+    let main_def = node
+      .body
+      .iter()
+      .filter(|_| is_entry_module)
+      .find_map(|statement| match &statement.kind {
+        TopLevelStatementKind::Def(def) if is_main_def(&def.kind) => Some(def),
+        _ => None,
+      });
+
+    if let Some(main_def) = main_def {
+      let return_type = def_return_type(&main_def.return_type);
+      let function_name = mangle::def_name(&main_def.kind, &[ValueType::Nothing]);
+      let value = self.build_call_by_name(&function_name, &[self.nothing_value()]);
+
+      self.build_exit_if_err(value, &return_type);
+      self.release_value(value, &return_type);
+      self.leave_main();
+    }
+
+    if let Some(debug_info) = self.debug_info.take() {
+      debug_info.finalize();
+    }
+  }
+
+  fn enter_top_level_statement(&mut self, node: &TopLevelStatementNode) {
+    match &node.kind {
+      TopLevelStatementKind::Def(def) if !is_generic_def(def) => self.compile_def(def),
+
+      TopLevelStatementKind::IntrinsicDef(def) if !has_generic_receiver(&def.kind) => {
+        self.compile_intrinsic_def(def)
+      }
+
+      TopLevelStatementKind::ExternDef(def) => self.compile_extern_def(def),
+
+      TopLevelStatementKind::Let(let_node) => {
+        self.position_in_main();
+        self.compile_let(let_node);
+        self.leave_main();
+      }
 
       TopLevelStatementKind::Expr(expr) => {
-        // Go back to the end of the entry block in main
-        let block = self
-          .main_function
-          .get_last_basic_block()
-          .expect("should have at least one block");
+        self.position_in_main();
 
-        self.llvm_builder.position_at_end(block);
+        let value = self.compile_expr(expr);
 
-        self.compile_expr(expr);
+        self.build_exit_if_err(value, &expr.typ);
+        self.release_value(value, &expr.typ);
+        self.leave_main();
       }
 
       _ => {}
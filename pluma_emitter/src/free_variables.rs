@@ -0,0 +1,40 @@
+use pluma_ast::*;
+use pluma_visitor::*;
+
+/// Returns the names of all variables that a block's body reads or
+/// assigns to, in the order they first appear. This over-approximates
+/// the block's free variables (it includes the block's own params and
+/// locals), so callers should only keep names bound outside the block.
+pub fn referenced_names(block: &BlockNode) -> Vec<String> {
+  let mut collector = NameCollector { names: Vec::new() };
+
+  for stmt in &block.body {
+    stmt.traverse(&mut collector);
+  }
+
+  collector.names
+}
+
+struct NameCollector {
+  names: Vec<String>,
+}
+
+impl NameCollector {
+  fn add(&mut self, name: &str) {
+    if !self.names.iter().any(|existing| existing == name) {
+      self.names.push(name.to_owned());
+    }
+  }
+}
+
+impl Visitor for NameCollector {
+  fn enter_expr(&mut self, node: &ExprNode) {
+    match &node.kind {
+      ExprKind::Identifier(ident) => self.add(&ident.name),
+
+      ExprKind::Assignment { left, .. } => self.add(&left.name),
+
+      _ => {}
+    }
+  }
+}
@@ -0,0 +1,175 @@
+use pluma_ast::*;
+use pluma_visitor::VisitorMut;
+use std::collections::HashMap;
+
+/// The types that a generic def's params stand for in one instance of
+/// the def, by param name.
+pub type TypeArgs = HashMap<String, ValueType>;
+
+/// The names of a def's generic params: the ones in its `where` clause,
+/// then any others that appear in its param and return types.
+pub fn generic_param_names<'a>(
+  constraints: &GenericTypeConstraints,
+  types: impl Iterator<Item = &'a ValueType>,
+) -> Vec<String> {
+  let mut names = constraints
+    .iter()
+    .map(|(name, _)| name.name.clone())
+    .collect::<Vec<String>>();
+
+  for typ in types {
+    collect_constrained_names(typ, &mut names);
+  }
+
+  names
+}
+
+fn collect_constrained_names(typ: &ValueType, names: &mut Vec<String>) {
+  match typ {
+    ValueType::Constrained(name, _) if !names.contains(name) => names.push(name.clone()),
+    ValueType::Generic(_, args) => {
+      for arg in args {
+        collect_constrained_names(arg, names);
+      }
+    }
+    ValueType::Func(param_types, return_type) => {
+      for param_type in param_types {
+        collect_constrained_names(param_type, names);
+      }
+
+      collect_constrained_names(return_type, names);
+    }
+    ValueType::UnlabeledTuple(entry_types) => {
+      for entry_type in entry_types {
+        collect_constrained_names(entry_type, names);
+      }
+    }
+    ValueType::LabeledTuple(entries) => {
+      for (_, entry_type) in entries {
+        collect_constrained_names(entry_type, names);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Works out what a def's generic params stand for, by lining up a type
+/// from the def's signature with the type that's used in its place.
+pub fn match_type_args(
+  param_names: &[String],
+  generic_type: &ValueType,
+  actual_type: &ValueType,
+  type_args: &mut TypeArgs,
+) {
+  let mut match_all = |generic_types: &[ValueType], actual_types: &[ValueType]| {
+    for (generic_type, actual_type) in generic_types.iter().zip(actual_types) {
+      match_type_args(param_names, generic_type, actual_type, type_args);
+    }
+  };
+
+  match (generic_type, actual_type) {
+    // Never fits anywhere, so it doesn't say anything about the param
+    (_, ValueType::Never) => {}
+
+    (ValueType::Constrained(name, _), _) | (ValueType::Named(name), _)
+      if param_names.contains(name) =>
+    {
+      type_args
+        .entry(name.clone())
+        .or_insert_with(|| actual_type.clone());
+    }
+
+    (ValueType::Generic(_, generic_args), ValueType::Generic(_, actual_args)) => {
+      match_all(generic_args, actual_args)
+    }
+
+    (ValueType::UnlabeledTuple(generic_entries), ValueType::UnlabeledTuple(actual_entries)) => {
+      match_all(generic_entries, actual_entries)
+    }
+
+    (ValueType::LabeledTuple(generic_entries), ValueType::LabeledTuple(actual_entries)) => {
+      for ((_, generic_entry), (_, actual_entry)) in generic_entries.iter().zip(actual_entries) {
+        match_type_args(param_names, generic_entry, actual_entry, type_args);
+      }
+    }
+
+    (
+      ValueType::Func(generic_params, generic_return),
+      ValueType::Func(actual_params, actual_return),
+    ) => {
+      match_all(generic_params, actual_params);
+      match_type_args(param_names, generic_return, actual_return, type_args);
+    }
+
+    _ => {}
+  }
+}
+
+/// Replaces each generic param in a type with the type it stands for.
+pub fn substitute(typ: &ValueType, type_args: &TypeArgs) -> ValueType {
+  let substitute_all = |types: &[ValueType]| {
+    types
+      .iter()
+      .map(|typ| substitute(typ, type_args))
+      .collect::<Vec<ValueType>>()
+  };
+
+  match typ {
+    ValueType::Constrained(name, _) | ValueType::Named(name) => match type_args.get(name) {
+      Some(type_arg) => type_arg.clone(),
+      None => typ.clone(),
+    },
+
+    ValueType::Generic(name, args) => ValueType::Generic(name.clone(), substitute_all(args)),
+
+    ValueType::Func(param_types, return_type) => ValueType::Func(
+      substitute_all(param_types),
+      Box::new(substitute(return_type, type_args)),
+    ),
+
+    ValueType::UnlabeledTuple(entry_types) => {
+      ValueType::UnlabeledTuple(substitute_all(entry_types))
+    }
+
+    ValueType::LabeledTuple(entries) => ValueType::LabeledTuple(
+      entries
+        .iter()
+        .map(|(label, entry_type)| (label.clone(), substitute(entry_type, type_args)))
+        .collect(),
+    ),
+
+    other => other.clone(),
+  }
+}
+
+/// Rewrites every type in a copy of a generic def, so that the copy can
+/// be compiled like any other def.
+pub struct TypeSubstitution<'a> {
+  type_args: &'a TypeArgs,
+}
+
+impl<'a> TypeSubstitution<'a> {
+  pub fn new(type_args: &'a TypeArgs) -> Self {
+    TypeSubstitution { type_args }
+  }
+}
+
+impl<'a> VisitorMut for TypeSubstitution<'a> {
+  fn enter_def(&mut self, node: &mut DefNode) {
+    if let Some(return_type) = &mut node.return_type {
+      return_type.typ = substitute(&return_type.typ, self.type_args);
+    }
+  }
+
+  fn enter_call(&mut self, node: &mut CallNode) {
+    node.typ = substitute(&node.typ, self.type_args);
+  }
+
+  fn enter_expr(&mut self, node: &mut ExprNode) {
+    node.typ = substitute(&node.typ, self.type_args);
+  }
+
+  fn enter_type_expr(&mut self, node: &mut TypeExprNode) {
+    node.typ = substitute(&node.typ, self.type_args);
+  }
+}
@@ -0,0 +1,212 @@
+use crate::generics::{self, TypeArgs};
+use pluma_ast::*;
+use pluma_match::{TypeInfo, Variant};
+use std::collections::HashMap;
+
+// Enum values are `{ i32 tag, i8* payload }` pairs. Payloads are boxed, so
+// every value of an enum has the same size whatever its variants hold (and
+// so recursive enums are possible). Variants without a payload use a null
+// pointer.
+//
+// Struct values are pointers to heap-allocated records, which have one
+// field per entry in the struct's inner type.
+//
+// A generic type's layout is stored in terms of its params, and the types
+// in it are filled in for each instance of the type (e.g. `Maybe<Int>`).
+
+#[derive(Clone)]
+pub enum TypeLayout {
+  Enum { variants: Vec<Variant> },
+  Struct { inner_type: ValueType },
+}
+
+#[derive(Clone)]
+pub enum ConstructorLayout {
+  Variant { tag: u32 },
+  Struct { type_name: String },
+}
+
+/// Tracks the layout of each user-defined type, and which type each
+/// constructor name creates.
+pub struct Layouts {
+  types: HashMap<String, TypeLayout>,
+  type_params: HashMap<String, Vec<String>>,
+  constructors: HashMap<String, ConstructorLayout>,
+}
+
+impl Layouts {
+  pub fn new() -> Self {
+    Layouts {
+      types: HashMap::new(),
+      type_params: HashMap::new(),
+      constructors: HashMap::new(),
+    }
+  }
+
+  pub fn add_type_def(&mut self, node: &TypeDefNode) {
+    let type_name = node.name.name.clone();
+
+    if !node.generic_type_constraints.is_empty() {
+      let param_names = node
+        .generic_type_constraints
+        .iter()
+        .map(|(name, _)| name.name.clone())
+        .collect();
+
+      self.type_params.insert(type_name.clone(), param_names);
+    }
+
+    match &node.kind {
+      TypeDefKind::Enum { variants } => {
+        let mut variant_list = Vec::with_capacity(variants.len());
+
+        for (tag, variant) in variants.iter().enumerate() {
+          let (ident, payload_type) = match &variant.kind {
+            EnumVariantKind::Identifier(ident) => (ident, None),
+            EnumVariantKind::Constructor(ident, param) => (ident, Some(param.typ.clone())),
+          };
+
+          self.constructors.insert(
+            ident.name.clone(),
+            ConstructorLayout::Variant { tag: tag as u32 },
+          );
+
+          variant_list.push(Variant {
+            name: ident.name.clone(),
+            payload_type,
+          });
+        }
+
+        self.types.insert(
+          type_name,
+          TypeLayout::Enum {
+            variants: variant_list,
+          },
+        );
+      }
+
+      TypeDefKind::Struct { inner } => {
+        self.constructors.insert(
+          type_name.clone(),
+          ConstructorLayout::Struct {
+            type_name: type_name.clone(),
+          },
+        );
+
+        self.types.insert(
+          type_name,
+          TypeLayout::Struct {
+            inner_type: inner.typ.clone(),
+          },
+        );
+      }
+
+      TypeDefKind::Alias { .. } | TypeDefKind::Trait { .. } => {}
+    }
+  }
+
+  pub fn get_type(&self, typ: &ValueType) -> Option<TypeLayout> {
+    match typ {
+      ValueType::Named(name) => self.types.get(name).cloned(),
+
+      ValueType::Generic(name, args) => {
+        let layout = self.types.get(name)?;
+
+        let type_args = self
+          .type_params
+          .get(name)?
+          .iter()
+          .cloned()
+          .zip(args.iter().cloned())
+          .collect();
+
+        Some(layout.substitute(&type_args))
+      }
+
+      _ => None,
+    }
+  }
+
+  pub fn get_constructor(&self, name: &str) -> Option<&ConstructorLayout> {
+    self.constructors.get(name)
+  }
+
+  /// Finds the variant of an enum type with the given name. Returns its
+  /// tag and payload type.
+  pub fn get_variant(&self, typ: &ValueType, name: &str) -> Option<(u32, Variant)> {
+    match self.get_type(typ) {
+      Some(TypeLayout::Enum { variants }) => variants
+        .into_iter()
+        .enumerate()
+        .find(|(_, variant)| variant.name == name)
+        .map(|(tag, variant)| (tag as u32, variant)),
+
+      _ => None,
+    }
+  }
+}
+
+impl TypeInfo for Layouts {
+  fn enum_variants(&self, typ: &ValueType) -> Option<Vec<Variant>> {
+    match self.get_type(typ) {
+      Some(TypeLayout::Enum { variants }) => Some(variants),
+      _ => None,
+    }
+  }
+
+  fn struct_inner_type(&self, typ: &ValueType) -> Option<ValueType> {
+    match self.get_type(typ) {
+      Some(TypeLayout::Struct { inner_type }) => Some(inner_type),
+      _ => None,
+    }
+  }
+}
+
+impl TypeLayout {
+  /// The layout of one instance of a generic type.
+  fn substitute(&self, type_args: &TypeArgs) -> TypeLayout {
+    match self {
+      TypeLayout::Enum { variants } => TypeLayout::Enum {
+        variants: variants
+          .iter()
+          .map(|variant| Variant {
+            name: variant.name.clone(),
+            payload_type: variant
+              .payload_type
+              .as_ref()
+              .map(|payload_type| generics::substitute(payload_type, type_args)),
+          })
+          .collect(),
+      },
+
+      TypeLayout::Struct { inner_type } => TypeLayout::Struct {
+        inner_type: generics::substitute(inner_type, type_args),
+      },
+    }
+  }
+}
+
+/// The fields of a struct's record, given its inner type. Tuple entries
+/// each get a field, and any other inner type is a single field "0".
+pub fn struct_fields(inner_type: &ValueType) -> Vec<(String, ValueType)> {
+  match inner_type {
+    ValueType::LabeledTuple(entries) => entries.clone(),
+
+    ValueType::UnlabeledTuple(entry_types) => entry_types
+      .iter()
+      .enumerate()
+      .map(|(i, entry_type)| (format!("{}", i), entry_type.clone()))
+      .collect(),
+
+    other => vec![("0".to_owned(), other.clone())],
+  }
+}
+
+/// Whether a struct's record has the same layout as its inner type, so
+/// that the two can be converted without copying fields one at a time.
+pub fn is_tuple_struct(inner_type: &ValueType) -> bool {
+  matches!(
+    inner_type,
+    ValueType::LabeledTuple(..) | ValueType::UnlabeledTuple(..)
+  )
+}
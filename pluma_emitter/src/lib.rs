@@ -1,3 +1,22 @@
+mod debug_info;
+mod emit_error;
+mod emit_kind;
 mod emitter;
+mod free_variables;
+mod generics;
+mod layout;
+mod linker;
+mod mangle;
+mod opt_level;
+mod runtime;
+mod scope;
+mod symbols;
+mod target_options;
 
+pub use emit_error::*;
+pub use emit_kind::*;
 pub use emitter::*;
+pub use linker::*;
+pub use opt_level::*;
+pub use symbols::ModuleImports;
+pub use target_options::*;
@@ -0,0 +1,90 @@
+use crate::emit_error::{EmitError, EmitErrorKind};
+use inkwell::support;
+use pluma_diagnostics::*;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::Path;
+use std::process::Command;
+
+/// Links object files into an executable by invoking a system linker
+/// (e.g. `cc` or `ld`).
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Linker {
+  program: String,
+  libs: Vec<String>,
+  search_paths: Vec<String>,
+}
+
+impl Linker {
+  pub fn new(program: String) -> Self {
+    Linker {
+      program,
+      libs: Vec::new(),
+      search_paths: Vec::new(),
+    }
+  }
+
+  /// Libraries to link against (e.g. `m` for `-lm`), for extern defs.
+  pub fn libs(mut self, libs: Vec<String>) -> Self {
+    self.libs = libs;
+    self
+  }
+
+  /// Directories to search for libraries in, before the system ones.
+  pub fn search_paths(mut self, search_paths: Vec<String>) -> Self {
+    self.search_paths = search_paths;
+    self
+  }
+
+  pub fn link(&self, object_path: &Path, output_path: &Path) -> Result<(), Diagnostic> {
+    let output = Command::new(&self.program)
+      .arg(object_path)
+      .arg("-o")
+      .arg(output_path)
+      .args(self.search_paths.iter().map(|path| format!("-L{}", path)))
+      .args(self.libs.iter().map(|lib| format!("-l{}", lib)))
+      .output()
+      .map_err(|err| {
+        Diagnostic::error(EmitError {
+          kind: EmitErrorKind::LinkerUnavailable(self.program.clone(), err.to_string()),
+        })
+      })?;
+
+    if !output.status.success() {
+      let mut linker_output = String::from_utf8_lossy(&output.stderr).into_owned();
+      linker_output.push_str(&String::from_utf8_lossy(&output.stdout));
+
+      return Err(Diagnostic::error(EmitError {
+        kind: EmitErrorKind::LinkFailed(self.program.clone(), linker_output.trim().to_owned()),
+      }));
+    }
+
+    Ok(())
+  }
+
+  /// Loads the libraries into this process instead, so that a program
+  /// that's run in place (i.e. by `pluma run`) can call into them. Each
+  /// library is looked for in the search paths first, like when linking.
+  pub fn load_libs(&self) -> Result<(), Diagnostic> {
+    for lib in &self.libs {
+      let file_name = format!("{}{}{}", DLL_PREFIX, lib, DLL_SUFFIX);
+
+      let path = self
+        .search_paths
+        .iter()
+        .map(|search_path| Path::new(search_path).join(&file_name))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or(file_name);
+
+      // Returns true if the library couldn't be loaded
+      if support::load_library_permanently(&path) {
+        return Err(Diagnostic::error(EmitError {
+          kind: EmitErrorKind::LibraryUnavailable(lib.clone()),
+        }));
+      }
+    }
+
+    Ok(())
+  }
+}
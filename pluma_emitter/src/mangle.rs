@@ -0,0 +1,203 @@
+use pluma_ast::*;
+
+// Symbol names for compiled definitions. Each kind of definition gets its
+// own prefix, so e.g. a function and a method with the same name can never
+// collide with each other (or with C symbols like `main` and `puts`).
+
+pub fn function_name(name_parts: &[String]) -> String {
+  format!("pluma.fn.{}", name_parts.join("."))
+}
+
+pub fn method_name(receiver_type: &ValueType, name_parts: &[String]) -> String {
+  format!(
+    "pluma.method.{}.{}",
+    type_name(receiver_type),
+    name_parts.join(".")
+  )
+}
+
+pub fn binary_operator_name(left_type: &ValueType, op: &str, right_type: &ValueType) -> String {
+  format!(
+    "pluma.binop.{}.{}.{}",
+    type_name(left_type),
+    operator_name(op),
+    type_name(right_type)
+  )
+}
+
+pub fn unary_operator_name(op: &str, right_type: &ValueType) -> String {
+  format!("pluma.unop.{}.{}", operator_name(op), type_name(right_type))
+}
+
+/// One instance of a generic def, compiled for the types that its generic
+/// params stand for, e.g. `pluma.fn.if.then.else<Int>`.
+pub fn instance_name(def_name: &str, type_args: &[ValueType]) -> String {
+  format!(
+    "{}<{}>",
+    def_name,
+    type_args
+      .iter()
+      .map(type_arg_name)
+      .collect::<Vec<String>>()
+      .join(",")
+  )
+}
+
+/// A def's symbol, for the module that defines it, e.g.
+/// `pluma.fn.bar@utils`.
+pub fn module_symbol(name: &str, module_name: &str) -> String {
+  format!("{}@{}", name, module_name)
+}
+
+/// Closures are anonymous, so they all share a name. LLVM makes each
+/// function's name unique by adding a numeric suffix.
+pub fn closure_name() -> String {
+  "pluma.closure".to_owned()
+}
+
+/// Functions in the runtime that's emitted into every module.
+pub fn runtime_name(name: &str) -> String {
+  format!("pluma.rt.{}", name)
+}
+
+/// The function that releases the contents of a heap object whose
+/// payload has this type.
+pub fn drop_name(payload_type: &ValueType) -> String {
+  format!("pluma.drop.{}", type_name(payload_type))
+}
+
+pub fn def_name(kind: &DefKind, param_types: &[ValueType]) -> String {
+  match kind {
+    DefKind::Function { signature } => function_name(&signature_parts(signature)),
+
+    DefKind::Method { signature, .. } => method_name(&param_types[0], &signature_parts(signature)),
+
+    DefKind::BinaryOperator { op, .. } => {
+      binary_operator_name(&param_types[0], &op.name, &param_types[1])
+    }
+
+    DefKind::UnaryOperator { op, .. } => unary_operator_name(&op.name, &param_types[0]),
+  }
+}
+
+fn signature_parts(signature: &Signature) -> Vec<String> {
+  signature
+    .iter()
+    .map(|(part_name, _)| part_name.name.clone())
+    .collect()
+}
+
+fn type_name(typ: &ValueType) -> String {
+  match typ {
+    ValueType::Int => "Int".to_owned(),
+    ValueType::Float => "Float".to_owned(),
+    ValueType::String => "String".to_owned(),
+    ValueType::Named(name) => name.clone(),
+    ValueType::Generic(name, _) => name.clone(),
+    other => format!("{}", other)
+      .chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+      .collect(),
+  }
+}
+
+/// Like `type_name`, but keeping a generic type's args, since instances
+/// for e.g. `Maybe<Int>` and `Maybe<String>` are different functions.
+fn type_arg_name(typ: &ValueType) -> String {
+  match typ {
+    ValueType::Generic(name, args) => instance_name(name, args),
+    other => type_name(other),
+  }
+}
+
+fn operator_name(op: &str) -> String {
+  op.chars()
+    .map(|c| match c {
+      '+' => "plus".to_owned(),
+      '-' => "minus".to_owned(),
+      '*' => "star".to_owned(),
+      '/' => "slash".to_owned(),
+      '%' => "percent".to_owned(),
+      '<' => "lt".to_owned(),
+      '>' => "gt".to_owned(),
+      '=' => "eq".to_owned(),
+      '!' => "bang".to_owned(),
+      '&' => "amp".to_owned(),
+      '|' => "pipe".to_owned(),
+      '^' => "caret".to_owned(),
+      '~' => "tilde".to_owned(),
+      other => format!("u{:x}", other as u32),
+    })
+    .collect::<Vec<String>>()
+    .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn functions() {
+    assert_eq!(function_name(&["print".to_owned()]), "pluma.fn.print");
+    assert_eq!(
+      function_name(&["if".to_owned(), "then".to_owned(), "else".to_owned()]),
+      "pluma.fn.if.then.else"
+    );
+  }
+
+  #[test]
+  fn methods() {
+    assert_eq!(
+      method_name(
+        &ValueType::Named("Person".to_owned()),
+        &["greet".to_owned(), "with".to_owned()]
+      ),
+      "pluma.method.Person.greet.with"
+    );
+  }
+
+  #[test]
+  fn operators() {
+    assert_eq!(
+      binary_operator_name(&ValueType::Int, "+", &ValueType::Int),
+      "pluma.binop.Int.plus.Int"
+    );
+    assert_eq!(
+      binary_operator_name(&ValueType::Float, "<=", &ValueType::Float),
+      "pluma.binop.Float.lt_eq.Float"
+    );
+    assert_eq!(
+      unary_operator_name("-", &ValueType::Int),
+      "pluma.unop.minus.Int"
+    );
+  }
+
+  #[test]
+  fn module_symbols() {
+    assert_eq!(
+      module_symbol(&function_name(&["bar".to_owned()]), "std/io"),
+      "pluma.fn.bar@std/io"
+    );
+  }
+
+  #[test]
+  fn instances() {
+    assert_eq!(
+      instance_name(
+        "pluma.fn.pair.with",
+        &[ValueType::Int, ValueType::Named("Person".to_owned())]
+      ),
+      "pluma.fn.pair.with<Int,Person>"
+    );
+    assert_eq!(
+      instance_name(
+        "pluma.fn.unwrap",
+        &[ValueType::Generic(
+          "Maybe".to_owned(),
+          vec![ValueType::String]
+        )]
+      ),
+      "pluma.fn.unwrap<Maybe<String>>"
+    );
+  }
+}
@@ -0,0 +1,45 @@
+use inkwell::OptimizationLevel;
+
+/// How much effort LLVM should put into optimizing the emitted code. `Os`
+/// and `Oz` optimize like `O2`, but prefer smaller code over faster code.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum OptLevel {
+  O0,
+  O1,
+  O2,
+  O3,
+  Os,
+  Oz,
+}
+
+impl OptLevel {
+  pub(crate) fn llvm_level(self) -> OptimizationLevel {
+    match self {
+      OptLevel::O0 => OptimizationLevel::None,
+      OptLevel::O1 => OptimizationLevel::Less,
+      OptLevel::O2 | OptLevel::Os | OptLevel::Oz => OptimizationLevel::Default,
+      OptLevel::O3 => OptimizationLevel::Aggressive,
+    }
+  }
+
+  pub(crate) fn size_level(self) -> u32 {
+    match self {
+      OptLevel::Os => 1,
+      OptLevel::Oz => 2,
+      _ => 0,
+    }
+  }
+
+  /// The inliner only runs at `O2` and above. These are the same
+  /// thresholds that clang uses for each level.
+  pub(crate) fn inline_threshold(self) -> Option<u32> {
+    match self {
+      OptLevel::O0 | OptLevel::O1 => None,
+      OptLevel::O2 => Some(225),
+      OptLevel::O3 => Some(250),
+      OptLevel::Os => Some(50),
+      OptLevel::Oz => Some(25),
+    }
+  }
+}
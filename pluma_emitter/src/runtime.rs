@@ -0,0 +1,2452 @@
+mod regex;
+mod string_pattern;
+
+use crate::mangle;
+use inkwell::basic_block::BasicBlock;
+use inkwell::builder::Builder;
+use inkwell::context::Context;
+use inkwell::module::{Linkage, Module};
+use inkwell::types::*;
+use inkwell::values::*;
+use inkwell::{AddressSpace, IntPredicate};
+use std::convert::TryFrom;
+
+// Every heap object starts with a header: `{ i64 refcount, void (i8*)* drop }`.
+// Pointers to an object point just past its header, at the object's payload,
+// so compiled code can use them like plain pointers to the payload. When an
+// object's refcount drops to zero, its drop function (if it has one) releases
+// whatever the payload refers to, and then the object is freed.
+//
+// Objects with a negative refcount are immortal, and retain and release leave
+// them alone. String literals are immortal objects in constant memory.
+//
+// Strings are objects with a `{ i64 length, [length x i8] bytes }` payload,
+// holding UTF-8. The bytes are followed by a NUL, which isn't counted in the
+// length, so that strings can be passed to C functions as they are.
+//
+// Lists are objects with a `{ i64 length, i64 capacity, i64 element_size,
+// void (i8*)* element_drop, i8* elements }` payload. The elements live in a
+// separate buffer, which grows as elements are pushed. The element drop
+// function is passed a pointer to an element, like an object's drop function
+// is passed a pointer to its payload.
+//
+// Dicts start with the same fields as lists, and keep their `{ key, value }`
+// entries as the elements, in insertion order. After those come the key's
+// hash and equality functions, and an open addressing hash table (`{ i64
+// table_size, i64* table }`) that maps each key to its entry's index. Empty
+// slots in the table hold -1, and the table is never more than half full.
+
+/// The size of a string's length field, which comes before its bytes.
+const STRING_LENGTH_SIZE: u64 = 8;
+
+const LENGTH_FIELD: u32 = 0;
+const CAPACITY_FIELD: u32 = 1;
+const ELEMENT_SIZE_FIELD: u32 = 2;
+const ELEMENT_DROP_FIELD: u32 = 3;
+const ELEMENTS_FIELD: u32 = 4;
+const HASH_FIELD: u32 = 5;
+const EQUALS_FIELD: u32 = 6;
+const TABLE_SIZE_FIELD: u32 = 7;
+const TABLE_FIELD: u32 = 8;
+
+/// The capacity that a list's buffer starts with, once it has elements.
+const INITIAL_CAPACITY: u64 = 4;
+
+/// The number of slots in a new dict's hash table. Must be a power of two.
+const INITIAL_TABLE_SIZE: u64 = 8;
+
+// 2^64 divided by the golden ratio, which scatters consecutive keys
+const HASH_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The status that programs exit with when they panic, so that a panic can
+/// be told apart from a program calling `exit 1` itself.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// How many of the innermost function calls are recorded for backtraces.
+/// Frames are kept in a ring buffer, so calls past this depth overwrite
+/// the outermost ones.
+const FRAME_LIMIT: u64 = 1024;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The hash and equality functions for one type of Dict key. Both are
+/// passed pointers to keys.
+#[derive(Clone, Copy)]
+pub struct KeyFunctions<'ctx> {
+  pub hash: FunctionValue<'ctx>,
+  pub equals: FunctionValue<'ctx>,
+}
+
+/// The memory management functions that compiled code calls. These are
+/// emitted alongside the program, so every binary carries its own runtime.
+pub struct Runtime<'ctx> {
+  pub alloc: FunctionValue<'ctx>,
+  pub retain: FunctionValue<'ctx>,
+  pub release: FunctionValue<'ctx>,
+  pub report_leaks: FunctionValue<'ctx>,
+  pub string_alloc: FunctionValue<'ctx>,
+  pub string_concat: FunctionValue<'ctx>,
+  pub string_equals: FunctionValue<'ctx>,
+  pub string_slice: FunctionValue<'ctx>,
+  pub string_from_c: FunctionValue<'ctx>,
+  pub string_pattern_match: FunctionValue<'ctx>,
+  pub write_line: FunctionValue<'ctx>,
+  pub panic: FunctionValue<'ctx>,
+  pub frame_push: FunctionValue<'ctx>,
+  pub frame_pop: FunctionValue<'ctx>,
+  pub int_to_string: FunctionValue<'ctx>,
+  pub uint_to_string: FunctionValue<'ctx>,
+  pub float_to_string: FunctionValue<'ctx>,
+  pub list_new: FunctionValue<'ctx>,
+  pub list_push_slot: FunctionValue<'ctx>,
+  pub list_get: FunctionValue<'ctx>,
+  pub dict_new: FunctionValue<'ctx>,
+  pub dict_get: FunctionValue<'ctx>,
+  pub dict_insert_slot: FunctionValue<'ctx>,
+  pub int_key: KeyFunctions<'ctx>,
+  pub float_key: KeyFunctions<'ctx>,
+  /// Keys for the sized numeric types narrower than 64 bits (e.g. `Int8`,
+  /// `UInt32` or `Float32`), which only need to match on their bits.
+  pub bits8_key: KeyFunctions<'ctx>,
+  pub bits16_key: KeyFunctions<'ctx>,
+  pub bits32_key: KeyFunctions<'ctx>,
+  pub string_key: KeyFunctions<'ctx>,
+  pub regex_search: FunctionValue<'ctx>,
+  llvm_context: &'ctx Context,
+  header_type: StructType<'ctx>,
+  drop_fn_type: FunctionType<'ctx>,
+  list_type: StructType<'ctx>,
+}
+
+impl<'ctx> Runtime<'ctx> {
+  pub fn build(llvm_context: &'ctx Context, llvm_module: &Module<'ctx>) -> Self {
+    let builder = RuntimeBuilder::new(llvm_context, llvm_module);
+
+    let alloc = builder.build_alloc();
+    let string_alloc = builder.build_string_alloc(alloc);
+    let string_equals = builder.build_string_equals();
+
+    let write_line = builder.build_write_line();
+
+    let list_drop = builder.build_list_drop();
+    let list_push_slot = builder.build_list_push_slot();
+    let dict_lookup = builder.build_dict_lookup();
+
+    Runtime {
+      alloc,
+      retain: builder.build_retain(),
+      release: builder.build_release(),
+      report_leaks: builder.build_report_leaks(),
+      string_alloc,
+      string_concat: builder.build_string_concat(string_alloc),
+      string_equals,
+      string_slice: builder.build_string_slice(string_alloc),
+      string_from_c: builder.build_string_from_c(string_alloc),
+      string_pattern_match: builder.build_string_pattern_match(),
+      write_line,
+      panic: builder.build_panic(write_line),
+      frame_push: builder.build_frame_push(),
+      frame_pop: builder.build_frame_pop(),
+      int_to_string: builder.build_to_string(
+        "int",
+        llvm_context.i64_type().into(),
+        "%lld",
+        string_alloc,
+      ),
+      uint_to_string: builder.build_to_string(
+        "uint",
+        llvm_context.i64_type().into(),
+        "%llu",
+        string_alloc,
+      ),
+      float_to_string: builder.build_to_string(
+        "float",
+        llvm_context.f64_type().into(),
+        "%g",
+        string_alloc,
+      ),
+      list_new: builder.build_list_new(alloc, list_drop),
+      list_push_slot,
+      list_get: builder.build_list_get(),
+      dict_new: builder.build_dict_new(alloc, list_drop),
+      dict_get: builder.build_dict_get(dict_lookup),
+      dict_insert_slot: builder.build_dict_insert_slot(dict_lookup, list_push_slot),
+      int_key: builder.build_number_key_functions("int", llvm_context.i64_type()),
+      float_key: builder.build_number_key_functions("float", llvm_context.i64_type()),
+      bits8_key: builder.build_number_key_functions("bits8", llvm_context.i8_type()),
+      bits16_key: builder.build_number_key_functions("bits16", llvm_context.i16_type()),
+      bits32_key: builder.build_number_key_functions("bits32", llvm_context.i32_type()),
+      string_key: builder.build_string_key_functions(string_equals),
+      regex_search: builder.build_regex_search(),
+      llvm_context,
+      header_type: builder.header_type,
+      drop_fn_type: builder.drop_fn_type,
+      list_type: builder.list_type,
+    }
+  }
+
+  /// The type of the functions that release an object's payload. They're
+  /// passed a pointer to the payload.
+  pub fn drop_fn_type(&self) -> FunctionType<'ctx> {
+    self.drop_fn_type
+  }
+
+  /// A header for an object that is never freed.
+  pub fn immortal_header(&self) -> StructValue<'ctx> {
+    let refcount = self.llvm_context.i64_type().const_int(-1i64 as u64, true);
+
+    let drop_fn = self
+      .drop_fn_type
+      .ptr_type(AddressSpace::Generic)
+      .const_null();
+
+    self
+      .header_type
+      .const_named_struct(&[refcount.into(), drop_fn.into()])
+  }
+
+  /// A string's payload, for a string literal with the given bytes.
+  pub fn const_string_payload(&self, value: &str) -> StructValue<'ctx> {
+    let length = self
+      .llvm_context
+      .i64_type()
+      .const_int(value.len() as u64, false);
+
+    let bytes = self.llvm_context.const_string(value.as_bytes(), true);
+
+    self
+      .llvm_context
+      .const_struct(&[length.into(), bytes.into()], false)
+  }
+
+  pub fn build_string_length(
+    &self,
+    builder: &Builder<'ctx>,
+    string: PointerValue<'ctx>,
+  ) -> IntValue<'ctx> {
+    build_string_length(self.llvm_context, builder, string)
+  }
+
+  pub fn build_string_bytes(
+    &self,
+    builder: &Builder<'ctx>,
+    string: PointerValue<'ctx>,
+  ) -> PointerValue<'ctx> {
+    build_string_bytes(self.llvm_context, builder, string)
+  }
+
+  /// The number of elements in a list, or entries in a dict.
+  pub fn build_length(
+    &self,
+    builder: &Builder<'ctx>,
+    collection: PointerValue<'ctx>,
+  ) -> IntValue<'ctx> {
+    let list = builder.build_pointer_cast(
+      collection,
+      self.list_type.ptr_type(AddressSpace::Generic),
+      "list",
+    );
+
+    let length_ptr = builder
+      .build_struct_gep(list, LENGTH_FIELD, "length")
+      .unwrap();
+
+    builder.build_load(length_ptr, "length").into_int_value()
+  }
+}
+
+struct RuntimeBuilder<'a, 'ctx> {
+  llvm_context: &'ctx Context,
+  llvm_module: &'a Module<'ctx>,
+  llvm_builder: Builder<'ctx>,
+  header_type: StructType<'ctx>,
+  drop_fn_type: FunctionType<'ctx>,
+  list_type: StructType<'ctx>,
+  dict_type: StructType<'ctx>,
+  hash_fn_type: FunctionType<'ctx>,
+  equals_fn_type: FunctionType<'ctx>,
+  regex_type: StructType<'ctx>,
+  live_objects: PointerValue<'ctx>,
+  frames: PointerValue<'ctx>,
+  frame_count: PointerValue<'ctx>,
+}
+
+impl<'a, 'ctx> RuntimeBuilder<'a, 'ctx> {
+  fn new(llvm_context: &'ctx Context, llvm_module: &'a Module<'ctx>) -> Self {
+    let i64_type = llvm_context.i64_type();
+
+    let drop_fn_type = llvm_context
+      .void_type()
+      .fn_type(&[opaque_ptr_type(llvm_context).into()], false);
+
+    let header_type = llvm_context.struct_type(
+      &[
+        i64_type.into(),
+        drop_fn_type.ptr_type(AddressSpace::Generic).into(),
+      ],
+      false,
+    );
+
+    let ptr_type = opaque_ptr_type(llvm_context);
+    let drop_fn_ptr_type = drop_fn_type.ptr_type(AddressSpace::Generic);
+
+    let list_fields = [
+      i64_type.into(),
+      i64_type.into(),
+      i64_type.into(),
+      drop_fn_ptr_type.into(),
+      ptr_type.into(),
+    ];
+
+    let list_type = llvm_context.struct_type(&list_fields, false);
+
+    let hash_fn_type = i64_type.fn_type(&[ptr_type.into()], false);
+    let equals_fn_type = llvm_context
+      .bool_type()
+      .fn_type(&[ptr_type.into(), ptr_type.into()], false);
+
+    let mut dict_fields = list_fields.to_vec();
+    dict_fields.extend_from_slice(&[
+      hash_fn_type.ptr_type(AddressSpace::Generic).into(),
+      equals_fn_type.ptr_type(AddressSpace::Generic).into(),
+      i64_type.into(),
+      i64_type.ptr_type(AddressSpace::Generic).into(),
+    ]);
+
+    let dict_type = llvm_context.struct_type(&dict_fields, false);
+
+    let regex_type = llvm_context.struct_type(
+      &[
+        i64_type.into(),
+        i64_type.into(),
+        llvm_context.i32_type().array_type(0).into(),
+      ],
+      false,
+    );
+
+    let live_objects =
+      llvm_module.add_global(i64_type, None, &mangle::runtime_name("live_objects"));
+    live_objects.set_linkage(Linkage::Internal);
+    live_objects.set_initializer(&i64_type.const_zero());
+
+    let frames_type = ptr_type.array_type(FRAME_LIMIT as u32);
+    let frames = llvm_module.add_global(frames_type, None, &mangle::runtime_name("frames"));
+    frames.set_linkage(Linkage::Internal);
+    frames.set_initializer(&frames_type.const_zero());
+
+    let frame_count = llvm_module.add_global(i64_type, None, &mangle::runtime_name("frame_count"));
+    frame_count.set_linkage(Linkage::Internal);
+    frame_count.set_initializer(&i64_type.const_zero());
+
+    RuntimeBuilder {
+      llvm_context,
+      llvm_module,
+      llvm_builder: llvm_context.create_builder(),
+      header_type,
+      drop_fn_type,
+      list_type,
+      dict_type,
+      hash_fn_type,
+      equals_fn_type,
+      regex_type,
+      live_objects: live_objects.as_pointer_value(),
+      frames: frames.as_pointer_value(),
+      frame_count: frame_count.as_pointer_value(),
+    }
+  }
+
+  /// `i8* alloc(i64 size, drop_fn drop)`: allocates an object with the
+  /// given payload size and a refcount of 1.
+  fn build_alloc(&self) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let function = self.add_function(
+      "alloc",
+      opaque_ptr_type(self.llvm_context).fn_type(
+        &[
+          i64_type.into(),
+          self.drop_fn_type.ptr_type(AddressSpace::Generic).into(),
+        ],
+        false,
+      ),
+    );
+
+    let malloc = self.declare_c_function(
+      "malloc",
+      opaque_ptr_type(self.llvm_context).fn_type(&[i64_type.into()], false),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let payload_size = function.get_nth_param(0).unwrap().into_int_value();
+    let drop_fn = function.get_nth_param(1).unwrap();
+
+    let header_size = self.header_type.size_of().expect("header should be sized");
+
+    let size = self
+      .llvm_builder
+      .build_int_add(payload_size, header_size, "size");
+
+    let memory = self
+      .llvm_builder
+      .build_call(malloc, &[size.into()], "memory")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let header = self.llvm_builder.build_pointer_cast(
+      memory,
+      self.header_type.ptr_type(AddressSpace::Generic),
+      "header",
+    );
+
+    let refcount_ptr = self
+      .llvm_builder
+      .build_struct_gep(header, 0, "refcount")
+      .unwrap();
+    let drop_fn_ptr = self
+      .llvm_builder
+      .build_struct_gep(header, 1, "drop")
+      .unwrap();
+
+    self
+      .llvm_builder
+      .build_store(refcount_ptr, i64_type.const_int(1, false));
+    self.llvm_builder.build_store(drop_fn_ptr, drop_fn);
+
+    self.add_to_live_objects(1);
+
+    let payload = unsafe {
+      self
+        .llvm_builder
+        .build_gep(header, &[i64_type.const_int(1, false)], "payload")
+    };
+
+    let payload =
+      self
+        .llvm_builder
+        .build_pointer_cast(payload, opaque_ptr_type(self.llvm_context), "payload");
+
+    self.llvm_builder.build_return(Some(&payload));
+
+    function
+  }
+
+  /// `void retain(i8* object)`
+  fn build_retain(&self) -> FunctionValue<'ctx> {
+    let function = self.add_function("retain", self.drop_fn_type);
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let increment_block = self.llvm_context.append_basic_block(function, "increment");
+    let done_block = self.llvm_context.append_basic_block(function, "done");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let object = function.get_nth_param(0).unwrap().into_pointer_value();
+    let refcount_ptr = self.build_mortal_refcount_ptr(object, increment_block, done_block);
+
+    let refcount = self
+      .llvm_builder
+      .build_load(refcount_ptr, "refcount")
+      .into_int_value();
+
+    let refcount = self.llvm_builder.build_int_add(
+      refcount,
+      self.llvm_context.i64_type().const_int(1, false),
+      "refcount",
+    );
+
+    self.llvm_builder.build_store(refcount_ptr, refcount);
+    self.llvm_builder.build_unconditional_branch(done_block);
+
+    self.llvm_builder.position_at_end(done_block);
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `void release(i8* object)`
+  fn build_release(&self) -> FunctionValue<'ctx> {
+    let function = self.add_function("release", self.drop_fn_type);
+
+    let free = self.declare_c_function(
+      "free",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[opaque_ptr_type(self.llvm_context).into()], false),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let decrement_block = self.llvm_context.append_basic_block(function, "decrement");
+    let drop_block = self.llvm_context.append_basic_block(function, "drop");
+    let free_block = self.llvm_context.append_basic_block(function, "free");
+    let done_block = self.llvm_context.append_basic_block(function, "done");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let object = function.get_nth_param(0).unwrap().into_pointer_value();
+    let refcount_ptr = self.build_mortal_refcount_ptr(object, decrement_block, done_block);
+
+    let refcount = self
+      .llvm_builder
+      .build_load(refcount_ptr, "refcount")
+      .into_int_value();
+
+    let i64_type = self.llvm_context.i64_type();
+    let refcount =
+      self
+        .llvm_builder
+        .build_int_sub(refcount, i64_type.const_int(1, false), "refcount");
+
+    self.llvm_builder.build_store(refcount_ptr, refcount);
+
+    let is_unused = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      refcount,
+      i64_type.const_zero(),
+      "unused",
+    );
+
+    let check_drop_block = self.llvm_context.append_basic_block(function, "check_drop");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_unused, check_drop_block, done_block);
+
+    self.llvm_builder.position_at_end(check_drop_block);
+
+    let header = self.build_header_ptr(object);
+
+    // Objects without anything to release have no drop function
+    let drop_fn_ptr = self
+      .llvm_builder
+      .build_struct_gep(header, 1, "drop")
+      .unwrap();
+    let drop_fn = self
+      .llvm_builder
+      .build_load(drop_fn_ptr, "drop")
+      .into_pointer_value();
+
+    let has_drop_fn = self.llvm_builder.build_is_not_null(drop_fn, "has_drop");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(has_drop_fn, drop_block, free_block);
+
+    self.llvm_builder.position_at_end(drop_block);
+
+    let drop_fn = CallableValue::try_from(drop_fn).expect("drop should be a function pointer");
+    self.llvm_builder.build_call(drop_fn, &[object.into()], "");
+    self.llvm_builder.build_unconditional_branch(free_block);
+
+    self.llvm_builder.position_at_end(free_block);
+
+    let memory =
+      self
+        .llvm_builder
+        .build_pointer_cast(header, opaque_ptr_type(self.llvm_context), "memory");
+
+    self.llvm_builder.build_call(free, &[memory.into()], "");
+    self.add_to_live_objects(-1);
+    self.llvm_builder.build_unconditional_branch(done_block);
+
+    self.llvm_builder.position_at_end(done_block);
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `void report_leaks()`: prints the number of objects that haven't
+  /// been freed to stderr.
+  fn build_report_leaks(&self) -> FunctionValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+
+    let function = self.add_function(
+      "report_leaks",
+      self.llvm_context.void_type().fn_type(&[], false),
+    );
+
+    let dprintf = self.declare_c_function(
+      "dprintf",
+      i32_type.fn_type(
+        &[i32_type.into(), opaque_ptr_type(self.llvm_context).into()],
+        true,
+      ),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let report_block = self.llvm_context.append_basic_block(function, "report");
+    let done_block = self.llvm_context.append_basic_block(function, "done");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let live_objects = self
+      .llvm_builder
+      .build_load(self.live_objects, "live")
+      .into_int_value();
+
+    let has_leaks = self.llvm_builder.build_int_compare(
+      IntPredicate::NE,
+      live_objects,
+      i64_type.const_zero(),
+      "has_leaks",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(has_leaks, report_block, done_block);
+
+    self.llvm_builder.position_at_end(report_block);
+
+    let message = self
+      .llvm_builder
+      .build_global_string_ptr("pluma: %lld heap object(s) leaked\n", "leak_message");
+
+    let stderr = i32_type.const_int(2, false);
+
+    self.llvm_builder.build_call(
+      dprintf,
+      &[
+        stderr.into(),
+        message.as_pointer_value().into(),
+        live_objects.into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_unconditional_branch(done_block);
+
+    self.llvm_builder.position_at_end(done_block);
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `i8* string_alloc(i64 length)`: allocates a string with room for
+  /// `length` bytes, which the caller fills in.
+  fn build_string_alloc(&self, alloc: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let function = self.add_function(
+      "string_alloc",
+      opaque_ptr_type(self.llvm_context).fn_type(&[i64_type.into()], false),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let length = function.get_nth_param(0).unwrap().into_int_value();
+
+    // The length field, the bytes and the NUL after them
+    let size = self.llvm_builder.build_int_add(
+      length,
+      i64_type.const_int(STRING_LENGTH_SIZE + 1, false),
+      "size",
+    );
+
+    let no_drop_fn = self
+      .drop_fn_type
+      .ptr_type(AddressSpace::Generic)
+      .const_null();
+
+    let string = self
+      .llvm_builder
+      .build_call(alloc, &[size.into(), no_drop_fn.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let length_ptr = self.llvm_builder.build_pointer_cast(
+      string,
+      i64_type.ptr_type(AddressSpace::Generic),
+      "length",
+    );
+
+    self.llvm_builder.build_store(length_ptr, length);
+
+    let bytes = self.build_string_bytes(string);
+    let end = unsafe { self.llvm_builder.build_gep(bytes, &[length], "end") };
+
+    self
+      .llvm_builder
+      .build_store(end, self.llvm_context.i8_type().const_zero());
+
+    self.llvm_builder.build_return(Some(&string));
+
+    function
+  }
+
+  /// `i8* string_concat(i8* left, i8* right)`
+  fn build_string_concat(&self, string_alloc: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "string_concat",
+      string_type.fn_type(&[string_type.into(), string_type.into()], false),
+    );
+
+    let memcpy = self.declare_memcpy();
+
+    self.position_at_new_block(function, "entry");
+
+    let left = function.get_nth_param(0).unwrap().into_pointer_value();
+    let right = function.get_nth_param(1).unwrap().into_pointer_value();
+
+    let left_length = self.build_string_length(left);
+    let right_length = self.build_string_length(right);
+
+    let length = self
+      .llvm_builder
+      .build_int_add(left_length, right_length, "length");
+
+    let string = self
+      .llvm_builder
+      .build_call(string_alloc, &[length.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let bytes = self.build_string_bytes(string);
+    let right_bytes = unsafe { self.llvm_builder.build_gep(bytes, &[left_length], "right") };
+
+    self.llvm_builder.build_call(
+      memcpy,
+      &[
+        bytes.into(),
+        self.build_string_bytes(left).into(),
+        left_length.into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_call(
+      memcpy,
+      &[
+        right_bytes.into(),
+        self.build_string_bytes(right).into(),
+        right_length.into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_return(Some(&string));
+
+    function
+  }
+
+  /// `i8* string_slice(i8* string, i64 start, i64 end)`: a new string
+  /// with the bytes from `start` up to (but not including) `end`.
+  fn build_string_slice(&self, string_alloc: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "string_slice",
+      string_type.fn_type(
+        &[string_type.into(), i64_type.into(), i64_type.into()],
+        false,
+      ),
+    );
+
+    let memcpy = self.declare_memcpy();
+
+    self.position_at_new_block(function, "entry");
+
+    let source = function.get_nth_param(0).unwrap().into_pointer_value();
+    let start = function.get_nth_param(1).unwrap().into_int_value();
+    let end = function.get_nth_param(2).unwrap().into_int_value();
+
+    let length = self.llvm_builder.build_int_sub(end, start, "length");
+
+    let string = self
+      .llvm_builder
+      .build_call(string_alloc, &[length.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let source_bytes = self.build_string_bytes(source);
+    let source_bytes = unsafe { self.llvm_builder.build_gep(source_bytes, &[start], "start") };
+
+    self.llvm_builder.build_call(
+      memcpy,
+      &[
+        self.build_string_bytes(string).into(),
+        source_bytes.into(),
+        length.into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_return(Some(&string));
+
+    function
+  }
+
+  /// `i8* string_from_c(i8* bytes)`: copies a NUL-terminated C string
+  /// into a new string. A null pointer becomes an empty string. The C
+  /// string is left for its owner to free.
+  fn build_string_from_c(&self, string_alloc: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "string_from_c",
+      string_type.fn_type(&[string_type.into()], false),
+    );
+
+    let memcpy = self.declare_memcpy();
+    let strlen = self.declare_c_function("strlen", i64_type.fn_type(&[string_type.into()], false));
+
+    self.position_at_new_block(function, "entry");
+
+    let source = function.get_nth_param(0).unwrap().into_pointer_value();
+
+    let null_block = self.llvm_context.append_basic_block(function, "null");
+    let copy_block = self.llvm_context.append_basic_block(function, "copy");
+
+    let is_null = self.llvm_builder.build_is_null(source, "is_null");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_null, null_block, copy_block);
+
+    self.llvm_builder.position_at_end(null_block);
+
+    let empty = self
+      .llvm_builder
+      .build_call(string_alloc, &[i64_type.const_zero().into()], "empty")
+      .try_as_basic_value()
+      .left()
+      .unwrap();
+
+    self.llvm_builder.build_return(Some(&empty));
+
+    self.llvm_builder.position_at_end(copy_block);
+
+    let length = self
+      .llvm_builder
+      .build_call(strlen, &[source.into()], "length")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    let string = self
+      .llvm_builder
+      .build_call(string_alloc, &[length.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    self.llvm_builder.build_call(
+      memcpy,
+      &[
+        self.build_string_bytes(string).into(),
+        source.into(),
+        length.into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_return(Some(&string));
+
+    function
+  }
+
+  /// `i1 string_equals(i8* left, i8* right)`: whether two strings have
+  /// the same bytes.
+  fn build_string_equals(&self) -> FunctionValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let bool_type = self.llvm_context.bool_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "string_equals",
+      bool_type.fn_type(&[string_type.into(), string_type.into()], false),
+    );
+
+    let memcmp = self.declare_memcmp();
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let compare_block = self.llvm_context.append_basic_block(function, "compare");
+    let different_block = self.llvm_context.append_basic_block(function, "different");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let left = function.get_nth_param(0).unwrap().into_pointer_value();
+    let right = function.get_nth_param(1).unwrap().into_pointer_value();
+
+    let left_length = self.build_string_length(left);
+    let right_length = self.build_string_length(right);
+
+    let same_length = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      left_length,
+      right_length,
+      "same_length",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(same_length, compare_block, different_block);
+
+    self.llvm_builder.position_at_end(compare_block);
+
+    let order = self
+      .llvm_builder
+      .build_call(
+        memcmp,
+        &[
+          self.build_string_bytes(left).into(),
+          self.build_string_bytes(right).into(),
+          left_length.into(),
+        ],
+        "order",
+      )
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    let same_bytes = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      order,
+      i32_type.const_zero(),
+      "same_bytes",
+    );
+
+    self.llvm_builder.build_return(Some(&same_bytes));
+
+    self.llvm_builder.position_at_end(different_block);
+    self
+      .llvm_builder
+      .build_return(Some(&bool_type.const_zero()));
+
+    function
+  }
+
+  /// `void write_line(i32 fd, i8* string)`: writes a string to a file
+  /// descriptor, followed by a newline.
+  fn build_write_line(&self) -> FunctionValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "write_line",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[i32_type.into(), string_type.into()], false),
+    );
+
+    let write = self.declare_c_function(
+      "write",
+      i64_type.fn_type(
+        &[i32_type.into(), string_type.into(), i64_type.into()],
+        false,
+      ),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let fd = function.get_nth_param(0).unwrap();
+    let string = function.get_nth_param(1).unwrap().into_pointer_value();
+
+    let length = self.build_string_length(string);
+    let bytes = self.build_string_bytes(string);
+
+    self
+      .llvm_builder
+      .build_call(write, &[fd, bytes.into(), length.into()], "");
+
+    let newline = self.llvm_builder.build_global_string_ptr("\n", "newline");
+
+    self.llvm_builder.build_call(
+      write,
+      &[
+        fd,
+        newline.as_pointer_value().into(),
+        i64_type.const_int(1, false).into(),
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `i8* <kind>_to_string(<value_type> value)`: formats a number with
+  /// `snprintf`, measuring it first so the string can be allocated.
+  fn build_to_string(
+    &self,
+    kind: &str,
+    value_type: BasicTypeEnum<'ctx>,
+    format: &str,
+    string_alloc: FunctionValue<'ctx>,
+  ) -> FunctionValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      &format!("{}_to_string", kind),
+      string_type.fn_type(&[value_type], false),
+    );
+
+    let snprintf = self.declare_c_function(
+      "snprintf",
+      i32_type.fn_type(
+        &[string_type.into(), i64_type.into(), string_type.into()],
+        true,
+      ),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let value = function.get_nth_param(0).unwrap();
+
+    let format = self
+      .llvm_builder
+      .build_global_string_ptr(format, "format")
+      .as_pointer_value();
+
+    let length = self
+      .llvm_builder
+      .build_call(
+        snprintf,
+        &[
+          string_type.const_null().into(),
+          i64_type.const_zero().into(),
+          format.into(),
+          value,
+        ],
+        "length",
+      )
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    let length = self
+      .llvm_builder
+      .build_int_s_extend(length, i64_type, "length");
+
+    let string = self
+      .llvm_builder
+      .build_call(string_alloc, &[length.into()], "string")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    // snprintf's size includes the NUL, which string_alloc made room for
+    let size = self
+      .llvm_builder
+      .build_int_add(length, i64_type.const_int(1, false), "size");
+
+    self.llvm_builder.build_call(
+      snprintf,
+      &[
+        self.build_string_bytes(string).into(),
+        size.into(),
+        format.into(),
+        value,
+      ],
+      "",
+    );
+
+    self.llvm_builder.build_return(Some(&string));
+
+    function
+  }
+
+  /// `void panic(i8* message)`: writes a string to stderr and exits the
+  /// program with `PANIC_EXIT_CODE`. When `PLUMA_BACKTRACE=1` is set, the
+  /// functions that were running are listed after it, innermost first.
+  fn build_panic(&self, write_line: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let i8_type = self.llvm_context.i8_type();
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "panic",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[string_type.into()], false),
+    );
+
+    let getenv =
+      self.declare_c_function("getenv", string_type.fn_type(&[string_type.into()], false));
+
+    let dprintf = self.declare_c_function(
+      "dprintf",
+      i32_type.fn_type(&[i32_type.into(), string_type.into()], true),
+    );
+
+    let exit = self.declare_c_function(
+      "exit",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[i32_type.into()], false),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let check_block = self
+      .llvm_context
+      .append_basic_block(function, "check_setting");
+    let backtrace_block = self.llvm_context.append_basic_block(function, "backtrace");
+    let exit_block = self.llvm_context.append_basic_block(function, "exit");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let stderr = i32_type.const_int(2, false);
+    let message = function.get_nth_param(0).unwrap();
+
+    self
+      .llvm_builder
+      .build_call(write_line, &[stderr.into(), message], "");
+
+    let setting_name = self
+      .llvm_builder
+      .build_global_string_ptr("PLUMA_BACKTRACE", "backtrace_setting");
+
+    let setting = self
+      .llvm_builder
+      .build_call(getenv, &[setting_name.as_pointer_value().into()], "setting")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let is_unset = self.llvm_builder.build_is_null(setting, "is_unset");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_unset, exit_block, check_block);
+
+    self.llvm_builder.position_at_end(check_block);
+
+    let first_char = self
+      .llvm_builder
+      .build_load(setting, "first_char")
+      .into_int_value();
+
+    let is_on = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      first_char,
+      i8_type.const_int(b'1' as u64, false),
+      "is_on",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_on, backtrace_block, exit_block);
+
+    self.llvm_builder.position_at_end(backtrace_block);
+
+    let header = self
+      .llvm_builder
+      .build_global_string_ptr("\nBacktrace:\n", "backtrace_header");
+    let frame_format = self
+      .llvm_builder
+      .build_global_string_ptr("  %lld: %s\n", "backtrace_frame");
+
+    self.llvm_builder.build_call(
+      dprintf,
+      &[stderr.into(), header.as_pointer_value().into()],
+      "",
+    );
+
+    // Only the innermost frames are still in the ring buffer, so the
+    // frames are walked from the innermost outward
+    let frame_count = self
+      .llvm_builder
+      .build_load(self.frame_count, "frame_count")
+      .into_int_value();
+    let limit = i64_type.const_int(FRAME_LIMIT, false);
+    let is_over_limit =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::UGT, frame_count, limit, "is_over_limit");
+    let recorded_count = self
+      .llvm_builder
+      .build_select(is_over_limit, limit, frame_count, "recorded_count")
+      .into_int_value();
+
+    self.build_loop(function, recorded_count, |i| {
+      let last = self
+        .llvm_builder
+        .build_int_sub(frame_count, i64_type.const_int(1, false), "last");
+      let index = self.llvm_builder.build_int_sub(last, i, "index");
+
+      let frame_ptr = self.build_frame_slot(index);
+      let name = self.llvm_builder.build_load(frame_ptr, "name");
+
+      self.llvm_builder.build_call(
+        dprintf,
+        &[
+          stderr.into(),
+          frame_format.as_pointer_value().into(),
+          i.into(),
+          name,
+        ],
+        "",
+      );
+    });
+
+    self.llvm_builder.build_unconditional_branch(exit_block);
+
+    self.llvm_builder.position_at_end(exit_block);
+
+    let status = i32_type.const_int(PANIC_EXIT_CODE as u64, false);
+
+    self.llvm_builder.build_call(exit, &[status.into()], "");
+    self.llvm_builder.build_unreachable();
+
+    function
+  }
+
+  /// `void frame_push(i8* name)`: records that a function has been called,
+  /// for backtraces. `name` is a C string.
+  fn build_frame_push(&self) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "frame_push",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[string_type.into()], false),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let frame_count = self
+      .llvm_builder
+      .build_load(self.frame_count, "frame_count")
+      .into_int_value();
+
+    let frame_ptr = self.build_frame_slot(frame_count);
+
+    self
+      .llvm_builder
+      .build_store(frame_ptr, function.get_nth_param(0).unwrap());
+
+    let frame_count =
+      self
+        .llvm_builder
+        .build_int_add(frame_count, i64_type.const_int(1, false), "frame_count");
+
+    self.llvm_builder.build_store(self.frame_count, frame_count);
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// The slot in the ring buffer of frames that the frame at `index` (the
+  /// number of calls outside it) is recorded in.
+  fn build_frame_slot(&self, index: IntValue<'ctx>) -> PointerValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let slot = self.llvm_builder.build_int_unsigned_rem(
+      index,
+      i64_type.const_int(FRAME_LIMIT, false),
+      "slot",
+    );
+
+    unsafe {
+      self
+        .llvm_builder
+        .build_in_bounds_gep(self.frames, &[i64_type.const_zero(), slot], "frame")
+    }
+  }
+
+  /// `void frame_pop()`: records that the last function called has returned.
+  fn build_frame_pop(&self) -> FunctionValue<'ctx> {
+    let function = self.add_function(
+      "frame_pop",
+      self.llvm_context.void_type().fn_type(&[], false),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let frame_count = self
+      .llvm_builder
+      .build_load(self.frame_count, "frame_count")
+      .into_int_value();
+
+    let frame_count = self.llvm_builder.build_int_sub(
+      frame_count,
+      self.llvm_context.i64_type().const_int(1, false),
+      "frame_count",
+    );
+
+    self.llvm_builder.build_store(self.frame_count, frame_count);
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `i8* list_new(i64 element_size, drop_fn element_drop)`
+  fn build_list_new(
+    &self,
+    alloc: FunctionValue<'ctx>,
+    list_drop: FunctionValue<'ctx>,
+  ) -> FunctionValue<'ctx> {
+    let function = self.add_function(
+      "list_new",
+      opaque_ptr_type(self.llvm_context).fn_type(
+        &[
+          self.llvm_context.i64_type().into(),
+          self.drop_fn_type.ptr_type(AddressSpace::Generic).into(),
+        ],
+        false,
+      ),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let object = self.build_collection_alloc(alloc, self.list_type, list_drop);
+    let list = self.build_list_ptr(object);
+
+    self.build_init_list_fields(
+      list,
+      function.get_nth_param(0).unwrap(),
+      function.get_nth_param(1).unwrap(),
+    );
+
+    self.llvm_builder.build_return(Some(&object));
+
+    function
+  }
+
+  /// `void list_drop(i8* list)`: releases a list's elements and frees its
+  /// buffer.
+  fn build_list_drop(&self) -> FunctionValue<'ctx> {
+    let function = self.add_function("list_drop", self.drop_fn_type);
+    let free = self.declare_free();
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let drop_elements_block = self
+      .llvm_context
+      .append_basic_block(function, "drop_elements");
+    let free_block = self.llvm_context.append_basic_block(function, "free");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let list = self.build_list_ptr(function.get_nth_param(0).unwrap().into_pointer_value());
+
+    // Elements without anything to release have no drop function
+    let element_drop = self
+      .load_field(list, ELEMENT_DROP_FIELD, "element_drop")
+      .into_pointer_value();
+
+    let has_element_drop = self
+      .llvm_builder
+      .build_is_not_null(element_drop, "has_element_drop");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(has_element_drop, drop_elements_block, free_block);
+
+    self.llvm_builder.position_at_end(drop_elements_block);
+
+    let length = self
+      .load_field(list, LENGTH_FIELD, "length")
+      .into_int_value();
+
+    self.build_loop(function, length, |index| {
+      let element = self.build_element_ptr(list, index);
+      let element_drop =
+        CallableValue::try_from(element_drop).expect("element drop should be a function pointer");
+
+      self
+        .llvm_builder
+        .build_call(element_drop, &[element.into()], "");
+    });
+
+    self.llvm_builder.build_unconditional_branch(free_block);
+
+    self.llvm_builder.position_at_end(free_block);
+
+    let elements = self.load_field(list, ELEMENTS_FIELD, "elements");
+
+    self.llvm_builder.build_call(free, &[elements], "");
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `i8* list_push_slot(i8* list)`: adds an element to the end of a list,
+  /// growing its buffer if it's full. Returns a pointer to the new element,
+  /// which the caller initializes.
+  fn build_list_push_slot(&self) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "list_push_slot",
+      ptr_type.fn_type(&[ptr_type.into()], false),
+    );
+
+    let realloc = self.declare_c_function(
+      "realloc",
+      ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let grow_block = self.llvm_context.append_basic_block(function, "grow");
+    let push_block = self.llvm_context.append_basic_block(function, "push");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let list = self.build_list_ptr(function.get_nth_param(0).unwrap().into_pointer_value());
+    let length = self
+      .load_field(list, LENGTH_FIELD, "length")
+      .into_int_value();
+    let capacity = self
+      .load_field(list, CAPACITY_FIELD, "capacity")
+      .into_int_value();
+
+    let is_full =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::EQ, length, capacity, "is_full");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_full, grow_block, push_block);
+
+    self.llvm_builder.position_at_end(grow_block);
+
+    let is_empty = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      capacity,
+      i64_type.const_zero(),
+      "is_empty",
+    );
+
+    let doubled_capacity =
+      self
+        .llvm_builder
+        .build_int_mul(capacity, i64_type.const_int(2, false), "doubled");
+
+    let capacity = self
+      .llvm_builder
+      .build_select(
+        is_empty,
+        i64_type.const_int(INITIAL_CAPACITY, false),
+        doubled_capacity,
+        "capacity",
+      )
+      .into_int_value();
+
+    let element_size = self
+      .load_field(list, ELEMENT_SIZE_FIELD, "element_size")
+      .into_int_value();
+
+    let size = self
+      .llvm_builder
+      .build_int_mul(capacity, element_size, "size");
+
+    let elements = self.load_field(list, ELEMENTS_FIELD, "elements");
+
+    let elements = self
+      .llvm_builder
+      .build_call(realloc, &[elements, size.into()], "elements")
+      .try_as_basic_value()
+      .left()
+      .unwrap();
+
+    self.store_field(list, ELEMENTS_FIELD, elements);
+    self.store_field(list, CAPACITY_FIELD, capacity.into());
+    self.llvm_builder.build_unconditional_branch(push_block);
+
+    self.llvm_builder.position_at_end(push_block);
+
+    let slot = self.build_element_ptr(list, length);
+
+    let length = self
+      .llvm_builder
+      .build_int_add(length, i64_type.const_int(1, false), "length");
+
+    self.store_field(list, LENGTH_FIELD, length.into());
+    self.llvm_builder.build_return(Some(&slot));
+
+    function
+  }
+
+  /// `i8* list_get(i8* list, i64 index)`: a pointer to the element at an
+  /// index, or null if the index is out of bounds.
+  fn build_list_get(&self) -> FunctionValue<'ctx> {
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "list_get",
+      ptr_type.fn_type(
+        &[ptr_type.into(), self.llvm_context.i64_type().into()],
+        false,
+      ),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let in_bounds_block = self.llvm_context.append_basic_block(function, "in_bounds");
+    let out_of_bounds_block = self
+      .llvm_context
+      .append_basic_block(function, "out_of_bounds");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let list = self.build_list_ptr(function.get_nth_param(0).unwrap().into_pointer_value());
+    let index = function.get_nth_param(1).unwrap().into_int_value();
+    let length = self
+      .load_field(list, LENGTH_FIELD, "length")
+      .into_int_value();
+
+    // Negative indexes are huge when they're compared as unsigned
+    let is_in_bounds =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULT, index, length, "is_in_bounds");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_in_bounds, in_bounds_block, out_of_bounds_block);
+
+    self.llvm_builder.position_at_end(in_bounds_block);
+
+    let element = self.build_element_ptr(list, index);
+    self.llvm_builder.build_return(Some(&element));
+
+    self.llvm_builder.position_at_end(out_of_bounds_block);
+    self.llvm_builder.build_return(Some(&ptr_type.const_null()));
+
+    function
+  }
+
+  /// `i8* dict_new(i64 entry_size, drop_fn entry_drop, hash_fn hash,
+  /// equals_fn equals)`
+  fn build_dict_new(
+    &self,
+    alloc: FunctionValue<'ctx>,
+    list_drop: FunctionValue<'ctx>,
+  ) -> FunctionValue<'ctx> {
+    let dict_drop = self.build_dict_drop(list_drop);
+
+    let function = self.add_function(
+      "dict_new",
+      opaque_ptr_type(self.llvm_context).fn_type(
+        &[
+          self.llvm_context.i64_type().into(),
+          self.drop_fn_type.ptr_type(AddressSpace::Generic).into(),
+          self.hash_fn_type.ptr_type(AddressSpace::Generic).into(),
+          self.equals_fn_type.ptr_type(AddressSpace::Generic).into(),
+        ],
+        false,
+      ),
+    );
+
+    self.position_at_new_block(function, "entry");
+
+    let object = self.build_collection_alloc(alloc, self.dict_type, dict_drop);
+    let dict = self.build_dict_ptr(object);
+
+    self.build_init_list_fields(
+      dict,
+      function.get_nth_param(0).unwrap(),
+      function.get_nth_param(1).unwrap(),
+    );
+
+    self.store_field(dict, HASH_FIELD, function.get_nth_param(2).unwrap());
+    self.store_field(dict, EQUALS_FIELD, function.get_nth_param(3).unwrap());
+
+    let table_size = self
+      .llvm_context
+      .i64_type()
+      .const_int(INITIAL_TABLE_SIZE, false);
+
+    let table = self.build_empty_table(table_size);
+
+    self.store_field(dict, TABLE_SIZE_FIELD, table_size.into());
+    self.store_field(dict, TABLE_FIELD, table.into());
+    self.llvm_builder.build_return(Some(&object));
+
+    function
+  }
+
+  /// `void dict_drop(i8* dict)`: releases a dict's entries and frees its
+  /// buffers.
+  fn build_dict_drop(&self, list_drop: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let function = self.add_function("dict_drop", self.drop_fn_type);
+    let free = self.declare_free();
+
+    self.position_at_new_block(function, "entry");
+
+    let object = function.get_nth_param(0).unwrap();
+
+    // The entries are stored just like a list's elements
+    self.llvm_builder.build_call(list_drop, &[object], "");
+
+    let dict = self.build_dict_ptr(object.into_pointer_value());
+    let table = self
+      .load_field(dict, TABLE_FIELD, "table")
+      .into_pointer_value();
+
+    let table =
+      self
+        .llvm_builder
+        .build_pointer_cast(table, opaque_ptr_type(self.llvm_context), "table");
+
+    self.llvm_builder.build_call(free, &[table.into()], "");
+    self.llvm_builder.build_return(None);
+
+    function
+  }
+
+  /// `i64* dict_lookup(i8* dict, i8* key)`: finds the slot in a dict's
+  /// hash table that holds the index of the key's entry. If the key isn't
+  /// in the dict, this is the empty slot where its index would go.
+  fn build_dict_lookup(&self) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "dict_lookup",
+      i64_type
+        .ptr_type(AddressSpace::Generic)
+        .fn_type(&[ptr_type.into(), ptr_type.into()], false),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let probe_block = self.llvm_context.append_basic_block(function, "probe");
+    let compare_block = self.llvm_context.append_basic_block(function, "compare");
+    let next_block = self.llvm_context.append_basic_block(function, "next");
+    let found_block = self.llvm_context.append_basic_block(function, "found");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let dict = self.build_dict_ptr(function.get_nth_param(0).unwrap().into_pointer_value());
+    let key = function.get_nth_param(1).unwrap();
+
+    let hash_fn = self
+      .load_field(dict, HASH_FIELD, "hash")
+      .into_pointer_value();
+    let hash_fn = CallableValue::try_from(hash_fn).expect("hash should be a function pointer");
+
+    let hash = self
+      .llvm_builder
+      .build_call(hash_fn, &[key], "hash")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    // The table size is a power of two, so this is the hash modulo the size
+    let table_size = self
+      .load_field(dict, TABLE_SIZE_FIELD, "table_size")
+      .into_int_value();
+    let mask = self
+      .llvm_builder
+      .build_int_sub(table_size, i64_type.const_int(1, false), "mask");
+
+    let table = self
+      .load_field(dict, TABLE_FIELD, "table")
+      .into_pointer_value();
+
+    let position_ptr = self.llvm_builder.build_alloca(i64_type, "position");
+    let position = self.llvm_builder.build_and(hash, mask, "position");
+
+    self.llvm_builder.build_store(position_ptr, position);
+    self.llvm_builder.build_unconditional_branch(probe_block);
+
+    self.llvm_builder.position_at_end(probe_block);
+
+    let position = self
+      .llvm_builder
+      .build_load(position_ptr, "position")
+      .into_int_value();
+
+    let slot = unsafe { self.llvm_builder.build_gep(table, &[position], "slot") };
+    let index = self.llvm_builder.build_load(slot, "index").into_int_value();
+
+    let is_empty = self.llvm_builder.build_int_compare(
+      IntPredicate::SLT,
+      index,
+      i64_type.const_zero(),
+      "is_empty",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_empty, found_block, compare_block);
+
+    self.llvm_builder.position_at_end(compare_block);
+
+    // Keys are the first field of their entries
+    let entry = self.build_element_ptr(dict, index);
+
+    let equals_fn = self
+      .load_field(dict, EQUALS_FIELD, "equals")
+      .into_pointer_value();
+    let equals_fn =
+      CallableValue::try_from(equals_fn).expect("equals should be a function pointer");
+
+    let is_same_key = self
+      .llvm_builder
+      .build_call(equals_fn, &[entry.into(), key], "is_same_key")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_same_key, found_block, next_block);
+
+    self.llvm_builder.position_at_end(next_block);
+
+    let position =
+      self
+        .llvm_builder
+        .build_int_add(position, i64_type.const_int(1, false), "position");
+    let position = self.llvm_builder.build_and(position, mask, "position");
+
+    self.llvm_builder.build_store(position_ptr, position);
+    self.llvm_builder.build_unconditional_branch(probe_block);
+
+    self.llvm_builder.position_at_end(found_block);
+    self.llvm_builder.build_return(Some(&slot));
+
+    function
+  }
+
+  /// `i8* dict_get(i8* dict, i8* key)`: a pointer to the entry for a key,
+  /// or null if the key isn't in the dict.
+  fn build_dict_get(&self, dict_lookup: FunctionValue<'ctx>) -> FunctionValue<'ctx> {
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "dict_get",
+      ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+    );
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let found_block = self.llvm_context.append_basic_block(function, "found");
+    let missing_block = self.llvm_context.append_basic_block(function, "missing");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let object = function.get_nth_param(0).unwrap();
+    let key = function.get_nth_param(1).unwrap();
+
+    let index = self.build_lookup_index(dict_lookup, object, key).1;
+
+    let is_missing = self.llvm_builder.build_int_compare(
+      IntPredicate::SLT,
+      index,
+      self.llvm_context.i64_type().const_zero(),
+      "is_missing",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_missing, missing_block, found_block);
+
+    self.llvm_builder.position_at_end(found_block);
+
+    let dict = self.build_dict_ptr(object.into_pointer_value());
+    let entry = self.build_element_ptr(dict, index);
+
+    self.llvm_builder.build_return(Some(&entry));
+
+    self.llvm_builder.position_at_end(missing_block);
+    self.llvm_builder.build_return(Some(&ptr_type.const_null()));
+
+    function
+  }
+
+  /// `i8* dict_insert_slot(i8* dict, i8* key)`: finds or adds the entry for
+  /// a key, and returns a pointer to it for the caller to initialize. If the
+  /// key was already in the dict, its old entry is released first.
+  fn build_dict_insert_slot(
+    &self,
+    dict_lookup: FunctionValue<'ctx>,
+    list_push_slot: FunctionValue<'ctx>,
+  ) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let function = self.add_function(
+      "dict_insert_slot",
+      ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+    );
+
+    let free = self.declare_free();
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let replace_block = self.llvm_context.append_basic_block(function, "replace");
+    let drop_block = self.llvm_context.append_basic_block(function, "drop");
+    let replaced_block = self.llvm_context.append_basic_block(function, "replaced");
+    let check_size_block = self.llvm_context.append_basic_block(function, "check_size");
+    let grow_block = self.llvm_context.append_basic_block(function, "grow");
+    let add_block = self.llvm_context.append_basic_block(function, "add");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let object = function.get_nth_param(0).unwrap();
+    let key = function.get_nth_param(1).unwrap();
+    let dict = self.build_dict_ptr(object.into_pointer_value());
+
+    let index = self.build_lookup_index(dict_lookup, object, key).1;
+
+    let exists = self.llvm_builder.build_int_compare(
+      IntPredicate::SGE,
+      index,
+      i64_type.const_zero(),
+      "exists",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(exists, replace_block, check_size_block);
+
+    self.llvm_builder.position_at_end(replace_block);
+
+    let entry = self.build_element_ptr(dict, index);
+    let entry_drop = self
+      .load_field(dict, ELEMENT_DROP_FIELD, "entry_drop")
+      .into_pointer_value();
+
+    let has_entry_drop = self
+      .llvm_builder
+      .build_is_not_null(entry_drop, "has_entry_drop");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(has_entry_drop, drop_block, replaced_block);
+
+    self.llvm_builder.position_at_end(drop_block);
+
+    let entry_drop =
+      CallableValue::try_from(entry_drop).expect("entry drop should be a function pointer");
+
+    self
+      .llvm_builder
+      .build_call(entry_drop, &[entry.into()], "");
+    self.llvm_builder.build_unconditional_branch(replaced_block);
+
+    self.llvm_builder.position_at_end(replaced_block);
+    self.llvm_builder.build_return(Some(&entry));
+
+    self.llvm_builder.position_at_end(check_size_block);
+
+    let length = self
+      .load_field(dict, LENGTH_FIELD, "length")
+      .into_int_value();
+    let table_size = self
+      .load_field(dict, TABLE_SIZE_FIELD, "table_size")
+      .into_int_value();
+
+    // Keep the table at most half full, so probes stay short
+    let new_length =
+      self
+        .llvm_builder
+        .build_int_add(length, i64_type.const_int(1, false), "new_length");
+    let needed_size =
+      self
+        .llvm_builder
+        .build_int_mul(new_length, i64_type.const_int(2, false), "needed_size");
+
+    let is_crowded =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::UGT, needed_size, table_size, "is_crowded");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_crowded, grow_block, add_block);
+
+    self.llvm_builder.position_at_end(grow_block);
+
+    let old_table = self
+      .load_field(dict, TABLE_FIELD, "old_table")
+      .into_pointer_value();
+    let old_table = self
+      .llvm_builder
+      .build_pointer_cast(old_table, ptr_type, "old_table");
+
+    let table_size =
+      self
+        .llvm_builder
+        .build_int_mul(table_size, i64_type.const_int(2, false), "table_size");
+    let table = self.build_empty_table(table_size);
+
+    self.llvm_builder.build_call(free, &[old_table.into()], "");
+    self.store_field(dict, TABLE_SIZE_FIELD, table_size.into());
+    self.store_field(dict, TABLE_FIELD, table.into());
+
+    // Every entry's index goes in a new slot
+    self.build_loop(function, length, |index| {
+      let entry = self.build_element_ptr(dict, index);
+      let slot = self.build_lookup_index(dict_lookup, object, entry.into()).0;
+
+      self.llvm_builder.build_store(slot, index);
+    });
+
+    self.llvm_builder.build_unconditional_branch(add_block);
+
+    self.llvm_builder.position_at_end(add_block);
+
+    // The table may have been replaced, so find the key's slot again
+    let slot = self.build_lookup_index(dict_lookup, object, key).0;
+
+    let entry = self
+      .llvm_builder
+      .build_call(list_push_slot, &[object], "entry")
+      .try_as_basic_value()
+      .left()
+      .unwrap();
+
+    self.llvm_builder.build_store(slot, length);
+    self.llvm_builder.build_return(Some(&entry));
+
+    function
+  }
+
+  /// `i64 hash_<kind>(i8* key)` and `i1 equals_<kind>(i8* left, i8* right)`
+  /// for number keys, which are compared by their bits. Floats are loaded as
+  /// integers of the same size.
+  fn build_number_key_functions(&self, kind: &str, key_type: IntType<'ctx>) -> KeyFunctions<'ctx> {
+    let hash = self.add_function(&format!("hash_{}", kind), self.hash_fn_type);
+
+    self.position_at_new_block(hash, "entry");
+
+    let key = self.build_load_key(hash.get_nth_param(0).unwrap(), key_type.into());
+    let bits =
+      self
+        .llvm_builder
+        .build_int_cast(key.into_int_value(), self.llvm_context.i64_type(), "bits");
+
+    let hash_value = self.build_hash_mix(bits);
+    self.llvm_builder.build_return(Some(&hash_value));
+
+    let equals = self.add_function(&format!("equals_{}", kind), self.equals_fn_type);
+
+    self.position_at_new_block(equals, "entry");
+
+    let left = self.build_load_key(equals.get_nth_param(0).unwrap(), key_type.into());
+    let right = self.build_load_key(equals.get_nth_param(1).unwrap(), key_type.into());
+
+    let is_equal = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      left.into_int_value(),
+      right.into_int_value(),
+      "is_equal",
+    );
+
+    self.llvm_builder.build_return(Some(&is_equal));
+
+    KeyFunctions { hash, equals }
+  }
+
+  /// `i64 hash_string(i8* key)` and `i1 equals_string(i8* left, i8*
+  /// right)`. Strings are hashed with FNV-1a.
+  fn build_string_key_functions(&self, string_equals: FunctionValue<'ctx>) -> KeyFunctions<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let string_type = opaque_ptr_type(self.llvm_context);
+
+    let hash = self.add_function("hash_string", self.hash_fn_type);
+
+    self.position_at_new_block(hash, "entry");
+
+    let string = self
+      .build_load_key(hash.get_nth_param(0).unwrap(), string_type.into())
+      .into_pointer_value();
+
+    let length = self.build_string_length(string);
+    let bytes = self.build_string_bytes(string);
+
+    let hash_ptr = self.llvm_builder.build_alloca(i64_type, "hash");
+
+    self
+      .llvm_builder
+      .build_store(hash_ptr, i64_type.const_int(FNV_OFFSET_BASIS, false));
+
+    self.build_loop(hash, length, |index| {
+      let byte_ptr = unsafe { self.llvm_builder.build_gep(bytes, &[index], "byte") };
+      let byte = self
+        .llvm_builder
+        .build_load(byte_ptr, "byte")
+        .into_int_value();
+      let byte = self.llvm_builder.build_int_z_extend(byte, i64_type, "byte");
+
+      let hash_value = self
+        .llvm_builder
+        .build_load(hash_ptr, "hash")
+        .into_int_value();
+      let hash_value = self.llvm_builder.build_xor(hash_value, byte, "hash");
+      let hash_value =
+        self
+          .llvm_builder
+          .build_int_mul(hash_value, i64_type.const_int(FNV_PRIME, false), "hash");
+
+      self.llvm_builder.build_store(hash_ptr, hash_value);
+    });
+
+    let hash_value = self.llvm_builder.build_load(hash_ptr, "hash");
+    self.llvm_builder.build_return(Some(&hash_value));
+
+    let equals = self.add_function("equals_string", self.equals_fn_type);
+
+    self.position_at_new_block(equals, "entry");
+
+    let left = self.build_load_key(equals.get_nth_param(0).unwrap(), string_type.into());
+    let right = self.build_load_key(equals.get_nth_param(1).unwrap(), string_type.into());
+
+    let is_equal = self
+      .llvm_builder
+      .build_call(string_equals, &[left, right], "is_equal")
+      .try_as_basic_value()
+      .left()
+      .unwrap();
+
+    self.llvm_builder.build_return(Some(&is_equal));
+
+    KeyFunctions { hash, equals }
+  }
+
+  /// Allocates an object with a list or dict payload, which is initialized
+  /// by the caller.
+  fn build_collection_alloc(
+    &self,
+    alloc: FunctionValue<'ctx>,
+    payload_type: StructType<'ctx>,
+    drop: FunctionValue<'ctx>,
+  ) -> PointerValue<'ctx> {
+    let size = payload_type
+      .size_of()
+      .expect("collection payload should be sized");
+
+    let drop_fn = drop.as_global_value().as_pointer_value();
+
+    self
+      .llvm_builder
+      .build_call(alloc, &[size.into(), drop_fn.into()], "object")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value()
+  }
+
+  /// Initializes the fields that lists and dicts share, for an empty
+  /// collection.
+  fn build_init_list_fields(
+    &self,
+    list: PointerValue<'ctx>,
+    element_size: BasicValueEnum<'ctx>,
+    element_drop: BasicValueEnum<'ctx>,
+  ) {
+    let i64_type = self.llvm_context.i64_type();
+    let no_elements = opaque_ptr_type(self.llvm_context).const_null();
+
+    self.store_field(list, LENGTH_FIELD, i64_type.const_zero().into());
+    self.store_field(list, CAPACITY_FIELD, i64_type.const_zero().into());
+    self.store_field(list, ELEMENT_SIZE_FIELD, element_size);
+    self.store_field(list, ELEMENT_DROP_FIELD, element_drop);
+    self.store_field(list, ELEMENTS_FIELD, no_elements.into());
+  }
+
+  /// Allocates a hash table with every slot empty (i.e. every byte 0xff).
+  fn build_empty_table(&self, table_size: IntValue<'ctx>) -> PointerValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let malloc = self.declare_c_function("malloc", ptr_type.fn_type(&[i64_type.into()], false));
+
+    let memset = self.declare_c_function(
+      "memset",
+      ptr_type.fn_type(&[ptr_type.into(), i32_type.into(), i64_type.into()], false),
+    );
+
+    let size =
+      self
+        .llvm_builder
+        .build_int_mul(table_size, i64_type.size_of(), "table_size_in_bytes");
+
+    let table = self
+      .llvm_builder
+      .build_call(malloc, &[size.into()], "table")
+      .try_as_basic_value()
+      .left()
+      .unwrap();
+
+    self.llvm_builder.build_call(
+      memset,
+      &[table, i32_type.const_int(0xff, false).into(), size.into()],
+      "",
+    );
+
+    self.llvm_builder.build_pointer_cast(
+      table.into_pointer_value(),
+      i64_type.ptr_type(AddressSpace::Generic),
+      "table",
+    )
+  }
+
+  /// Looks up a key in a dict. Returns its slot in the hash table, and the
+  /// index that the slot holds.
+  fn build_lookup_index(
+    &self,
+    dict_lookup: FunctionValue<'ctx>,
+    object: BasicValueEnum<'ctx>,
+    key: BasicValueEnum<'ctx>,
+  ) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+    let slot = self
+      .llvm_builder
+      .build_call(dict_lookup, &[object, key], "slot")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let index = self.llvm_builder.build_load(slot, "index").into_int_value();
+
+    (slot, index)
+  }
+
+  /// Spreads a key's bits out, so that keys which only differ in their high
+  /// bits still land in different slots.
+  fn build_hash_mix(&self, bits: IntValue<'ctx>) -> IntValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let hash =
+      self
+        .llvm_builder
+        .build_int_mul(bits, i64_type.const_int(HASH_MULTIPLIER, false), "hash");
+
+    let high_bits =
+      self
+        .llvm_builder
+        .build_right_shift(hash, i64_type.const_int(32, false), false, "high_bits");
+
+    self.llvm_builder.build_xor(hash, high_bits, "hash")
+  }
+
+  fn build_load_key(
+    &self,
+    key: BasicValueEnum<'ctx>,
+    key_type: BasicTypeEnum<'ctx>,
+  ) -> BasicValueEnum<'ctx> {
+    let key_ptr = self.llvm_builder.build_pointer_cast(
+      key.into_pointer_value(),
+      key_type.ptr_type(AddressSpace::Generic),
+      "key",
+    );
+
+    self.llvm_builder.build_load(key_ptr, "key")
+  }
+
+  fn build_element_ptr(
+    &self,
+    list: PointerValue<'ctx>,
+    index: IntValue<'ctx>,
+  ) -> PointerValue<'ctx> {
+    let element_size = self
+      .load_field(list, ELEMENT_SIZE_FIELD, "element_size")
+      .into_int_value();
+    let elements = self
+      .load_field(list, ELEMENTS_FIELD, "elements")
+      .into_pointer_value();
+
+    let offset = self
+      .llvm_builder
+      .build_int_mul(index, element_size, "offset");
+
+    unsafe { self.llvm_builder.build_gep(elements, &[offset], "element") }
+  }
+
+  fn build_list_ptr(&self, object: PointerValue<'ctx>) -> PointerValue<'ctx> {
+    self.llvm_builder.build_pointer_cast(
+      object,
+      self.list_type.ptr_type(AddressSpace::Generic),
+      "list",
+    )
+  }
+
+  fn build_dict_ptr(&self, object: PointerValue<'ctx>) -> PointerValue<'ctx> {
+    self.llvm_builder.build_pointer_cast(
+      object,
+      self.dict_type.ptr_type(AddressSpace::Generic),
+      "dict",
+    )
+  }
+
+  fn load_field(
+    &self,
+    collection: PointerValue<'ctx>,
+    field: u32,
+    name: &str,
+  ) -> BasicValueEnum<'ctx> {
+    let field_ptr = self
+      .llvm_builder
+      .build_struct_gep(collection, field, name)
+      .unwrap();
+
+    self.llvm_builder.build_load(field_ptr, name)
+  }
+
+  fn store_field(&self, collection: PointerValue<'ctx>, field: u32, value: BasicValueEnum<'ctx>) {
+    let field_ptr = self
+      .llvm_builder
+      .build_struct_gep(collection, field, "field")
+      .unwrap();
+
+    self.llvm_builder.build_store(field_ptr, value);
+  }
+
+  /// Builds a loop that runs `body` with each index from 0 up to (but not
+  /// including) `count`. Leaves the builder positioned after the loop.
+  fn build_loop(
+    &self,
+    function: FunctionValue<'ctx>,
+    count: IntValue<'ctx>,
+    body: impl FnOnce(IntValue<'ctx>),
+  ) {
+    let i64_type = self.llvm_context.i64_type();
+
+    let check_block = self.llvm_context.append_basic_block(function, "loop_check");
+    let body_block = self.llvm_context.append_basic_block(function, "loop_body");
+    let done_block = self.llvm_context.append_basic_block(function, "loop_done");
+
+    let index_ptr = self.llvm_builder.build_alloca(i64_type, "index");
+
+    self
+      .llvm_builder
+      .build_store(index_ptr, i64_type.const_zero());
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(check_block);
+
+    let index = self
+      .llvm_builder
+      .build_load(index_ptr, "index")
+      .into_int_value();
+
+    let is_in_range =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULT, index, count, "is_in_range");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_in_range, body_block, done_block);
+
+    self.llvm_builder.position_at_end(body_block);
+
+    body(index);
+
+    let index = self
+      .llvm_builder
+      .build_int_add(index, i64_type.const_int(1, false), "index");
+
+    self.llvm_builder.build_store(index_ptr, index);
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(done_block);
+  }
+
+  fn declare_free(&self) -> FunctionValue<'ctx> {
+    self.declare_c_function(
+      "free",
+      self
+        .llvm_context
+        .void_type()
+        .fn_type(&[opaque_ptr_type(self.llvm_context).into()], false),
+    )
+  }
+
+  /// Branches to `done_block` if the object is null or immortal. Otherwise,
+  /// continues in `mortal_block` with a pointer to the object's refcount.
+  fn build_mortal_refcount_ptr(
+    &self,
+    object: PointerValue<'ctx>,
+    mortal_block: BasicBlock<'ctx>,
+    done_block: BasicBlock<'ctx>,
+  ) -> PointerValue<'ctx> {
+    let function = mortal_block.get_parent().unwrap();
+    let check_block = self.llvm_context.append_basic_block(function, "check");
+
+    let is_null = self.llvm_builder.build_is_null(object, "is_null");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_null, done_block, check_block);
+
+    self.llvm_builder.position_at_end(check_block);
+
+    let header = self.build_header_ptr(object);
+    let refcount_ptr = self
+      .llvm_builder
+      .build_struct_gep(header, 0, "refcount")
+      .unwrap();
+
+    let refcount = self
+      .llvm_builder
+      .build_load(refcount_ptr, "refcount")
+      .into_int_value();
+
+    let is_immortal = self.llvm_builder.build_int_compare(
+      IntPredicate::SLT,
+      refcount,
+      self.llvm_context.i64_type().const_zero(),
+      "immortal",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_immortal, done_block, mortal_block);
+
+    self.llvm_builder.position_at_end(mortal_block);
+
+    refcount_ptr
+  }
+
+  fn build_header_ptr(&self, object: PointerValue<'ctx>) -> PointerValue<'ctx> {
+    let payload = self.llvm_builder.build_pointer_cast(
+      object,
+      self.header_type.ptr_type(AddressSpace::Generic),
+      "payload",
+    );
+
+    unsafe {
+      self.llvm_builder.build_gep(
+        payload,
+        &[self.llvm_context.i64_type().const_int(-1i64 as u64, true)],
+        "header",
+      )
+    }
+  }
+
+  fn build_string_length(&self, string: PointerValue<'ctx>) -> IntValue<'ctx> {
+    build_string_length(self.llvm_context, &self.llvm_builder, string)
+  }
+
+  fn build_string_bytes(&self, string: PointerValue<'ctx>) -> PointerValue<'ctx> {
+    build_string_bytes(self.llvm_context, &self.llvm_builder, string)
+  }
+
+  fn add_to_live_objects(&self, delta: i64) {
+    let i64_type = self.llvm_context.i64_type();
+
+    let live_objects = self
+      .llvm_builder
+      .build_load(self.live_objects, "live")
+      .into_int_value();
+
+    let live_objects =
+      self
+        .llvm_builder
+        .build_int_add(live_objects, i64_type.const_int(delta as u64, true), "live");
+
+    self
+      .llvm_builder
+      .build_store(self.live_objects, live_objects);
+  }
+
+  fn add_function(&self, name: &str, fn_type: FunctionType<'ctx>) -> FunctionValue<'ctx> {
+    self.llvm_module.add_function(
+      &mangle::runtime_name(name),
+      fn_type,
+      Some(Linkage::Internal),
+    )
+  }
+
+  fn declare_c_function(&self, name: &str, fn_type: FunctionType<'ctx>) -> FunctionValue<'ctx> {
+    self.llvm_module.get_function(name).unwrap_or_else(|| {
+      self
+        .llvm_module
+        .add_function(name, fn_type, Some(Linkage::External))
+    })
+  }
+
+  fn declare_memcpy(&self) -> FunctionValue<'ctx> {
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    self.declare_c_function(
+      "memcpy",
+      ptr_type.fn_type(
+        &[
+          ptr_type.into(),
+          ptr_type.into(),
+          self.llvm_context.i64_type().into(),
+        ],
+        false,
+      ),
+    )
+  }
+
+  fn declare_memcmp(&self) -> FunctionValue<'ctx> {
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    self.declare_c_function(
+      "memcmp",
+      self.llvm_context.i32_type().fn_type(
+        &[
+          ptr_type.into(),
+          ptr_type.into(),
+          self.llvm_context.i64_type().into(),
+        ],
+        false,
+      ),
+    )
+  }
+
+  fn position_at_new_block(&self, function: FunctionValue<'ctx>, name: &str) {
+    let block = self.llvm_context.append_basic_block(function, name);
+
+    self.llvm_builder.position_at_end(block);
+  }
+}
+
+fn opaque_ptr_type(llvm_context: &Context) -> PointerType<'_> {
+  llvm_context.i8_type().ptr_type(AddressSpace::Generic)
+}
+
+fn build_string_length<'ctx>(
+  llvm_context: &'ctx Context,
+  builder: &Builder<'ctx>,
+  string: PointerValue<'ctx>,
+) -> IntValue<'ctx> {
+  let length_ptr = builder.build_pointer_cast(
+    string,
+    llvm_context.i64_type().ptr_type(AddressSpace::Generic),
+    "length",
+  );
+
+  builder.build_load(length_ptr, "length").into_int_value()
+}
+
+fn build_string_bytes<'ctx>(
+  llvm_context: &'ctx Context,
+  builder: &Builder<'ctx>,
+  string: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+  let offset = llvm_context.i64_type().const_int(STRING_LENGTH_SIZE, false);
+
+  unsafe { builder.build_gep(string, &[offset], "bytes") }
+}
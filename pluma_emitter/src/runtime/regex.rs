@@ -0,0 +1,626 @@
+use super::*;
+use pluma_regex::{Instruction, Program};
+
+// Regexes are immortal objects in constant memory, with a `{ i64
+// instruction_count, i64 slot_count, [instruction_count x { i32 op, i32 a,
+// i32 b }] instructions }` payload. Each instruction is an op code and its
+// two operands (which are zero if the op doesn't use them):
+//
+// - OP_BYTE_RANGE a b: matches one byte between a and b (inclusive)
+// - OP_SPLIT a b: continues at a, backtracking to b if that fails
+// - OP_JUMP a: continues at a
+// - OP_SAVE a: records the current position in slot a
+// - OP_MATCH: the match succeeded
+//
+// Regexes are matched by backtracking, but never try the same instruction
+// at the same position twice (that attempt is bound to fail the same way
+// again), so matching takes time proportional to the number of
+// instructions times the length of the string.
+
+const OP_BYTE_RANGE: u64 = 0;
+const OP_SPLIT: u64 = 1;
+const OP_JUMP: u64 = 2;
+const OP_SAVE: u64 = 3;
+const OP_MATCH: u64 = 4;
+
+const INSTRUCTION_COUNT_FIELD: u32 = 0;
+const SLOT_COUNT_FIELD: u32 = 1;
+const INSTRUCTIONS_FIELD: u32 = 2;
+
+/// The number of i32s in each instruction.
+const INSTRUCTION_WIDTH: u64 = 3;
+
+/// The number of i64s in each backtracking job: `{ pc, position, -1 }` to
+/// resume matching, or `{ 0, old_value, slot }` to restore a capture slot.
+const JOB_WIDTH: u64 = 3;
+
+/// The number of jobs that the backtracking stack has room for at first.
+const INITIAL_JOB_CAPACITY: u64 = 16;
+
+/// The allocas that hold the backtracking stack while a regex is matched.
+struct JobStack<'ctx> {
+  jobs: PointerValue<'ctx>,
+  length: PointerValue<'ctx>,
+  capacity: PointerValue<'ctx>,
+}
+
+impl<'ctx> Runtime<'ctx> {
+  /// A regex's payload, for a regex literal that compiled to `program`.
+  pub fn const_regex_payload(&self, program: &Program) -> StructValue<'ctx> {
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+
+    let mut fields = Vec::with_capacity(program.instructions.len() * INSTRUCTION_WIDTH as usize);
+
+    for instruction in &program.instructions {
+      let (op, a, b) = match *instruction {
+        Instruction::ByteRange(low, high) => (OP_BYTE_RANGE, low as usize, high as usize),
+        Instruction::Split(first, second) => (OP_SPLIT, first, second),
+        Instruction::Jump(target) => (OP_JUMP, target, 0),
+        Instruction::Save(slot) => (OP_SAVE, slot, 0),
+        Instruction::Match => (OP_MATCH, 0, 0),
+      };
+
+      fields.push(i32_type.const_int(op, false));
+      fields.push(i32_type.const_int(a as u64, false));
+      fields.push(i32_type.const_int(b as u64, false));
+    }
+
+    self.llvm_context.const_struct(
+      &[
+        i64_type
+          .const_int(program.instructions.len() as u64, false)
+          .into(),
+        i64_type
+          .const_int(program.slot_count() as u64, false)
+          .into(),
+        i32_type.const_array(&fields).into(),
+      ],
+      false,
+    )
+  }
+}
+
+impl<'a, 'ctx> RuntimeBuilder<'a, 'ctx> {
+  /// `i1 regex_search(i8* regex, i8* string, i1 anchored, i64* slots)`:
+  /// finds the leftmost match of a regex in a string, and fills in its
+  /// capture slots. Slots for captures that didn't take part in the match
+  /// are -1. Anchored searches only match the whole string.
+  pub(super) fn build_regex_search(&self) -> FunctionValue<'ctx> {
+    let i8_type = self.llvm_context.i8_type();
+    let i32_type = self.llvm_context.i32_type();
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+    let slots_type = i64_type.ptr_type(AddressSpace::Generic);
+
+    let function = self.add_function(
+      "regex_search",
+      self.llvm_context.bool_type().fn_type(
+        &[
+          ptr_type.into(),
+          ptr_type.into(),
+          self.llvm_context.bool_type().into(),
+          slots_type.into(),
+        ],
+        false,
+      ),
+    );
+
+    let calloc = self.declare_c_function(
+      "calloc",
+      ptr_type.fn_type(&[i64_type.into(), i64_type.into()], false),
+    );
+    let malloc = self.declare_c_function("malloc", ptr_type.fn_type(&[i64_type.into()], false));
+    let free = self.declare_free();
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let start_check_block = self
+      .llvm_context
+      .append_basic_block(function, "start_check");
+    let start_block = self.llvm_context.append_basic_block(function, "start");
+    let pop_block = self.llvm_context.append_basic_block(function, "pop");
+    let pop_job_block = self.llvm_context.append_basic_block(function, "pop_job");
+    let restore_block = self.llvm_context.append_basic_block(function, "restore");
+    let resume_block = self.llvm_context.append_basic_block(function, "resume");
+    let step_block = self.llvm_context.append_basic_block(function, "step");
+    let dispatch_block = self.llvm_context.append_basic_block(function, "dispatch");
+    let byte_block = self.llvm_context.append_basic_block(function, "byte");
+    let byte_check_block = self.llvm_context.append_basic_block(function, "byte_check");
+    let byte_matched_block = self
+      .llvm_context
+      .append_basic_block(function, "byte_matched");
+    let split_block = self.llvm_context.append_basic_block(function, "split");
+    let jump_block = self.llvm_context.append_basic_block(function, "jump");
+    let save_block = self.llvm_context.append_basic_block(function, "save");
+    let match_block = self.llvm_context.append_basic_block(function, "match");
+    let next_start_block = self.llvm_context.append_basic_block(function, "next_start");
+    let advance_block = self.llvm_context.append_basic_block(function, "advance");
+    let found_block = self.llvm_context.append_basic_block(function, "found");
+    let not_found_block = self.llvm_context.append_basic_block(function, "not_found");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let regex = self.llvm_builder.build_pointer_cast(
+      function.get_nth_param(0).unwrap().into_pointer_value(),
+      self.regex_type.ptr_type(AddressSpace::Generic),
+      "regex",
+    );
+    let string = function.get_nth_param(1).unwrap().into_pointer_value();
+    let anchored = function.get_nth_param(2).unwrap().into_int_value();
+    let slots = function.get_nth_param(3).unwrap().into_pointer_value();
+
+    let instruction_count = self
+      .load_field(regex, INSTRUCTION_COUNT_FIELD, "instruction_count")
+      .into_int_value();
+    let slot_count = self
+      .load_field(regex, SLOT_COUNT_FIELD, "slot_count")
+      .into_int_value();
+
+    let instructions = self
+      .llvm_builder
+      .build_struct_gep(regex, INSTRUCTIONS_FIELD, "instructions")
+      .unwrap();
+    let instructions = self.llvm_builder.build_pointer_cast(
+      instructions,
+      i32_type.ptr_type(AddressSpace::Generic),
+      "instructions",
+    );
+
+    let length = self.build_string_length(string);
+    let bytes = self.build_string_bytes(string);
+
+    // One flag for each instruction at each position (including the end)
+    let positions =
+      self
+        .llvm_builder
+        .build_int_add(length, i64_type.const_int(1, false), "positions");
+    let state_count = self
+      .llvm_builder
+      .build_int_mul(instruction_count, positions, "state_count");
+
+    let visited = self
+      .llvm_builder
+      .build_call(
+        calloc,
+        &[state_count.into(), i64_type.const_int(1, false).into()],
+        "visited",
+      )
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+
+    let job_size = i64_type.const_int(INITIAL_JOB_CAPACITY * JOB_WIDTH * 8, false);
+    let jobs = self
+      .llvm_builder
+      .build_call(malloc, &[job_size.into()], "jobs")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+    let jobs = self
+      .llvm_builder
+      .build_pointer_cast(jobs, slots_type, "jobs");
+
+    let stack = JobStack {
+      jobs: self.llvm_builder.build_alloca(slots_type, "jobs"),
+      length: self.llvm_builder.build_alloca(i64_type, "stack_length"),
+      capacity: self.llvm_builder.build_alloca(i64_type, "stack_capacity"),
+    };
+
+    self.llvm_builder.build_store(stack.jobs, jobs);
+    self
+      .llvm_builder
+      .build_store(stack.length, i64_type.const_zero());
+    self.llvm_builder.build_store(
+      stack.capacity,
+      i64_type.const_int(INITIAL_JOB_CAPACITY, false),
+    );
+
+    let start_ptr = self.llvm_builder.build_alloca(i64_type, "start");
+    let pc_ptr = self.llvm_builder.build_alloca(i64_type, "pc");
+    let position_ptr = self.llvm_builder.build_alloca(i64_type, "position");
+
+    self
+      .llvm_builder
+      .build_store(start_ptr, i64_type.const_zero());
+    self
+      .llvm_builder
+      .build_unconditional_branch(start_check_block);
+
+    // Tries to match at each start position in turn
+    self.llvm_builder.position_at_end(start_check_block);
+
+    let start = self
+      .llvm_builder
+      .build_load(start_ptr, "start")
+      .into_int_value();
+    let is_past_end =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::UGT, start, length, "is_past_end");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_past_end, not_found_block, start_block);
+
+    self.llvm_builder.position_at_end(start_block);
+
+    self.build_loop(function, slot_count, |slot| {
+      let slot_ptr = unsafe { self.llvm_builder.build_gep(slots, &[slot], "slot") };
+
+      self
+        .llvm_builder
+        .build_store(slot_ptr, i64_type.const_int(-1i64 as u64, true));
+    });
+
+    let no_slot = i64_type.const_int(-1i64 as u64, true);
+    self.build_push_job(&stack, [i64_type.const_zero(), start, no_slot]);
+    self.llvm_builder.build_unconditional_branch(pop_block);
+
+    // Backtracks to the most recent job
+    self.llvm_builder.position_at_end(pop_block);
+
+    let stack_length = self
+      .llvm_builder
+      .build_load(stack.length, "stack_length")
+      .into_int_value();
+    let is_empty = self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      stack_length,
+      i64_type.const_zero(),
+      "is_empty",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_empty, next_start_block, pop_job_block);
+
+    self.llvm_builder.position_at_end(pop_job_block);
+
+    let stack_length =
+      self
+        .llvm_builder
+        .build_int_sub(stack_length, i64_type.const_int(1, false), "stack_length");
+    self.llvm_builder.build_store(stack.length, stack_length);
+
+    let job = self.build_job_ptr(&stack, stack_length);
+    let job_pc = self.build_load_at(job, 0, "job_pc");
+    let job_value = self.build_load_at(job, 1, "job_value");
+    let job_slot = self.build_load_at(job, 2, "job_slot");
+
+    let is_restore = self.llvm_builder.build_int_compare(
+      IntPredicate::SGE,
+      job_slot,
+      i64_type.const_zero(),
+      "is_restore",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_restore, restore_block, resume_block);
+
+    self.llvm_builder.position_at_end(restore_block);
+
+    let slot_ptr = unsafe { self.llvm_builder.build_gep(slots, &[job_slot], "slot") };
+    self.llvm_builder.build_store(slot_ptr, job_value);
+    self.llvm_builder.build_unconditional_branch(pop_block);
+
+    self.llvm_builder.position_at_end(resume_block);
+
+    self.llvm_builder.build_store(pc_ptr, job_pc);
+    self.llvm_builder.build_store(position_ptr, job_value);
+    self.llvm_builder.build_unconditional_branch(step_block);
+
+    // Runs the instruction at pc, unless it already ran at this position
+    self.llvm_builder.position_at_end(step_block);
+
+    let pc = self.llvm_builder.build_load(pc_ptr, "pc").into_int_value();
+    let position = self
+      .llvm_builder
+      .build_load(position_ptr, "position")
+      .into_int_value();
+
+    let state = self.llvm_builder.build_int_mul(pc, positions, "state");
+    let state = self.llvm_builder.build_int_add(state, position, "state");
+    let visited_ptr = unsafe { self.llvm_builder.build_gep(visited, &[state], "visited") };
+
+    let was_visited = self
+      .llvm_builder
+      .build_load(visited_ptr, "was_visited")
+      .into_int_value();
+    let was_visited = self.llvm_builder.build_int_compare(
+      IntPredicate::NE,
+      was_visited,
+      i8_type.const_zero(),
+      "was_visited",
+    );
+
+    self
+      .llvm_builder
+      .build_conditional_branch(was_visited, pop_block, dispatch_block);
+
+    self.llvm_builder.position_at_end(dispatch_block);
+
+    self
+      .llvm_builder
+      .build_store(visited_ptr, i8_type.const_int(1, false));
+
+    let instruction_offset = self.llvm_builder.build_int_mul(
+      pc,
+      i64_type.const_int(INSTRUCTION_WIDTH, false),
+      "instruction",
+    );
+    let instruction = unsafe {
+      self
+        .llvm_builder
+        .build_gep(instructions, &[instruction_offset], "instruction")
+    };
+
+    let op = self.build_load_at(instruction, 0, "op");
+    let a = self.build_load_at(instruction, 1, "a");
+    let b = self.build_load_at(instruction, 2, "b");
+    let a = self.llvm_builder.build_int_z_extend(a, i64_type, "a");
+    let b = self.llvm_builder.build_int_z_extend(b, i64_type, "b");
+
+    let next_pc = self
+      .llvm_builder
+      .build_int_add(pc, i64_type.const_int(1, false), "next_pc");
+
+    self.llvm_builder.build_switch(
+      op,
+      match_block,
+      &[
+        (i32_type.const_int(OP_BYTE_RANGE, false), byte_block),
+        (i32_type.const_int(OP_SPLIT, false), split_block),
+        (i32_type.const_int(OP_JUMP, false), jump_block),
+        (i32_type.const_int(OP_SAVE, false), save_block),
+      ],
+    );
+
+    self.llvm_builder.position_at_end(byte_block);
+
+    let is_in_bounds =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULT, position, length, "is_in_bounds");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_in_bounds, byte_check_block, pop_block);
+
+    self.llvm_builder.position_at_end(byte_check_block);
+
+    let byte_ptr = unsafe { self.llvm_builder.build_gep(bytes, &[position], "byte") };
+    let byte = self
+      .llvm_builder
+      .build_load(byte_ptr, "byte")
+      .into_int_value();
+    let byte = self.llvm_builder.build_int_z_extend(byte, i64_type, "byte");
+
+    let is_above_low =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::UGE, byte, a, "is_above_low");
+    let is_below_high =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULE, byte, b, "is_below_high");
+    let is_in_range = self
+      .llvm_builder
+      .build_and(is_above_low, is_below_high, "is_in_range");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_in_range, byte_matched_block, pop_block);
+
+    self.llvm_builder.position_at_end(byte_matched_block);
+
+    let next_position =
+      self
+        .llvm_builder
+        .build_int_add(position, i64_type.const_int(1, false), "next_position");
+
+    self.llvm_builder.build_store(pc_ptr, next_pc);
+    self.llvm_builder.build_store(position_ptr, next_position);
+    self.llvm_builder.build_unconditional_branch(step_block);
+
+    self.llvm_builder.position_at_end(split_block);
+
+    self.build_push_job(&stack, [b, position, no_slot]);
+    self.llvm_builder.build_store(pc_ptr, a);
+    self.llvm_builder.build_unconditional_branch(step_block);
+
+    self.llvm_builder.position_at_end(jump_block);
+
+    self.llvm_builder.build_store(pc_ptr, a);
+    self.llvm_builder.build_unconditional_branch(step_block);
+
+    self.llvm_builder.position_at_end(save_block);
+
+    // Backtracking past this instruction puts the slot's old value back
+    let slot_ptr = unsafe { self.llvm_builder.build_gep(slots, &[a], "slot") };
+    let old_value = self
+      .llvm_builder
+      .build_load(slot_ptr, "old_value")
+      .into_int_value();
+
+    self.build_push_job(&stack, [i64_type.const_zero(), old_value, a]);
+    self.llvm_builder.build_store(slot_ptr, position);
+    self.llvm_builder.build_store(pc_ptr, next_pc);
+    self.llvm_builder.build_unconditional_branch(step_block);
+
+    self.llvm_builder.position_at_end(match_block);
+
+    let is_at_end =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::EQ, position, length, "is_at_end");
+    let is_unanchored = self.llvm_builder.build_not(anchored, "is_unanchored");
+    let is_match = self
+      .llvm_builder
+      .build_or(is_unanchored, is_at_end, "is_match");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_match, found_block, pop_block);
+
+    // Every attempt from this start position failed
+    self.llvm_builder.position_at_end(next_start_block);
+
+    self
+      .llvm_builder
+      .build_conditional_branch(anchored, not_found_block, advance_block);
+
+    self.llvm_builder.position_at_end(advance_block);
+
+    let start = self
+      .llvm_builder
+      .build_load(start_ptr, "start")
+      .into_int_value();
+    let start = self
+      .llvm_builder
+      .build_int_add(start, i64_type.const_int(1, false), "start");
+
+    self.llvm_builder.build_store(start_ptr, start);
+    self
+      .llvm_builder
+      .build_unconditional_branch(start_check_block);
+
+    for (block, result) in &[(found_block, 1), (not_found_block, 0)] {
+      self.llvm_builder.position_at_end(*block);
+
+      let jobs = self.llvm_builder.build_load(stack.jobs, "jobs");
+      let jobs = self
+        .llvm_builder
+        .build_pointer_cast(jobs.into_pointer_value(), ptr_type, "jobs");
+
+      self.llvm_builder.build_call(free, &[visited.into()], "");
+      self.llvm_builder.build_call(free, &[jobs.into()], "");
+
+      self.llvm_builder.build_return(Some(
+        &self.llvm_context.bool_type().const_int(*result, false),
+      ));
+    }
+
+    function
+  }
+
+  /// Pushes a job onto the backtracking stack, doubling the stack's buffer
+  /// first if it's full.
+  fn build_push_job(&self, stack: &JobStack<'ctx>, job: [IntValue<'ctx>; 3]) {
+    let i64_type = self.llvm_context.i64_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+
+    let realloc = self.declare_c_function(
+      "realloc",
+      ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false),
+    );
+
+    let function = self
+      .llvm_builder
+      .get_insert_block()
+      .unwrap()
+      .get_parent()
+      .unwrap();
+
+    let grow_block = self.llvm_context.append_basic_block(function, "grow");
+    let push_block = self.llvm_context.append_basic_block(function, "push");
+
+    let length = self
+      .llvm_builder
+      .build_load(stack.length, "stack_length")
+      .into_int_value();
+    let capacity = self
+      .llvm_builder
+      .build_load(stack.capacity, "stack_capacity")
+      .into_int_value();
+
+    let is_full =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::EQ, length, capacity, "is_full");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_full, grow_block, push_block);
+
+    self.llvm_builder.position_at_end(grow_block);
+
+    let capacity =
+      self
+        .llvm_builder
+        .build_int_mul(capacity, i64_type.const_int(2, false), "stack_capacity");
+    let size =
+      self
+        .llvm_builder
+        .build_int_mul(capacity, i64_type.const_int(JOB_WIDTH * 8, false), "size");
+
+    let jobs = self.llvm_builder.build_load(stack.jobs, "jobs");
+    let jobs = self
+      .llvm_builder
+      .build_pointer_cast(jobs.into_pointer_value(), ptr_type, "jobs");
+
+    let jobs = self
+      .llvm_builder
+      .build_call(realloc, &[jobs.into(), size.into()], "jobs")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_pointer_value();
+    let jobs =
+      self
+        .llvm_builder
+        .build_pointer_cast(jobs, i64_type.ptr_type(AddressSpace::Generic), "jobs");
+
+    self.llvm_builder.build_store(stack.jobs, jobs);
+    self.llvm_builder.build_store(stack.capacity, capacity);
+    self.llvm_builder.build_unconditional_branch(push_block);
+
+    self.llvm_builder.position_at_end(push_block);
+
+    let job_ptr = self.build_job_ptr(stack, length);
+
+    for (i, value) in job.iter().enumerate() {
+      let field_ptr = unsafe {
+        self
+          .llvm_builder
+          .build_gep(job_ptr, &[i64_type.const_int(i as u64, false)], "job_field")
+      };
+
+      self.llvm_builder.build_store(field_ptr, *value);
+    }
+
+    let length =
+      self
+        .llvm_builder
+        .build_int_add(length, i64_type.const_int(1, false), "stack_length");
+    self.llvm_builder.build_store(stack.length, length);
+  }
+
+  fn build_job_ptr(&self, stack: &JobStack<'ctx>, index: IntValue<'ctx>) -> PointerValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let jobs = self
+      .llvm_builder
+      .build_load(stack.jobs, "jobs")
+      .into_pointer_value();
+    let offset =
+      self
+        .llvm_builder
+        .build_int_mul(index, i64_type.const_int(JOB_WIDTH, false), "offset");
+
+    unsafe { self.llvm_builder.build_gep(jobs, &[offset], "job") }
+  }
+
+  /// Loads the integer at an index past `ptr`.
+  fn build_load_at(&self, ptr: PointerValue<'ctx>, index: u64, name: &str) -> IntValue<'ctx> {
+    let index = self.llvm_context.i64_type().const_int(index, false);
+    let element_ptr = unsafe { self.llvm_builder.build_gep(ptr, &[index], name) };
+
+    self
+      .llvm_builder
+      .build_load(element_ptr, name)
+      .into_int_value()
+  }
+}
@@ -0,0 +1,351 @@
+use super::*;
+
+// String patterns (like `"$(name).pa"` in a match case) are immortal objects
+// in constant memory, with a `{ i64 literal_count, [literal_count x i8*]
+// literals }` payload. The literals are strings, and there's a hole between
+// each pair of them (so the first and last literals may be empty).
+//
+// A string matches a pattern if it starts with the first literal, ends with
+// the last one, and contains the others in order in between. Each literal in
+// between is matched at its leftmost occurrence, so when there's more than
+// one way to match, earlier holes match as little as they can.
+
+const LITERAL_COUNT_FIELD: u32 = 0;
+const LITERALS_FIELD: u32 = 1;
+
+impl<'ctx> Runtime<'ctx> {
+  /// A string pattern's payload, given its literals (which should be
+  /// string literals themselves).
+  pub fn const_string_pattern_payload(&self, literals: &[PointerValue<'ctx>]) -> StructValue<'ctx> {
+    let length = self
+      .llvm_context
+      .i64_type()
+      .const_int(literals.len() as u64, false);
+
+    let literals = opaque_ptr_type(self.llvm_context).const_array(literals);
+
+    self
+      .llvm_context
+      .const_struct(&[length.into(), literals.into()], false)
+  }
+}
+
+impl<'a, 'ctx> RuntimeBuilder<'a, 'ctx> {
+  /// `i1 string_pattern_match(i8* pattern, i8* string, i64* bounds)`:
+  /// whether a string matches a string pattern. If it does, the start and
+  /// end of each hole are stored in `bounds`.
+  pub(super) fn build_string_pattern_match(&self) -> FunctionValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+    let bool_type = self.llvm_context.bool_type();
+    let ptr_type = opaque_ptr_type(self.llvm_context);
+    let bounds_type = i64_type.ptr_type(AddressSpace::Generic);
+
+    let function = self.add_function(
+      "string_pattern_match",
+      bool_type.fn_type(
+        &[ptr_type.into(), ptr_type.into(), bounds_type.into()],
+        false,
+      ),
+    );
+
+    let memcmp = self.declare_memcmp();
+
+    let pattern_type = self
+      .llvm_context
+      .struct_type(&[i64_type.into(), ptr_type.array_type(0).into()], false);
+
+    let entry_block = self.llvm_context.append_basic_block(function, "entry");
+    let ends_block = self.llvm_context.append_basic_block(function, "ends");
+    let holes_block = self.llvm_context.append_basic_block(function, "holes");
+    let failed_block = self.llvm_context.append_basic_block(function, "failed");
+
+    self.llvm_builder.position_at_end(entry_block);
+
+    let pattern = self.llvm_builder.build_pointer_cast(
+      function.get_nth_param(0).unwrap().into_pointer_value(),
+      pattern_type.ptr_type(AddressSpace::Generic),
+      "pattern",
+    );
+    let string = function.get_nth_param(1).unwrap().into_pointer_value();
+    let bounds = function.get_nth_param(2).unwrap().into_pointer_value();
+
+    let literal_count = self
+      .load_field(pattern, LITERAL_COUNT_FIELD, "literal_count")
+      .into_int_value();
+
+    let literals = self
+      .llvm_builder
+      .build_struct_gep(pattern, LITERALS_FIELD, "literals")
+      .unwrap();
+    let literals = self.llvm_builder.build_pointer_cast(
+      literals,
+      ptr_type.ptr_type(AddressSpace::Generic),
+      "literals",
+    );
+
+    let load_literal = |index: IntValue<'ctx>| {
+      let literal_ptr = unsafe { self.llvm_builder.build_gep(literals, &[index], "literal") };
+
+      self
+        .llvm_builder
+        .build_load(literal_ptr, "literal")
+        .into_pointer_value()
+    };
+
+    let hole_count =
+      self
+        .llvm_builder
+        .build_int_sub(literal_count, i64_type.const_int(1, false), "hole_count");
+
+    let length = self.build_string_length(string);
+    let bytes = self.build_string_bytes(string);
+
+    let prefix = load_literal(i64_type.const_zero());
+    let suffix = load_literal(hole_count);
+    let prefix_length = self.build_string_length(prefix);
+    let suffix_length = self.build_string_length(suffix);
+
+    // The prefix and suffix can't overlap
+    let ends_length = self
+      .llvm_builder
+      .build_int_add(prefix_length, suffix_length, "ends_length");
+    let ends_fit =
+      self
+        .llvm_builder
+        .build_int_compare(IntPredicate::ULE, ends_length, length, "ends_fit");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(ends_fit, ends_block, failed_block);
+
+    self.llvm_builder.position_at_end(ends_block);
+
+    let suffix_start = self
+      .llvm_builder
+      .build_int_sub(length, suffix_length, "suffix_start");
+    let suffix_bytes = unsafe {
+      self
+        .llvm_builder
+        .build_gep(bytes, &[suffix_start], "suffix_bytes")
+    };
+
+    let has_prefix = self.build_bytes_equal(
+      memcmp,
+      bytes,
+      self.build_string_bytes(prefix),
+      prefix_length,
+    );
+    let has_suffix = self.build_bytes_equal(
+      memcmp,
+      suffix_bytes,
+      self.build_string_bytes(suffix),
+      suffix_length,
+    );
+    let has_ends = self
+      .llvm_builder
+      .build_and(has_prefix, has_suffix, "has_ends");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(has_ends, holes_block, failed_block);
+
+    self.llvm_builder.position_at_end(holes_block);
+
+    let position_ptr = self.llvm_builder.build_alloca(i64_type, "position");
+    self.llvm_builder.build_store(position_ptr, prefix_length);
+
+    self.build_loop(function, hole_count, |hole| {
+      let last_hole_block = self.llvm_context.append_basic_block(function, "last_hole");
+      let inner_hole_block = self.llvm_context.append_basic_block(function, "inner_hole");
+      let found_block = self.llvm_context.append_basic_block(function, "found");
+      let next_hole_block = self.llvm_context.append_basic_block(function, "next_hole");
+
+      let start_index =
+        self
+          .llvm_builder
+          .build_int_mul(hole, i64_type.const_int(2, false), "start_index");
+      let end_index =
+        self
+          .llvm_builder
+          .build_int_add(start_index, i64_type.const_int(1, false), "end_index");
+      let start_ptr = unsafe { self.llvm_builder.build_gep(bounds, &[start_index], "start") };
+      let end_ptr = unsafe { self.llvm_builder.build_gep(bounds, &[end_index], "end") };
+
+      let start = self
+        .llvm_builder
+        .build_load(position_ptr, "start")
+        .into_int_value();
+      self.llvm_builder.build_store(start_ptr, start);
+
+      let next_literal_index =
+        self
+          .llvm_builder
+          .build_int_add(hole, i64_type.const_int(1, false), "next_literal_index");
+      let is_last = self.llvm_builder.build_int_compare(
+        IntPredicate::EQ,
+        next_literal_index,
+        hole_count,
+        "is_last",
+      );
+
+      self
+        .llvm_builder
+        .build_conditional_branch(is_last, last_hole_block, inner_hole_block);
+
+      // The last hole runs up to the suffix
+      self.llvm_builder.position_at_end(last_hole_block);
+      self.llvm_builder.build_store(end_ptr, suffix_start);
+      self
+        .llvm_builder
+        .build_unconditional_branch(next_hole_block);
+
+      // Other holes run up to the next literal
+      self.llvm_builder.position_at_end(inner_hole_block);
+
+      let next_literal = load_literal(next_literal_index);
+      let next_literal_length = self.build_string_length(next_literal);
+
+      let found = self.build_find_bytes(
+        function,
+        memcmp,
+        bytes,
+        start,
+        suffix_start,
+        self.build_string_bytes(next_literal),
+        next_literal_length,
+      );
+      let is_found = self.llvm_builder.build_int_compare(
+        IntPredicate::SGE,
+        found,
+        i64_type.const_zero(),
+        "is_found",
+      );
+
+      self
+        .llvm_builder
+        .build_conditional_branch(is_found, found_block, failed_block);
+
+      self.llvm_builder.position_at_end(found_block);
+      self.llvm_builder.build_store(end_ptr, found);
+
+      let position = self
+        .llvm_builder
+        .build_int_add(found, next_literal_length, "position");
+      self.llvm_builder.build_store(position_ptr, position);
+      self
+        .llvm_builder
+        .build_unconditional_branch(next_hole_block);
+
+      self.llvm_builder.position_at_end(next_hole_block);
+    });
+
+    self
+      .llvm_builder
+      .build_return(Some(&bool_type.const_int(1, false)));
+
+    self.llvm_builder.position_at_end(failed_block);
+    self
+      .llvm_builder
+      .build_return(Some(&bool_type.const_zero()));
+
+    function
+  }
+
+  /// Whether `length` bytes at `left` and `right` are the same.
+  fn build_bytes_equal(
+    &self,
+    memcmp: FunctionValue<'ctx>,
+    left: PointerValue<'ctx>,
+    right: PointerValue<'ctx>,
+    length: IntValue<'ctx>,
+  ) -> IntValue<'ctx> {
+    let order = self
+      .llvm_builder
+      .build_call(memcmp, &[left.into(), right.into(), length.into()], "order")
+      .try_as_basic_value()
+      .left()
+      .unwrap()
+      .into_int_value();
+
+    self.llvm_builder.build_int_compare(
+      IntPredicate::EQ,
+      order,
+      order.get_type().const_zero(),
+      "is_equal",
+    )
+  }
+
+  /// Finds the first occurrence of a needle in `bytes`, between `start` and
+  /// `end`. Returns its index, or -1 if it isn't there.
+  #[allow(clippy::too_many_arguments)]
+  fn build_find_bytes(
+    &self,
+    function: FunctionValue<'ctx>,
+    memcmp: FunctionValue<'ctx>,
+    bytes: PointerValue<'ctx>,
+    start: IntValue<'ctx>,
+    end: IntValue<'ctx>,
+    needle: PointerValue<'ctx>,
+    needle_length: IntValue<'ctx>,
+  ) -> IntValue<'ctx> {
+    let i64_type = self.llvm_context.i64_type();
+
+    let check_block = self.llvm_context.append_basic_block(function, "find_check");
+    let compare_block = self
+      .llvm_context
+      .append_basic_block(function, "find_compare");
+    let advance_block = self
+      .llvm_context
+      .append_basic_block(function, "find_advance");
+    let done_block = self.llvm_context.append_basic_block(function, "find_done");
+
+    let index_ptr = self.llvm_builder.build_alloca(i64_type, "index");
+    self.llvm_builder.build_store(index_ptr, start);
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(check_block);
+
+    let index = self
+      .llvm_builder
+      .build_load(index_ptr, "index")
+      .into_int_value();
+    let needle_end = self
+      .llvm_builder
+      .build_int_add(index, needle_length, "needle_end");
+    let fits = self
+      .llvm_builder
+      .build_int_compare(IntPredicate::ULE, needle_end, end, "fits");
+
+    self
+      .llvm_builder
+      .build_conditional_branch(fits, compare_block, done_block);
+
+    self.llvm_builder.position_at_end(compare_block);
+
+    let candidate = unsafe { self.llvm_builder.build_gep(bytes, &[index], "candidate") };
+    let is_equal = self.build_bytes_equal(memcmp, candidate, needle, needle_length);
+
+    self
+      .llvm_builder
+      .build_conditional_branch(is_equal, done_block, advance_block);
+
+    self.llvm_builder.position_at_end(advance_block);
+
+    let next_index =
+      self
+        .llvm_builder
+        .build_int_add(index, i64_type.const_int(1, false), "next_index");
+    self.llvm_builder.build_store(index_ptr, next_index);
+    self.llvm_builder.build_unconditional_branch(check_block);
+
+    self.llvm_builder.position_at_end(done_block);
+
+    let found = self.llvm_builder.build_phi(i64_type, "found");
+    found.add_incoming(&[
+      (&index, compare_block),
+      (&i64_type.const_all_ones(), check_block),
+    ]);
+
+    found.as_basic_value().into_int_value()
+  }
+}
@@ -0,0 +1,115 @@
+use inkwell::values::PointerValue;
+use pluma_ast::ValueType;
+use std::collections::HashMap;
+
+pub struct Variable<'ctx> {
+  pub ptr: PointerValue<'ctx>,
+  pub typ: ValueType,
+}
+
+type ScopeLevel<'ctx> = HashMap<String, Variable<'ctx>>;
+
+/// Tracks where the value of each name in scope is stored.
+pub struct Scope<'ctx> {
+  levels: Vec<ScopeLevel<'ctx>>,
+}
+
+impl<'ctx> Scope<'ctx> {
+  pub fn new() -> Self {
+    Scope { levels: Vec::new() }
+  }
+
+  pub fn enter(&mut self) {
+    self.levels.push(HashMap::new());
+  }
+
+  pub fn exit(&mut self) {
+    self.levels.pop();
+  }
+
+  /// The number of levels, which can be passed to `variables_since` to
+  /// get the variables added after this point.
+  pub fn depth(&self) -> usize {
+    self.levels.len()
+  }
+
+  /// Whether we are in the module's top-level scope, where bindings
+  /// live in globals rather than on the stack.
+  pub fn is_top_level(&self) -> bool {
+    self.levels.len() <= 1
+  }
+
+  pub fn add_variable(&mut self, name: String, ptr: PointerValue<'ctx>, typ: ValueType) {
+    let current_level = self.levels.last_mut().expect("no current scope");
+
+    current_level.insert(name, Variable { ptr, typ });
+  }
+
+  /// The variables added since the current level was entered, which
+  /// have to be released when it's exited. They're sorted by name, so
+  /// that the emitted code doesn't depend on the map's order.
+  pub fn current_variables(&self) -> Vec<(PointerValue<'ctx>, ValueType)> {
+    let current_level = self.levels.last().expect("no current scope");
+
+    let mut names = current_level.keys().collect::<Vec<&String>>();
+    names.sort();
+
+    names
+      .into_iter()
+      .map(|name| {
+        let variable = &current_level[name];
+
+        (variable.ptr, variable.typ.clone())
+      })
+      .collect()
+  }
+
+  /// Like `current_variables`, but for every level entered after the
+  /// given depth, innermost first.
+  pub fn variables_since(&self, depth: usize) -> Vec<(PointerValue<'ctx>, ValueType)> {
+    let mut variables = Vec::new();
+
+    for level in self.levels[depth..].iter().rev() {
+      let mut names = level.keys().collect::<Vec<&String>>();
+      names.sort();
+
+      for name in names {
+        variables.push((level[name].ptr, level[name].typ.clone()));
+      }
+    }
+
+    variables
+  }
+
+  pub fn get_variable(&self, name: &str) -> Option<&Variable<'ctx>> {
+    for level in self.levels.iter().rev() {
+      if let Some(variable) = level.get(name) {
+        return Some(variable);
+      }
+    }
+
+    None
+  }
+
+  /// Like `get_variable`, but ignores top-level variables. These are
+  /// the variables that a closure has to capture.
+  pub fn get_local_variable(&self, name: &str) -> Option<&Variable<'ctx>> {
+    for level in self.levels.iter().skip(1).rev() {
+      if let Some(variable) = level.get(name) {
+        return Some(variable);
+      }
+    }
+
+    None
+  }
+
+  /// Removes every level except the top-level one, so that a closure's
+  /// body can't see the locals of the function it was created in.
+  pub fn take_locals(&mut self) -> Vec<ScopeLevel<'ctx>> {
+    self.levels.split_off(self.levels.len().min(1))
+  }
+
+  pub fn restore_locals(&mut self, mut locals: Vec<ScopeLevel<'ctx>>) {
+    self.levels.append(&mut locals);
+  }
+}
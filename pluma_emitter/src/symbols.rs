@@ -0,0 +1,113 @@
+use crate::mangle;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Defs in different modules can have the same name, so each def's symbol
+// includes the module that defines it (e.g. `pluma.fn.bar@utils`). A name
+// in a module refers to the module's own def if it has one, and otherwise
+// to the def of a module that it imports.
+//
+// Intrinsic and extern defs are the same wherever they're declared, so
+// their symbols stay as they are.
+
+/// A module that's being emitted, and the modules it imports.
+#[derive(Default)]
+pub struct ModuleImports {
+  pub module_name: String,
+  /// Each imported module's name, with its qualifier if it has one (e.g.
+  /// `f` in `use foo as f`).
+  pub imports: Vec<(String, Option<String>)>,
+}
+
+/// Tracks which modules define each def.
+pub struct Symbols {
+  /// The modules that define a def, by its symbol without a module.
+  def_modules: HashMap<String, Vec<String>>,
+  current: Rc<ModuleImports>,
+}
+
+impl Symbols {
+  pub fn new() -> Self {
+    Symbols {
+      def_modules: HashMap::new(),
+      current: Rc::new(ModuleImports::default()),
+    }
+  }
+
+  /// Makes names resolve as they do in a module. Returns the module that
+  /// names resolved in before, so that it can be restored.
+  pub fn enter_module(&mut self, module: Rc<ModuleImports>) -> Rc<ModuleImports> {
+    std::mem::replace(&mut self.current, module)
+  }
+
+  pub fn current_module(&self) -> Rc<ModuleImports> {
+    self.current.clone()
+  }
+
+  /// Records that the current module defines a def, and returns the def's
+  /// symbol.
+  pub fn add_def(&mut self, name: &str) -> String {
+    let module_name = &self.current.module_name;
+
+    self
+      .def_modules
+      .entry(name.to_owned())
+      .or_default()
+      .push(module_name.clone());
+
+    mangle::module_symbol(name, module_name)
+  }
+
+  pub fn is_qualifier(&self, name: &str) -> bool {
+    self
+      .current
+      .imports
+      .iter()
+      .any(|(_, qualifier)| qualifier.as_deref() == Some(name))
+  }
+
+  /// The symbol of the def that a name refers to in the current module.
+  /// Names that no module defines (e.g. intrinsics) are their own symbol.
+  pub fn resolve(&self, name: &str) -> String {
+    let modules = match self.def_modules.get(name) {
+      Some(modules) => modules,
+      None => return name.to_owned(),
+    };
+
+    // The module's own defs come first, then those of unqualified imports.
+    // Methods and operators come with qualified imports too.
+    let mut visible_modules = Some(&self.current.module_name)
+      .into_iter()
+      .chain(self.imported_modules(|qualifier| qualifier.is_none()))
+      .chain(self.imported_modules(|qualifier| qualifier.is_some()));
+
+    let module_name = visible_modules
+      .find(|module_name| modules.contains(module_name))
+      .unwrap_or(&modules[0]);
+
+    mangle::module_symbol(name, module_name)
+  }
+
+  /// The symbol of the def that a qualified name (e.g. `f.bar`) refers to.
+  pub fn resolve_qualified(&self, qualifier: &str, name: &str) -> String {
+    match self
+      .imported_modules(|import_qualifier| import_qualifier == Some(qualifier))
+      .next()
+    {
+      Some(module_name) => mangle::module_symbol(name, module_name),
+      None => self.resolve(name),
+    }
+  }
+
+  fn imported_modules<'a>(
+    &'a self,
+    matches_qualifier: impl Fn(Option<&str>) -> bool + 'a,
+  ) -> impl Iterator<Item = &'a String> {
+    self
+      .current
+      .imports
+      .iter()
+      .filter(move |(_, qualifier)| matches_qualifier(qualifier.as_deref()))
+      .map(|(module_name, _)| module_name)
+  }
+}
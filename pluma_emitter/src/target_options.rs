@@ -0,0 +1,9 @@
+/// Describes the machine that the emitter should generate code for. Any
+/// value left unset is detected from the host machine.
+#[derive(Clone, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct TargetOptions {
+  pub triple: Option<String>,
+  pub cpu: Option<String>,
+  pub features: Option<String>,
+}
@@ -168,6 +168,12 @@ where
       LiteralKind::Str(val) => {
         self.out(format_args!("\"{}\"", val));
       }
+      LiteralKind::SizedInt(val, sized_type) => {
+        self.out(format_args!("{}{}", val, sized_type.suffix()));
+      }
+      LiteralKind::SizedFloat(val, sized_type) => {
+        self.out(format_args!("{}{}", val, sized_type.suffix()));
+      }
     }
   }
 
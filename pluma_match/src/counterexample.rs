@@ -0,0 +1,285 @@
+use crate::decision::*;
+use crate::type_info::TypeInfo;
+use pluma_ast::*;
+use std::fmt;
+
+/// A value that none of a match's cases cover, written the way it would
+/// be written as a pattern, e.g. `(Left _, Blue)`.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Counterexample {
+  Wildcard,
+  Variant(String, Option<Box<Counterexample>>),
+  Struct(String, Box<Counterexample>),
+  Tuple(Vec<Counterexample>),
+  LabeledTuple(Vec<(String, Counterexample)>),
+  Literal(LiteralValue),
+}
+
+/// What the path to a leaf of a decision tree says about part of the
+/// subject: either it passed a test, or it failed all of these tests.
+enum Constraint {
+  Is(Test),
+  IsNot(Vec<Test>),
+}
+
+/// Finds a counterexample for each failure leaf of a decision tree, by
+/// collecting the tests on the path to the leaf.
+pub(crate) fn find_counterexamples<T: TypeInfo>(
+  tree: &Decision,
+  subject_type: &ValueType,
+  type_info: &T,
+) -> Vec<Counterexample> {
+  let mut finder = CounterexampleFinder {
+    subject: Access::subject(subject_type.clone()),
+    type_info,
+    known: Vec::new(),
+    found: Vec::new(),
+  };
+
+  finder.walk(tree);
+  finder.found
+}
+
+struct CounterexampleFinder<'a, T: TypeInfo> {
+  subject: Access,
+  type_info: &'a T,
+  known: Vec<(Access, Constraint)>,
+  found: Vec<Counterexample>,
+}
+
+impl<'a, T: TypeInfo> CounterexampleFinder<'a, T> {
+  fn walk(&mut self, decision: &Decision) {
+    match decision {
+      Decision::Success { .. } => {}
+
+      Decision::Failure => {
+        let counterexample = self.build(&self.subject);
+
+        if !self.found.contains(&counterexample) {
+          self.found.push(counterexample);
+        }
+      }
+
+      Decision::Switch {
+        access,
+        branches,
+        fallback,
+      } => {
+        for (test, branch) in branches {
+          self
+            .known
+            .push((access.clone(), Constraint::Is(test.clone())));
+          self.walk(branch);
+          self.known.pop();
+        }
+
+        if let Some(fallback) = fallback {
+          let tests = branches.iter().map(|(test, _)| test.clone()).collect();
+
+          self.known.push((access.clone(), Constraint::IsNot(tests)));
+          self.walk(fallback);
+          self.known.pop();
+        }
+      }
+    }
+  }
+
+  fn build(&self, access: &Access) -> Counterexample {
+    let constraints = self
+      .known
+      .iter()
+      .filter(|(known_access, _)| known_access.path == access.path)
+      .map(|(_, constraint)| constraint);
+
+    let mut excluded = Vec::new();
+
+    for constraint in constraints {
+      match constraint {
+        Constraint::Is(test) => return self.build_passed(access, test),
+        Constraint::IsNot(tests) => excluded.extend(tests),
+      }
+    }
+
+    if !excluded.is_empty() {
+      return self.build_failed(access, &excluded);
+    }
+
+    // Nothing is known about this part of the subject itself, but there
+    // might be something known about the parts inside it
+    let has_known_parts = self.known.iter().any(|(known_access, _)| {
+      known_access.path.len() > access.path.len() && known_access.path.starts_with(&access.path)
+    });
+
+    if !has_known_parts {
+      return Counterexample::Wildcard;
+    }
+
+    match &access.typ {
+      ValueType::UnlabeledTuple(entry_types) => Counterexample::Tuple(
+        entry_types
+          .iter()
+          .enumerate()
+          .map(|(i, entry_type)| {
+            self.build(&access.child(AccessStep::TupleEntry(i), entry_type.clone()))
+          })
+          .collect(),
+      ),
+
+      ValueType::LabeledTuple(entries) => Counterexample::LabeledTuple(
+        entries
+          .iter()
+          .enumerate()
+          .map(|(i, (label, entry_type))| {
+            let entry_access = access.child(AccessStep::TupleEntry(i), entry_type.clone());
+
+            (label.clone(), self.build(&entry_access))
+          })
+          .collect(),
+      ),
+
+      ValueType::Named(name) | ValueType::Generic(name, _) => {
+        match self.type_info.struct_inner_type(&access.typ) {
+          Some(inner_type) => {
+            let inner_access = access.child(
+              AccessStep::StructPayload(inner_type.clone()),
+              inner_type,
+            );
+
+            Counterexample::Struct(name.clone(), Box::new(self.build(&inner_access)))
+          }
+          None => Counterexample::Wildcard,
+        }
+      }
+
+      _ => Counterexample::Wildcard,
+    }
+  }
+
+  fn build_passed(&self, access: &Access, test: &Test) -> Counterexample {
+    match test {
+      Test::Variant { name, .. } => {
+        let payload = self
+          .variant_payload_type(&access.typ, name)
+          .map(|payload_type| {
+            let payload_access = access.child(
+              AccessStep::VariantPayload(payload_type.clone()),
+              payload_type,
+            );
+
+            Box::new(self.build(&payload_access))
+          });
+
+        Counterexample::Variant(name.clone(), payload)
+      }
+
+      Test::Literal(value) => Counterexample::Literal(value.clone()),
+
+      // There's no way to write "a string that matches this pattern, but
+      // none of the later ones"
+      Test::Interpolation(..) => Counterexample::Wildcard,
+    }
+  }
+
+  /// Picks a value that fails all of the given tests.
+  fn build_failed(&self, access: &Access, excluded: &[&Test]) -> Counterexample {
+    if let Some(variants) = self.type_info.enum_variants(&access.typ) {
+      let missing_variant = variants.iter().find(|variant| {
+        !excluded
+          .iter()
+          .any(|test| matches!(test, Test::Variant { name, .. } if *name == variant.name))
+      });
+
+      if let Some(variant) = missing_variant {
+        let payload = variant
+          .payload_type
+          .as_ref()
+          .map(|_| Box::new(Counterexample::Wildcard));
+
+        return Counterexample::Variant(variant.name.clone(), payload);
+      }
+    }
+
+    if is_int_type(&access.typ) {
+      let missing_int = (0..)
+        .find(|i| !excluded.contains(&&Test::Literal(LiteralValue::Int(*i))))
+        .unwrap();
+
+      return Counterexample::Literal(LiteralValue::Int(missing_int));
+    }
+
+    Counterexample::Wildcard
+  }
+
+  fn variant_payload_type(&self, typ: &ValueType, name: &str) -> Option<ValueType> {
+    self
+      .type_info
+      .enum_variants(typ)?
+      .into_iter()
+      .find(|variant| variant.name == name)?
+      .payload_type
+  }
+}
+
+fn is_int_type(typ: &ValueType) -> bool {
+  match typ {
+    ValueType::Int => true,
+    ValueType::Sized(sized_type) => *sized_type != SizedType::Float32,
+    _ => false,
+  }
+}
+
+impl Counterexample {
+  /// Whether this needs parentheses when it's the payload of a variant or
+  /// struct, e.g. `Some (Left _)`.
+  fn needs_parens(&self) -> bool {
+    matches!(
+      self,
+      Counterexample::Variant(_, Some(..)) | Counterexample::Struct(..)
+    )
+  }
+}
+
+impl fmt::Display for Counterexample {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Counterexample::Wildcard => write!(f, "_"),
+
+      Counterexample::Variant(name, None) => write!(f, "{}", name),
+
+      Counterexample::Variant(name, Some(payload)) | Counterexample::Struct(name, payload) => {
+        if payload.needs_parens() {
+          write!(f, "{} ({})", name, payload)
+        } else {
+          write!(f, "{} {}", name, payload)
+        }
+      }
+
+      Counterexample::Tuple(entries) => write!(
+        f,
+        "({})",
+        entries
+          .iter()
+          .map(|entry| format!("{}", entry))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+
+      Counterexample::LabeledTuple(entries) => write!(
+        f,
+        "({})",
+        entries
+          .iter()
+          .map(|(label, entry)| format!("{}: {}", label, entry))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+
+      Counterexample::Literal(LiteralValue::Int(value)) => write!(f, "{}", value),
+
+      Counterexample::Literal(LiteralValue::Float(value)) => write!(f, "{}", value),
+
+      Counterexample::Literal(LiteralValue::String(value)) => write!(f, "\"{}\"", value),
+    }
+  }
+}
@@ -0,0 +1,133 @@
+use pluma_ast::ValueType;
+
+/// A decision tree for a match expression. Walking the tree from the root
+/// tests each part of the subject at most once on the way to a leaf.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Decision {
+  /// The case at this index matched. Its bindings refer to parts of the
+  /// subject, and should be bound before running the case's body.
+  Success {
+    case_index: usize,
+    bindings: Vec<Binding>,
+  },
+
+  /// No case matched.
+  Failure,
+
+  /// Tests part of the subject, and continues with the first branch whose
+  /// test passes. If none pass, continues with the fallback (if there's no
+  /// fallback, the branches cover every possible value).
+  Switch {
+    access: Access,
+    branches: Vec<(Test, Decision)>,
+    fallback: Option<Box<Decision>>,
+  },
+}
+
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Binding {
+  pub name: String,
+  pub access: Access,
+}
+
+/// Describes how to get to part of the subject, along with that part's
+/// type. An empty path refers to the subject itself.
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Access {
+  pub path: Vec<AccessStep>,
+  pub typ: ValueType,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum AccessStep {
+  /// The entry at this index of a tuple (labeled tuples use the position
+  /// of the label in the tuple's type).
+  TupleEntry(usize),
+
+  /// The payload of an enum value, which must already have been tested
+  /// for a variant with a payload of this type.
+  VariantPayload(ValueType),
+
+  /// The value that a struct was constructed from, which has this type.
+  StructPayload(ValueType),
+
+  /// The part of a string that the hole at this index matched, in a
+  /// pattern with these literals. The string must already have been
+  /// tested against the same literals.
+  InterpolationHole { literals: Vec<String>, index: usize },
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Test {
+  Variant {
+    tag: u32,
+    name: String,
+  },
+  Literal(LiteralValue),
+  /// Whether a string starts and ends with the first and last of these
+  /// literals, and contains the ones in between, in order.
+  Interpolation(Vec<String>),
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum LiteralValue {
+  Int(i64),
+  Float(f64),
+  String(String),
+}
+
+impl Access {
+  pub fn subject(typ: ValueType) -> Self {
+    Access {
+      path: Vec::new(),
+      typ,
+    }
+  }
+
+  pub(crate) fn child(&self, step: AccessStep, typ: ValueType) -> Self {
+    let mut path = self.path.clone();
+    path.push(step);
+
+    Access { path, typ }
+  }
+}
+
+impl Test {
+  /// Whether a value could pass both this test and a different one on the
+  /// same part of the subject. Variants and literals never overlap, but a
+  /// string can match several interpolation patterns and literals at once.
+  pub fn can_overlap(&self) -> bool {
+    match self {
+      Test::Variant { .. } | Test::Literal(..) => false,
+      Test::Interpolation(..) => true,
+    }
+  }
+}
+
+impl Decision {
+  /// Calls `f` with the index of each case that a success leaf leads to.
+  pub fn for_each_case<F: FnMut(usize)>(&self, f: &mut F) {
+    match self {
+      Decision::Success { case_index, .. } => f(*case_index),
+
+      Decision::Failure => {}
+
+      Decision::Switch {
+        branches, fallback, ..
+      } => {
+        for (_, branch) in branches {
+          branch.for_each_case(f);
+        }
+
+        if let Some(fallback) = fallback {
+          fallback.for_each_case(f);
+        }
+      }
+    }
+  }
+}
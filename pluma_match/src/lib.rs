@@ -0,0 +1,10 @@
+mod counterexample;
+mod decision;
+mod match_compiler;
+mod pattern;
+mod type_info;
+
+pub use counterexample::*;
+pub use decision::*;
+pub use match_compiler::*;
+pub use type_info::*;
@@ -0,0 +1,285 @@
+use crate::counterexample::*;
+use crate::decision::*;
+use crate::pattern::Pattern;
+use crate::type_info::TypeInfo;
+use pluma_ast::*;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct CompiledMatch {
+  pub tree: Decision,
+  /// Indexes of cases that can never match, because the cases before them
+  /// already match every value they would.
+  pub unreachable_cases: Vec<usize>,
+  /// Values that no case matches. Empty if the cases are exhaustive.
+  pub missing_patterns: Vec<Counterexample>,
+}
+
+/// Compiles the cases of a match expression to a decision tree, roughly
+/// following Maranget's "Compiling Pattern Matching to Good Decision Trees".
+pub struct MatchCompiler<'a, T: TypeInfo> {
+  type_info: &'a T,
+}
+
+/// A row of the pattern matrix: the tests that still have to pass for a
+/// case to match, and the names it binds if they do.
+#[derive(Clone)]
+struct Row {
+  case_index: usize,
+  tests: Vec<(Access, Pattern)>,
+  bindings: Vec<Binding>,
+}
+
+impl<'a, T: TypeInfo> MatchCompiler<'a, T> {
+  pub fn new(type_info: &'a T) -> Self {
+    MatchCompiler { type_info }
+  }
+
+  pub fn compile(&self, cases: &[MatchCaseNode], subject_type: &ValueType) -> CompiledMatch {
+    let subject = Access::subject(subject_type.clone());
+
+    let rows = cases
+      .iter()
+      .enumerate()
+      .map(|(case_index, case)| {
+        let pattern = Pattern::from_node(&case.pattern, subject_type, self.type_info);
+
+        let mut row = Row {
+          case_index,
+          tests: Vec::new(),
+          bindings: Vec::new(),
+        };
+
+        flatten(subject.clone(), pattern, &mut row.tests, &mut row.bindings);
+
+        row
+      })
+      .collect();
+
+    let tree = self.compile_rows(rows);
+
+    let mut reachable = vec![false; cases.len()];
+    tree.for_each_case(&mut |case_index| reachable[case_index] = true);
+
+    let unreachable_cases = (0..cases.len())
+      .filter(|case_index| !reachable[*case_index])
+      .collect();
+
+    let missing_patterns = find_counterexamples(&tree, subject_type, self.type_info);
+
+    CompiledMatch {
+      tree,
+      unreachable_cases,
+      missing_patterns,
+    }
+  }
+
+  fn compile_rows(&self, mut rows: Vec<Row>) -> Decision {
+    let first_row = match rows.first() {
+      Some(row) => row,
+      None => return Decision::Failure,
+    };
+
+    // If the first row has nothing left to test, it matches
+    if first_row.tests.is_empty() {
+      let row = rows.remove(0);
+
+      return Decision::Success {
+        case_index: row.case_index,
+        bindings: row.bindings,
+      };
+    }
+
+    // Otherwise, test whatever the first row needs to test next. Every row
+    // that tests the same part of the subject shares this test, unless the
+    // tests can overlap. Then only the first row's test gets a branch, and
+    // the other rows keep theirs for later.
+    let access = first_row.tests[0].0.clone();
+    let mut tests = vec![head_test(&first_row.tests[0].1)];
+
+    if !tests[0].can_overlap() {
+      for row in &rows {
+        if let Some(pattern) = row_test(row, &access) {
+          let test = head_test(pattern);
+
+          if !test.can_overlap() && !tests.contains(&test) {
+            tests.push(test);
+          }
+        }
+      }
+    }
+
+    let branches = tests
+      .iter()
+      .map(|test| {
+        let specialized_rows = rows
+          .iter()
+          .filter_map(|row| specialize(row, &access, test))
+          .collect();
+
+        (test.clone(), self.compile_rows(specialized_rows))
+      })
+      .collect();
+
+    // If there's a branch for every variant of an enum, no other value is
+    // possible, so there's no need for a fallback.
+    let is_complete = match self.type_info.enum_variants(&access.typ) {
+      Some(variants) => variants.len() == tests.len(),
+      None => false,
+    };
+
+    let fallback = if is_complete {
+      None
+    } else {
+      let default_rows = rows
+        .into_iter()
+        .filter(|row| match row_test(row, &access) {
+          Some(pattern) => !tests.contains(&head_test(pattern)),
+          None => true,
+        })
+        .collect();
+
+      Some(Box::new(self.compile_rows(default_rows)))
+    };
+
+    Decision::Switch {
+      access,
+      branches,
+      fallback,
+    }
+  }
+}
+
+/// Breaks a pattern down into the tests it makes on parts of the subject
+/// and the names it binds. Tuples and structs always match, so only their
+/// contents need testing.
+fn flatten(
+  access: Access,
+  pattern: Pattern,
+  tests: &mut Vec<(Access, Pattern)>,
+  bindings: &mut Vec<Binding>,
+) {
+  match pattern {
+    Pattern::Wildcard(Some(name)) => bindings.push(Binding { name, access }),
+
+    Pattern::Wildcard(None) => {}
+
+    Pattern::Tuple(entries) => {
+      for (i, (entry_pattern, entry_type)) in entries.into_iter().enumerate() {
+        let entry_access = access.child(AccessStep::TupleEntry(i), entry_type);
+
+        flatten(entry_access, entry_pattern, tests, bindings);
+      }
+    }
+
+    Pattern::Struct(inner_pattern, inner_type) => {
+      let inner_access = access.child(AccessStep::StructPayload(inner_type.clone()), inner_type);
+
+      flatten(inner_access, *inner_pattern, tests, bindings);
+    }
+
+    Pattern::Variant { .. } | Pattern::Literal(..) | Pattern::Interpolation { .. } => {
+      tests.push((access, pattern))
+    }
+  }
+}
+
+/// Specializes a row for the branch where the given test passed. Returns
+/// None if the row can't match in that branch.
+fn specialize(row: &Row, access: &Access, test: &Test) -> Option<Row> {
+  let position = row
+    .tests
+    .iter()
+    .position(|(row_access, _)| row_access == access);
+
+  let i = match position {
+    Some(i) => i,
+    // The row doesn't care about this part of the subject
+    None => return Some(row.clone()),
+  };
+
+  let own_test = head_test(&row.tests[i].1);
+
+  if own_test != *test {
+    // The row's own test might still pass, so it's left for later
+    if own_test.can_overlap() || test.can_overlap() {
+      return Some(row.clone());
+    }
+
+    return None;
+  }
+
+  let mut row = row.clone();
+  let (_, pattern) = row.tests.remove(i);
+
+  match pattern {
+    // Now that we know the variant, we can test its payload. These tests go
+    // where the variant's test was, so cases are still tested left to right.
+    Pattern::Variant {
+      payload: Some((payload_pattern, payload_type)),
+      ..
+    } => {
+      let payload_access = access.child(
+        AccessStep::VariantPayload(payload_type.clone()),
+        payload_type,
+      );
+
+      let mut payload_tests = Vec::new();
+      flatten(
+        payload_access,
+        *payload_pattern,
+        &mut payload_tests,
+        &mut row.bindings,
+      );
+
+      row.tests.splice(i..i, payload_tests);
+    }
+
+    // Holes always match, once the literals around them have
+    Pattern::Interpolation { literals, holes } => {
+      for (index, hole) in holes.into_iter().enumerate() {
+        if let Some(name) = hole {
+          let hole_access = access.child(
+            AccessStep::InterpolationHole {
+              literals: literals.clone(),
+              index,
+            },
+            ValueType::String,
+          );
+
+          row.bindings.push(Binding {
+            name,
+            access: hole_access,
+          });
+        }
+      }
+    }
+
+    _ => {}
+  }
+
+  Some(row)
+}
+
+/// The pattern that a row tests the given part of the subject against.
+fn row_test<'r>(row: &'r Row, access: &Access) -> Option<&'r Pattern> {
+  row
+    .tests
+    .iter()
+    .find(|(row_access, _)| row_access == access)
+    .map(|(_, pattern)| pattern)
+}
+
+fn head_test(pattern: &Pattern) -> Test {
+  match pattern {
+    Pattern::Variant { tag, name, .. } => Test::Variant {
+      tag: *tag,
+      name: name.clone(),
+    },
+
+    Pattern::Literal(value) => Test::Literal(value.clone()),
+
+    Pattern::Interpolation { literals, .. } => Test::Interpolation(literals.clone()),
+
+    _ => unreachable!("only variant, literal and interpolation patterns need testing"),
+  }
+}
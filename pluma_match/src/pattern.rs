@@ -0,0 +1,167 @@
+use crate::decision::*;
+use crate::type_info::TypeInfo;
+use pluma_ast::*;
+
+/// A simplified version of a PatternNode. Names are resolved, so we know
+/// whether each identifier is a binding or an enum variant, and labeled
+/// tuple entries are put in the same order as in the tuple's type.
+#[derive(Clone)]
+pub enum Pattern {
+  Wildcard(Option<String>),
+  Variant {
+    tag: u32,
+    name: String,
+    payload: Option<(Box<Pattern>, ValueType)>,
+  },
+  Struct(Box<Pattern>, ValueType),
+  Tuple(Vec<(Pattern, ValueType)>),
+  Literal(LiteralValue),
+  /// A string pattern with holes, e.g. `"$(name).pa"`. There's one more
+  /// literal than there are holes, and the first and last may be empty.
+  Interpolation {
+    literals: Vec<String>,
+    holes: Vec<Option<String>>,
+  },
+}
+
+impl Pattern {
+  pub fn from_node<T: TypeInfo>(node: &PatternNode, typ: &ValueType, type_info: &T) -> Self {
+    match &node.kind {
+      PatternKind::Identifier(ident, _is_mutable) => {
+        match find_variant(&ident.name, typ, type_info) {
+          Some((tag, _)) => Pattern::Variant {
+            tag,
+            name: ident.name.clone(),
+            payload: None,
+          },
+
+          None => Pattern::Wildcard(Some(ident.name.clone())),
+        }
+      }
+
+      PatternKind::Underscore => Pattern::Wildcard(None),
+
+      PatternKind::Constructor(ident, inner) => match find_variant(&ident.name, typ, type_info) {
+        Some((tag, payload_type)) => {
+          let payload_type =
+            payload_type.expect("analyzer should reject constructor patterns without a payload");
+
+          Pattern::Variant {
+            tag,
+            name: ident.name.clone(),
+            payload: Some((
+              Box::new(Pattern::from_node(inner, &payload_type, type_info)),
+              payload_type,
+            )),
+          }
+        }
+
+        None => {
+          let inner_type = type_info
+            .struct_inner_type(typ)
+            .expect("analyzer should reject unknown constructors");
+
+          Pattern::Struct(
+            Box::new(Pattern::from_node(inner, &inner_type, type_info)),
+            inner_type,
+          )
+        }
+      },
+
+      PatternKind::UnlabeledTuple(element_patterns) => match typ {
+        ValueType::UnlabeledTuple(element_types) => Pattern::Tuple(
+          element_patterns
+            .iter()
+            .zip(element_types)
+            .map(|(element_pattern, element_type)| {
+              (
+                Pattern::from_node(element_pattern, element_type, type_info),
+                element_type.clone(),
+              )
+            })
+            .collect(),
+        ),
+
+        _ => unreachable!("analyzer should reject non-tuple values"),
+      },
+
+      PatternKind::LabeledTuple(element_patterns) => match typ {
+        ValueType::LabeledTuple(entries) => Pattern::Tuple(
+          entries
+            .iter()
+            .map(|(label, entry_type)| {
+              let element_pattern = element_patterns
+                .iter()
+                .find(|(pattern_label, _)| pattern_label.name == *label);
+
+              let pattern = match element_pattern {
+                Some((_, element_pattern)) => {
+                  Pattern::from_node(element_pattern, entry_type, type_info)
+                }
+                None => Pattern::Wildcard(None),
+              };
+
+              (pattern, entry_type.clone())
+            })
+            .collect(),
+        ),
+
+        _ => unreachable!("analyzer should reject non-tuple values"),
+      },
+
+      PatternKind::Literal(lit) => Pattern::Literal(literal_value(lit)),
+
+      PatternKind::Interpolation(parts) => {
+        let mut literals = Vec::new();
+        let mut holes = Vec::new();
+
+        for part in parts {
+          match &part.kind {
+            ExprKind::Literal(LiteralNode {
+              kind: LiteralKind::Str(value),
+              ..
+            }) => literals.push(value.clone()),
+
+            ExprKind::Identifier(ident) => holes.push(Some(ident.name.clone())),
+
+            ExprKind::Underscore => holes.push(None),
+
+            _ => unreachable!("analyzer should reject holes that aren't names"),
+          }
+        }
+
+        Pattern::Interpolation { literals, holes }
+      }
+    }
+  }
+}
+
+fn find_variant<T: TypeInfo>(
+  name: &str,
+  typ: &ValueType,
+  type_info: &T,
+) -> Option<(u32, Option<ValueType>)> {
+  type_info
+    .enum_variants(typ)?
+    .into_iter()
+    .enumerate()
+    .find(|(_, variant)| variant.name == name)
+    .map(|(tag, variant)| (tag as u32, variant.payload_type))
+}
+
+fn literal_value(lit: &LiteralNode) -> LiteralValue {
+  match &lit.kind {
+    LiteralKind::IntDecimal(value)
+    | LiteralKind::IntOctal(value)
+    | LiteralKind::IntHex(value)
+    | LiteralKind::IntBinary(value) => LiteralValue::Int(*value),
+
+    LiteralKind::SizedInt(value, _) => LiteralValue::Int(*value as i64),
+
+    LiteralKind::FloatDecimal(value) | LiteralKind::SizedFloat(value, _) => {
+      LiteralValue::Float(*value)
+    }
+
+    LiteralKind::Str(value) => LiteralValue::String(value.clone()),
+  }
+}
@@ -0,0 +1,22 @@
+use pluma_ast::ValueType;
+
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Variant {
+  pub name: String,
+  pub payload_type: Option<ValueType>,
+}
+
+/// What the match compiler needs to know about user-defined types. This
+/// is implemented by whatever is consuming the decision trees, since it
+/// knows how those types are laid out.
+pub trait TypeInfo {
+  /// The variants of an enum type, in tag order. Returns None if the type
+  /// is not an enum. For a generic enum, the payload types are the ones
+  /// for the type's args (e.g. `Int` for `Some` in `Maybe<Int>`).
+  fn enum_variants(&self, typ: &ValueType) -> Option<Vec<Variant>>;
+
+  /// The inner type of a struct type. Returns None if the type is not a
+  /// struct.
+  fn struct_inner_type(&self, typ: &ValueType) -> Option<ValueType>;
+}
@@ -0,0 +1,143 @@
+use pluma_ast::*;
+use pluma_match::*;
+use std::collections::HashMap;
+
+/// Type info for tests, collected straight from a module's type defs.
+pub struct TestTypeInfo {
+  enums: HashMap<String, Vec<Variant>>,
+  structs: HashMap<String, ValueType>,
+}
+
+impl TestTypeInfo {
+  pub fn from_module(module: &ModuleNode) -> Self {
+    let mut enums = HashMap::new();
+    let mut structs = HashMap::new();
+
+    for statement in &module.body {
+      if let TopLevelStatementKind::TypeDef(type_def) = &statement.kind {
+        match &type_def.kind {
+          TypeDefKind::Enum { variants } => {
+            let variants = variants
+              .iter()
+              .map(|variant| match &variant.kind {
+                EnumVariantKind::Identifier(ident) => Variant {
+                  name: ident.name.clone(),
+                  payload_type: None,
+                },
+                EnumVariantKind::Constructor(ident, param) => Variant {
+                  name: ident.name.clone(),
+                  payload_type: Some(param.typ.clone()),
+                },
+              })
+              .collect();
+
+            enums.insert(type_def.name.name.clone(), variants);
+          }
+
+          TypeDefKind::Struct { inner } => {
+            structs.insert(type_def.name.name.clone(), inner.typ.clone());
+          }
+
+          _ => {}
+        }
+      }
+    }
+
+    TestTypeInfo { enums, structs }
+  }
+}
+
+impl TypeInfo for TestTypeInfo {
+  fn enum_variants(&self, typ: &ValueType) -> Option<Vec<Variant>> {
+    match typ {
+      ValueType::Named(name) => self.enums.get(name).cloned(),
+      _ => None,
+    }
+  }
+
+  fn struct_inner_type(&self, typ: &ValueType) -> Option<ValueType> {
+    match typ {
+      ValueType::Named(name) => self.structs.get(name).cloned(),
+      _ => None,
+    }
+  }
+}
+
+/// Finds the last match expression at the top level of a module.
+pub fn last_match(module: &ModuleNode) -> &MatchNode {
+  module
+    .body
+    .iter()
+    .rev()
+    .find_map(|statement| {
+      let expr = match &statement.kind {
+        TopLevelStatementKind::Let(let_node) => &let_node.value,
+        TopLevelStatementKind::Expr(expr) => expr,
+        _ => return None,
+      };
+
+      match &expr.kind {
+        ExprKind::Match(match_node) => Some(match_node),
+        _ => None,
+      }
+    })
+    .expect("expected a match expression")
+}
+
+#[macro_export]
+macro_rules! test_match {
+  ($($name:ident: $source:literal,)*) => {
+    $(
+        #[test]
+        fn $name() {
+            use insta::assert_snapshot;
+            use pluma_analyzer::*;
+            use pluma_match::*;
+            use pluma_parser::*;
+            use pluma_visitor::TraverseMut;
+
+            let replaced = $source.replace("\n    |", "\n");
+            let source = replaced.trim();
+            let source_copy = source.clone();
+            let bytes = Vec::from(source);
+            let tokenizer = Tokenizer::from_source(&bytes, false);
+            let mut parser = Parser::new(&bytes, tokenizer, false);
+            let (mut ast, _imports, _, errors) = parser.parse_module();
+
+            if !errors.is_empty() {
+              panic!("parse errors: {:#?}", errors);
+            }
+
+            let mut scope = Scope::new();
+            scope.enter();
+
+            let mut analyzer = Analyzer::new(&mut scope);
+            ast.traverse_mut(&mut analyzer);
+
+            // Warnings (e.g. for unreachable cases) don't stop the match
+            // from compiling
+            if analyzer.diagnostics.iter().any(|diag| diag.is_error()) {
+              panic!("analysis errors: {:#?}", analyzer.diagnostics);
+            }
+
+            let type_info = macros::TestTypeInfo::from_module(&ast);
+            let match_node = macros::last_match(&ast);
+
+            let compiled = MatchCompiler::new(&type_info)
+              .compile(&match_node.cases, &match_node.subject.typ);
+
+            let file_name = format!("{}", stringify!($name));
+
+            let formatted = format!("
+=== Source ===
+{}
+
+=== Compiled match ===
+{:#?}
+", source_copy, compiled);
+
+            assert_snapshot!(file_name, formatted, &source_copy);
+        }
+    )*
+  }
+}
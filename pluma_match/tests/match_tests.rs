@@ -0,0 +1,97 @@
+#[macro_use]
+mod macros;
+
+test_match! {
+  enum_variants: r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let n = match c
+    |  | Red => 1
+    |  | Green => 2
+    |  | Blue => 3
+  "#,
+
+  enum_variants_with_fallback: r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let d = match c
+    |  | Red => Blue
+    |  | other => other
+  "#,
+
+  int_literals: r#"
+    |intrinsic_type Int
+    |
+    |let i = 2
+    |
+    |let n = match i
+    |  | 1 => 100
+    |  | 2 => 200
+    |  | _ => 0
+  "#,
+
+  nested_constructors_in_tuples: r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |enum Either
+    | | Left(Int)
+    | | Right(String)
+    |
+    |let pair = (Left(1), Right("two"))
+    |
+    |let n = match pair
+    |  | (Left 0, _) => 0
+    |  | (Left a, Right _) => a
+    |  | (_, Left b) => b
+    |  | (Right _, Right _) => 3
+  "#,
+
+  struct_patterns: r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |struct Point (x :: Int)
+    |
+    |let p = Point (x: 1)
+    |
+    |let n = match p
+    |  | Point (x: 0) => 0
+    |  | Point (x: x) => x
+  "#,
+
+  unreachable_cases: r#"
+    |intrinsic_type Int
+    |
+    |enum Color | Red | Green | Blue
+    |
+    |let c = Green
+    |
+    |let n = match c
+    |  | Red => 1
+    |  | _ => 2
+    |  | Blue => 3
+    |  | Red => 4
+  "#,
+
+  string_patterns: r#"
+    |intrinsic_type Int
+    |intrinsic_type String
+    |
+    |let pair = ("main.pa", 1)
+    |
+    |let s = match pair
+    |  | ("$(name).pa", 0) => name
+    |  | ("main.$(ext)", _) => ext
+    |  | ("$(name).pa", _) => name
+    |  | (other, _) => other
+  "#,
+}
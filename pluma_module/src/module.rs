@@ -12,6 +12,7 @@ pub struct Module {
   pub ast: Option<ModuleNode>,
   pub comments: Option<CommentMap>,
   pub line_break_positions: Option<Vec<Position>>,
+  pub source: Option<Vec<u8>>,
   imports: Option<Vec<UseNode>>,
   collect_comments: bool,
 }
@@ -25,26 +26,31 @@ impl Module {
       imports: None,
       comments: None,
       line_break_positions: None,
+      source: None,
       collect_comments,
     }
   }
 
   pub fn parse(&mut self) -> Result<(), Vec<Diagnostic>> {
-    let mut diagnostics = Vec::new();
-
     match fs::read(&self.module_path) {
-      Ok(bytes) => self.build_ast(bytes, &mut diagnostics),
-      Err(err) => diagnostics.push(
-        Diagnostic::error(err)
-          .with_module(self.module_name.clone(), self.module_path.to_path_buf()),
-      ),
+      Ok(bytes) => self.parse_source(bytes),
+      Err(err) => Err(vec![Diagnostic::error(err)
+        .with_module(self.module_name.clone(), self.module_path.to_path_buf())]),
     }
+  }
+
+  /// Parses source that isn't read from the module's path, e.g. a module
+  /// that's bundled into the compiler.
+  pub fn parse_source(&mut self, bytes: Vec<u8>) -> Result<(), Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+
+    self.build_ast(bytes, &mut diagnostics);
 
     if diagnostics.is_empty() {
       return Ok(());
     }
 
-    return Err(diagnostics);
+    Err(diagnostics)
   }
 
   pub fn did_parse(&self) -> bool {
@@ -65,6 +71,13 @@ impl Module {
     imports
   }
 
+  /// Adds an import that isn't written in the module, e.g. the prelude.
+  pub fn add_import(&mut self, import_node: UseNode) {
+    if let Some(imports) = &mut self.imports {
+      imports.push(import_node);
+    }
+  }
+
   pub fn traverse<V: Visitor>(&self, visitor: &mut V) {
     if let Some(ast) = &self.ast {
       ast.traverse(visitor)
@@ -80,8 +93,8 @@ impl Module {
   fn build_ast(&mut self, bytes: Vec<u8>, diagnostics: &mut Vec<Diagnostic>) {
     let tokenizer = Tokenizer::from_source(&bytes, self.collect_comments);
 
-    let (ast, imports, comment_data, errors) =
-      Parser::new(&bytes, tokenizer, self.collect_comments).parse_module();
+    let mut parser = Parser::new(&bytes, tokenizer, self.collect_comments);
+    let (ast, imports, comment_data, errors) = parser.parse_module();
 
     if !errors.is_empty() {
       for err in errors {
@@ -95,19 +108,14 @@ impl Module {
       return;
     }
 
-    // imports.push(UseNode {
-    //   pos: (0, 0),
-    //   module_name: "std/prelude".into(),
-    //   qualifier: None,
-    // });
-
     self.ast = Some(ast);
     self.imports = Some(imports);
+    self.line_break_positions = Some(parser.line_break_positions().to_vec());
+    self.source = Some(bytes.clone());
 
     if self.collect_comments {
-      let (comments, line_break_positions) = comment_data.unwrap();
+      let (comments, _) = comment_data.unwrap();
       self.comments = Some(comments);
-      self.line_break_positions = Some(line_break_positions);
     }
   }
 }
@@ -1,4 +1,5 @@
 use crate::tokens::Token;
+use pluma_ast::SizedType;
 use std::fmt;
 
 #[derive(Copy, Clone)]
@@ -15,6 +16,9 @@ pub enum ParseErrorKind {
   EmptyRegularExpressionGroup,
   EmptyRegularExpressionCount,
   IncompleteMethodSignature,
+  IntegerLiteralTooLarge,
+  IntegerLiteralTooLargeForType(SizedType),
+  IntegerSuffixOnFloatLiteral(SizedType),
   InvalidBinaryDigit,
   InvalidDecimalDigit,
   InvalidHexDigit,
@@ -30,13 +34,13 @@ pub enum ParseErrorKind {
   MissingExpressionAfterReturn,
   MissingIdentifier,
   MissingIndexBetweenBrackets,
+  MissingLinkName,
   MissingLabelInTuple,
   MissingMatchCases,
   MissingQualifierAfterAs,
   MissingReturnType,
   MissingRightHandSideOfAssignment,
   MissingStructFields,
-  MissingTraitConstraints,
   MissingTupleEntries,
   MissingType,
   MissingTypeInBlockType,
@@ -62,6 +66,17 @@ impl fmt::Display for ParseError {
       EmptyRegularExpressionCount => write!(f, "Empty repetition count in regular expression."),
       EmptyRegularExpressionGroup => write!(f, "Empty grouping in regular expression."),
       IncompleteMethodSignature => write!(f, "Incomplete method signature."),
+      IntegerLiteralTooLarge => write!(f, "Integer literal is too large to fit in an Int."),
+      IntegerLiteralTooLargeForType(sized_type) => write!(
+        f,
+        "Integer literal is too large to fit in its type, {}.",
+        sized_type.name()
+      ),
+      IntegerSuffixOnFloatLiteral(sized_type) => write!(
+        f,
+        "A float literal can't have the suffix for an integer type, {}.",
+        sized_type.name()
+      ),
       InvalidBinaryDigit => write!(f, "Invalid binary digit."),
       InvalidDecimalDigit => write!(f, "Invalid digit."),
       InvalidHexDigit => write!(f, "Invalid hexadecimal digit."),
@@ -79,6 +94,7 @@ impl fmt::Display for ParseError {
       MissingExpressionAfterReturn => write!(f, "Missing expression after 'return'."),
       MissingIdentifier => write!(f, "Missing identifier."),
       MissingIndexBetweenBrackets => write!(f, "Missing index between '[' and ']'."),
+      MissingLinkName => write!(f, "Missing C symbol name after '=' in extern definition."),
       MissingLabelInTuple => write!(f, "Missing label in labeled tuple."),
       MissingMatchCases => write!(f, "Missing cases in match expression."),
       MissingQualifierAfterAs => write!(f, "Missing identifier after 'as'."),
@@ -87,7 +103,6 @@ impl fmt::Display for ParseError {
         write!(f, "Missing expression after '=' in let statement.")
       }
       MissingStructFields => write!(f, "Missing struct fields."),
-      MissingTraitConstraints => write!(f, "Missing trait constraints."),
       MissingTupleEntries => write!(f, "Missing tuple entries."),
       MissingType => write!(f, "Missing type."),
       MissingTypeInBlockType => write!(f, "Missing type between '{{' and '}}' in block type."),
@@ -131,6 +131,12 @@ impl<'a> Parser<'a> {
     (module_node, imports, comment_data, self.errors.clone())
   }
 
+  /// The positions of every line break the parser has seen so far, which
+  /// can be used to convert positions into line and column numbers.
+  pub fn line_break_positions(&self) -> &[Position] {
+    &self.line_break_positions
+  }
+
   fn advance(&mut self) {
     self.prev_token = self.current_token;
     self.current_token = self.tokenizer.next();
@@ -139,11 +145,9 @@ impl<'a> Parser<'a> {
 
   fn skip_line_breaks(&mut self) {
     while current_token_is!(self, Token::LineBreak) {
-      if self.collect_comments {
-        self
-          .line_break_positions
-          .push(self.current_token_position())
-      }
+      self
+        .line_break_positions
+        .push(self.current_token_position());
 
       self.advance()
     }
@@ -204,15 +208,19 @@ impl<'a> Parser<'a> {
   }
 
   fn parse_binary_number(&mut self) -> Option<LiteralNode> {
-    let (start, end, value) = expect_token_and_do!(self, Token::BinaryDigits, {
+    let (start, end, kind) = expect_token_and_do!(self, Token::BinaryDigits, {
       let (start, end) = self.current_token_position();
-      (start, end, self.parse_numeric_literal(start, end, 2))
+      (
+        start,
+        end,
+        self.parse_int_literal(start, end, 2, LiteralKind::IntBinary),
+      )
     });
 
     self.advance();
 
     Some(LiteralNode {
-      kind: LiteralKind::IntBinary(value),
+      kind,
       pos: (start, end),
     })
   }
@@ -316,6 +324,20 @@ impl<'a> Parser<'a> {
     })
   }
 
+  fn parse_break(&mut self) -> Option<ExprNode> {
+    let pos = expect_token_and_do!(self, Token::KeywordBreak, {
+      let pos = self.current_token_position();
+      self.advance();
+      pos
+    });
+
+    Some(ExprNode {
+      pos,
+      kind: ExprKind::Break,
+      typ: ValueType::Unknown,
+    })
+  }
+
   fn parse_call(&mut self, last_expr: ExprNode) -> Option<CallNode> {
     // At this point, last_expr is either an Identifier (e.g. print in `print "hello"`)
     // or a Chain (e.g. `a 1 . b` in `a 1 . b 2.`).
@@ -574,29 +596,115 @@ impl<'a> Parser<'a> {
       pos
     });
 
-    if current_token_is!(self, Token::Dot) {
+    let (_, int_suffix) = self.split_numeric_suffix(start, end, 10);
+
+    if int_suffix.is_none() && current_token_is!(self, Token::Dot) {
       self.advance();
 
       expect_token_and_do!(self, Token::DecimalDigits, {
-        let (_, end) = self.current_token_position();
+        let (fraction_start, end) = self.current_token_position();
 
         self.advance();
 
-        let str_value = read_string!(self, start, end);
+        let (digits_end, suffix) = self.split_numeric_suffix(fraction_start, end, 10);
+        let str_value = read_string!(self, start, digits_end);
         let float_value = str_value.parse::<f64>().unwrap();
 
+        let kind = match suffix {
+          None => LiteralKind::FloatDecimal(float_value),
+          Some(sized_type) if sized_type.is_float() => {
+            LiteralKind::SizedFloat(float_value, sized_type)
+          }
+          Some(sized_type) => {
+            self.error::<()>(ParseError {
+              pos: (digits_end, end),
+              kind: ParseErrorKind::IntegerSuffixOnFloatLiteral(sized_type),
+            });
+
+            LiteralKind::FloatDecimal(float_value)
+          }
+        };
+
         return Some(LiteralNode {
-          kind: LiteralKind::FloatDecimal(float_value),
+          kind,
           pos: (start, end),
         });
       });
     }
 
-    let value = self.parse_numeric_literal(start, end, 10);
-
     Some(LiteralNode {
-      kind: LiteralKind::IntDecimal(value),
+      kind: self.parse_int_literal(start, end, 10, LiteralKind::IntDecimal),
+      pos: (start, end),
+    })
+  }
+
+  fn parse_extern_definition(&mut self) -> Option<ExternDefNode> {
+    let start = expect_token_and_do!(self, Token::KeywordExtern, {
+      let pos = self.current_token_position();
+      self.advance();
+      pos.0
+    });
+
+    expect_token_and_do!(self, Token::KeywordDef, {
+      self.advance();
+    });
+
+    let kind = match self.parse_definition_kind() {
+      Some(kind_node) => kind_node,
+      _ => {
+        // Just return without adding a new error. Assumes that the
+        // failure to parse the kind has already generated an error.
+        return None;
+      }
+    };
+
+    let return_type = if current_token_is!(self, Token::Arrow) {
+      self.advance();
+
+      match self.parse_type_expression() {
+        Some(type_node) => Some(type_node),
+        _ => {
+          return self.error(ParseError {
+            pos: self.current_token_position(),
+            kind: ParseErrorKind::IncompleteMethodSignature,
+          })
+        }
+      }
+    } else {
+      None
+    };
+
+    // e.g. `extern def string_length String -> UInt64 = "strlen"`
+    let link_name = if current_token_is!(self, Token::Equals) {
+      self.advance();
+
+      match self.current_token {
+        Some(Token::StringLiteral(start, end)) => {
+          self.advance();
+          Some(read_string_with_escapes!(self, start, end))
+        }
+        _ => {
+          return self.error(ParseError {
+            pos: self.current_token_position(),
+            kind: ParseErrorKind::MissingLinkName,
+          })
+        }
+      }
+    } else {
+      None
+    };
+
+    let end = match self.prev_token {
+      Some(token) => token.get_position().1,
+      _ => start,
+    };
+
+    Some(ExternDefNode {
       pos: (start, end),
+      visibility: self.current_visibility,
+      kind,
+      return_type,
+      link_name,
     })
   }
 
@@ -782,8 +890,11 @@ impl<'a> Parser<'a> {
       // If we have a .. now, we know the first ident was a receiver type
       receiver = Some(type_ident);
       self.advance();
-    } else if current_token_is!(self, Token::Operator) {
-      // If we have an operator now, this is a binary operator definition
+    } else if current_token_is!(self, Token::Operator)
+      || current_token_is!(self, Token::ForwardSlash)
+    {
+      // If we have an operator now, this is a binary operator definition.
+      // A lone `/` is tokenized on its own, since it also starts regexes.
       receiver = Some(type_ident);
 
       let (start, end) = self.current_token_position();
@@ -967,15 +1078,19 @@ impl<'a> Parser<'a> {
   }
 
   fn parse_hex_number(&mut self) -> Option<LiteralNode> {
-    let (start, end, value) = expect_token_and_do!(self, Token::HexDigits, {
+    let (start, end, kind) = expect_token_and_do!(self, Token::HexDigits, {
       let (start, end) = self.current_token_position();
-      (start, end, self.parse_numeric_literal(start, end, 16))
+      (
+        start,
+        end,
+        self.parse_int_literal(start, end, 16, LiteralKind::IntHex),
+      )
     });
 
     self.advance();
 
     Some(LiteralNode {
-      kind: LiteralKind::IntHex(value),
+      kind,
       pos: (start, end),
     })
   }
@@ -1027,20 +1142,8 @@ impl<'a> Parser<'a> {
       pos.0
     });
 
-    let (name, end) = match self.current_token {
-      Some(Token::Identifier(start, end)) => {
-        let name_str = read_string!(self, start, end);
-
-        self.advance();
-
-        (
-          IdentifierNode {
-            pos: (start, end),
-            name: name_str,
-          },
-          end,
-        )
-      }
+    let name = match self.parse_type_identifier() {
+      Some(type_id) => type_id,
       _ => {
         return self.error(ParseError {
           pos: self.current_token_position(),
@@ -1049,11 +1152,18 @@ impl<'a> Parser<'a> {
       }
     };
 
+    let generic_type_constraints = self.parse_generic_type_constraints().unwrap_or_default();
+
+    let end = match generic_type_constraints.last() {
+      Some((_, constraint)) => constraint.pos.1,
+      None => name.pos.1,
+    };
+
     Some(IntrinsicTypeDefNode {
       pos: (start, end),
       visibility: self.current_visibility,
       name,
-      generic_type_constraints: Vec::new(),
+      generic_type_constraints,
     })
   }
 
@@ -1231,33 +1341,117 @@ impl<'a> Parser<'a> {
     })
   }
 
-  fn parse_numeric_literal(&self, start: usize, end: usize, radix: i32) -> i32 {
-    let mut result: i32 = 0;
-    let mut i: i32 = 1;
+  /// Reads an integer literal token. Without a type suffix, it's an Int.
+  fn parse_int_literal(
+    &mut self,
+    start: usize,
+    end: usize,
+    radix: u32,
+    int_kind: fn(i64) -> LiteralKind,
+  ) -> LiteralKind {
+    let (digits_end, suffix) = self.split_numeric_suffix(start, end, radix);
 
-    for byte in self.source[start..end].iter().rev() {
-      let byte_value = match byte {
-        b'0'..=b'9' => byte - 48,
-        _ => unreachable!(),
-      };
+    match suffix {
+      None => int_kind(self.parse_numeric_literal(start, end, radix)),
+
+      // e.g. `1f32`, which the tokenizer only allows for decimal literals
+      Some(sized_type) if sized_type.is_float() => {
+        let str_value = read_string!(self, start, digits_end);
+        LiteralKind::SizedFloat(str_value.parse::<f64>().unwrap(), sized_type)
+      }
+
+      Some(sized_type) => match self.read_digits(start, digits_end, radix) {
+        Some(value) if value <= sized_type.max_int() => LiteralKind::SizedInt(value, sized_type),
+        _ => {
+          self.error::<()>(ParseError {
+            pos: (start, end),
+            kind: ParseErrorKind::IntegerLiteralTooLargeForType(sized_type),
+          });
 
-      result += (byte_value as i32) * i;
-      i *= radix;
+          LiteralKind::SizedInt(0, sized_type)
+        }
+      },
     }
+  }
+
+  /// Reads the integer in a numeric literal token, ignoring any type
+  /// suffix. Literals that don't fit in an Int are reported, and read as 0.
+  fn parse_numeric_literal(&mut self, start: usize, end: usize, radix: u32) -> i64 {
+    let (digits_end, _) = self.split_numeric_suffix(start, end, radix);
 
-    result
+    match self.read_digits(start, digits_end, radix) {
+      Some(value) if value <= i64::MAX as u64 => value as i64,
+      _ => {
+        self.error::<()>(ParseError {
+          pos: (start, end),
+          kind: ParseErrorKind::IntegerLiteralTooLarge,
+        });
+
+        0
+      }
+    }
+  }
+
+  /// Reads the digits of a numeric literal, or returns None if they don't
+  /// fit in 64 bits.
+  fn read_digits(&self, start: usize, end: usize, radix: u32) -> Option<u64> {
+    // Binary, octal and hex literals start with a prefix, like "0x"
+    let digits_start = if radix == 10 { start } else { start + 2 };
+    let mut result: u64 = 0;
+
+    for byte in &self.source[digits_start..end] {
+      let digit = (*byte as char)
+        .to_digit(radix)
+        .expect("tokenizer should only allow digits in the literal's radix");
+
+      result = result
+        .checked_mul(u64::from(radix))?
+        .checked_add(u64::from(digit))?;
+    }
+
+    Some(result)
+  }
+
+  /// Splits a numeric literal token at the start of its type suffix (like
+  /// the `u8` in `255u8`), if it has one.
+  fn split_numeric_suffix(
+    &self,
+    start: usize,
+    end: usize,
+    radix: u32,
+  ) -> (usize, Option<SizedType>) {
+    let digits_start = if radix == 10 { start } else { start + 2 };
+
+    let digits_end = (digits_start..end)
+      .find(|&i| !(self.source[i] as char).is_digit(radix))
+      .unwrap_or(end);
+
+    if digits_end == end {
+      return (end, None);
+    }
+
+    let suffix = read_string!(self, digits_end, end);
+
+    (
+      digits_end,
+      Some(SizedType::from_suffix(&suffix).expect("tokenizer should only allow valid suffixes")),
+    )
   }
 
   fn parse_octal_number(&mut self) -> Option<LiteralNode> {
-    let (start, end, value) = expect_token_and_do!(self, Token::OctalDigits, {
+    let (start, end, kind) = expect_token_and_do!(self, Token::OctalDigits, {
       let (start, end) = self.current_token_position();
-      (start, end, self.parse_numeric_literal(start, end, 8))
+      (
+        start,
+        end,
+        self.parse_int_literal(start, end, 8, LiteralKind::IntOctal),
+      )
     });
 
     self.advance();
 
     Some(LiteralNode {
-      kind: LiteralKind::IntOctal(value),
+      kind,
       pos: (start, end),
     })
   }
@@ -2041,6 +2235,7 @@ impl<'a> Parser<'a> {
       Some(Token::LeftBracket(..)) => self.parse_list_or_dict(),
       Some(Token::StringLiteral(..)) => self.parse_string(),
       Some(Token::KeywordMatch(..)) => self.parse_match(),
+      Some(Token::KeywordBreak(..)) => self.parse_break(),
       Some(Token::Underscore(..)) => self.parse_underscore(),
       Some(Token::Identifier(..))
       | Some(Token::IdentifierSpecialParam(..))
@@ -2123,6 +2318,14 @@ impl<'a> Parser<'a> {
             kind: TopLevelStatementKind::IntrinsicDef(intrinsic_def_node),
           })
       }
+      Some(Token::KeywordExtern(..)) => {
+        self
+          .parse_extern_definition()
+          .map(|extern_def_node| TopLevelStatementNode {
+            pos: extern_def_node.pos,
+            kind: TopLevelStatementKind::ExternDef(extern_def_node),
+          })
+      }
       Some(Token::KeywordAlias(..)) => {
         self
           .parse_alias()
@@ -2199,7 +2402,7 @@ impl<'a> Parser<'a> {
 
     let mut fields = Vec::new();
     let mut methods = Vec::new();
-    let mut end = start;
+    let mut end = name.pos.1;
 
     loop {
       match self.current_token {
@@ -2212,7 +2415,9 @@ impl<'a> Parser<'a> {
 
           let field_name = self.parse_identifier(false).unwrap();
 
-          expect_token_and_do!(self, Token::DoubleColon, {});
+          expect_token_and_do!(self, Token::DoubleColon, {
+            self.advance();
+          });
 
           match self.parse_type_expression() {
             Some(field_type) => {
@@ -2278,15 +2483,10 @@ impl<'a> Parser<'a> {
       }
     }
 
+    // A trait with no members (e.g. the prelude's `Any`) is satisfied by
+    // every type
     self.skip_line_breaks();
 
-    if fields.is_empty() && methods.is_empty() {
-      return self.error(ParseError {
-        pos: self.current_token_position(),
-        kind: ParseErrorKind::MissingTraitConstraints,
-      });
-    }
-
     Some(TypeDefNode {
       pos: (start, end),
       visibility: self.current_visibility,
@@ -1,5 +1,6 @@
 use crate::parse_error::{ParseError, ParseErrorKind::*};
 use crate::tokens::{Token, Token::*};
+use pluma_ast::SizedType;
 use std::collections::HashMap;
 
 pub type CommentMap = HashMap<usize, Token>;
@@ -50,6 +51,29 @@ impl<'a> Tokenizer<'a> {
 
     peeked_token
   }
+
+  /// Skips the type suffix at the end of a numeric literal (like the `u8`
+  /// in `255u8`), if there is one. Float suffixes are only allowed on
+  /// decimal literals, since `f` is a hex digit.
+  fn skip_numeric_suffix(&mut self, allow_float: bool) -> bool {
+    let mut end = self.index;
+
+    while end < self.length && is_identifier_char(self.source[end]) {
+      end += 1;
+    }
+
+    let suffix = std::str::from_utf8(&self.source[self.index..end])
+      .ok()
+      .and_then(SizedType::from_suffix);
+
+    match suffix {
+      Some(sized_type) if allow_float || !sized_type.is_float() => {
+        self.index = end;
+        true
+      }
+      _ => false,
+    }
+  }
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
@@ -317,6 +341,7 @@ impl<'a> Iterator for Tokenizer<'a> {
             b"enum" if self.brace_depth == 0 => KeywordEnum,
             b"alias" if self.brace_depth == 0 => KeywordAlias,
             b"as" if self.brace_depth == 0 => KeywordAs,
+            b"extern" if self.brace_depth == 0 => KeywordExtern,
             b"intrinsic_def" if self.brace_depth == 0 => KeywordIntrinsicDef,
             b"intrinsic_type" if self.brace_depth == 0 => KeywordIntrinsicType,
             b"private" if self.brace_depth == 0 => KeywordPrivate,
@@ -345,6 +370,10 @@ impl<'a> Iterator for Tokenizer<'a> {
 
                 while self.index < self.length && is_identifier_char(self.source[self.index]) {
                   if self.source[self.index] != b'0' && self.source[self.index] != b'1' {
+                    if self.skip_numeric_suffix(false) {
+                      break;
+                    }
+
                     let error_start = self.index;
 
                     while self.index < self.length && !self.source[self.index].is_ascii_whitespace()
@@ -371,6 +400,10 @@ impl<'a> Iterator for Tokenizer<'a> {
 
                 while self.index < self.length && is_identifier_char(self.source[self.index]) {
                   if !self.source[self.index].is_ascii_hexdigit() {
+                    if self.skip_numeric_suffix(false) {
+                      break;
+                    }
+
                     let error_start = self.index;
 
                     while self.index < self.length && !self.source[self.index].is_ascii_whitespace()
@@ -397,6 +430,10 @@ impl<'a> Iterator for Tokenizer<'a> {
 
                 while self.index < self.length && is_identifier_char(self.source[self.index]) {
                   if self.source[self.index] < 48 || self.source[self.index] > 55 {
+                    if self.skip_numeric_suffix(false) {
+                      break;
+                    }
+
                     let error_start = self.index;
 
                     while self.index < self.length && !self.source[self.index].is_ascii_whitespace()
@@ -424,6 +461,10 @@ impl<'a> Iterator for Tokenizer<'a> {
 
           while self.index < self.length && is_identifier_char(self.source[self.index]) {
             if !self.source[self.index].is_ascii_digit() {
+              if self.skip_numeric_suffix(true) {
+                break;
+              }
+
               let error_start = self.index;
 
               while self.index < self.length && !self.source[self.index].is_ascii_whitespace() {
@@ -28,6 +28,7 @@ pub enum Token {
   KeywordConst(usize, usize),
   KeywordDef(usize, usize),
   KeywordEnum(usize, usize),
+  KeywordExtern(usize, usize),
   KeywordInternal(usize, usize),
   KeywordIntrinsicDef(usize, usize),
   KeywordIntrinsicType(usize, usize),
@@ -84,6 +85,7 @@ impl Token {
       &Token::KeywordConst(start, end) => (start, end),
       &Token::KeywordDef(start, end) => (start, end),
       &Token::KeywordEnum(start, end) => (start, end),
+      &Token::KeywordExtern(start, end) => (start, end),
       &Token::KeywordInternal(start, end) => (start, end),
       &Token::KeywordIntrinsicDef(start, end) => (start, end),
       &Token::KeywordIntrinsicType(start, end) => (start, end),
@@ -142,6 +144,7 @@ impl fmt::Display for Token {
       &Token::KeywordConst(..) => "keyword 'const'",
       &Token::KeywordDef(..) => "keyword 'def'",
       &Token::KeywordEnum(..) => "keyword 'enum'",
+      &Token::KeywordExtern(..) => "keyword 'extern'",
       &Token::KeywordInternal(..) => "keyword 'internal'",
       &Token::KeywordIntrinsicDef(..) => "keyword 'intrinsic_def'",
       &Token::KeywordIntrinsicType(..) => "keyword 'intrinsic_type'",
@@ -38,3 +38,45 @@ macro_rules! test_parse_success {
     )*
   }
 }
+
+#[macro_export]
+macro_rules! test_parse_fail {
+  ($($name:ident: $source:literal,)*) => {
+    $(
+        #[test]
+        fn $name() {
+            use insta::assert_snapshot;
+            use pluma_parser::*;
+
+            let replaced = $source.replace("\n    |", "\n");
+            let source = replaced.trim();
+            let source_copy = source.clone();
+            let bytes = Vec::from(source);
+            let tokenizer = Tokenizer::from_source(&bytes, false);
+            let mut parser = Parser::new(&bytes, tokenizer, false);
+            let (_ast, _imports, _, errors) = parser.parse_module();
+
+            if errors.is_empty() {
+              panic!("expected parse errors, but found none");
+            }
+
+            let file_name = format!("{}", stringify!($name));
+
+            let messages = errors
+              .iter()
+              .map(|err| format!("{:?}: {}", err.pos, err))
+              .collect::<Vec<_>>();
+
+            let formatted = format!("
+=== Source ===
+{}
+
+=== Errors ===
+{:#?}
+", source_copy, messages);
+
+            assert_snapshot!(file_name, formatted, &source_copy);
+        }
+    )*
+  }
+}
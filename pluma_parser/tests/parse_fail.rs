@@ -0,0 +1,24 @@
+#[macro_use]
+mod macros;
+
+test_parse_fail! {
+  decimal_literal_too_large: r#"
+    |9223372036854775808
+  "#,
+
+  hex_literal_too_large: r#"
+    |0x10000000000000000
+  "#,
+
+  suffixed_literal_too_large: r#"
+    |256u8
+  "#,
+
+  float_literal_with_int_suffix: r#"
+    |1.5i32
+  "#,
+
+  extern_def_missing_link_name: r#"
+    |extern def string_length String -> UInt64 = strlen
+  "#,
+}
@@ -9,6 +9,24 @@ test_parse_success! {
     |47
   "#,
 
+  number_largest_int: r#"
+    |9223372036854775807
+  "#,
+
+  number_prefixed: r#"
+    |0b101
+    |0o17
+    |0xff
+  "#,
+
+  number_suffixed: r#"
+    |255u8
+    |0xffffu16
+    |18446744073709551615u64
+    |1.5f32
+    |1f32
+  "#,
+
   string: r#"
     |"wow"
   "#,
@@ -299,6 +317,16 @@ test_parse_success! {
     |intrinsic_def Int + Int -> Int
   "#,
 
+  intrinsic_def_binary_op_divide: r#"
+    |intrinsic_def Int / Int -> Int
+  "#,
+
+  extern_def: r#"
+    |extern def puts String -> Int32
+    |extern def string_length String -> UInt64 = "strlen"
+    |extern def abort ()
+  "#,
+
   block_with_special_positional_params: r#"
     |let b = { $0 + $1 }
   "#,
@@ -307,6 +335,10 @@ test_parse_success! {
     |let b = { $self .. return 47 }
   "#,
 
+  break_in_block: r#"
+    |loop { break }
+  "#,
+
   def_binary_op: r#"
     |def A + A -> A {}
   "#,
@@ -511,6 +543,10 @@ test_parse_success! {
     |  { (A, Int) -> Cool }
   "#,
 
+  type_trait_no_members: r#"
+    |trait Any
+  "#,
+
   type_trait_same_line_one_field: r#"
     |trait HasName . name :: String
   "#,
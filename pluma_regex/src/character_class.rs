@@ -0,0 +1,32 @@
+/// A sequence of byte ranges, which matches one byte from each range in
+/// turn.
+pub type ByteSequence = &'static [(u8, u8)];
+
+/// The byte sequences that a character class can match, or None if there
+/// is no class with this name. Every class but `any` is ASCII-only; `any`
+/// matches any single UTF-8 encoded character.
+pub fn character_class(name: &str) -> Option<&'static [ByteSequence]> {
+  let sequences: &'static [ByteSequence] = match name {
+    "any" => &[
+      &[(0x00, 0x7f)],
+      &[(0xc2, 0xdf), (0x80, 0xbf)],
+      &[(0xe0, 0xef), (0x80, 0xbf), (0x80, 0xbf)],
+      &[(0xf0, 0xf4), (0x80, 0xbf), (0x80, 0xbf), (0x80, 0xbf)],
+    ],
+    "digit" => &[&[(b'0', b'9')]],
+    "letter" => &[&[(b'a', b'z')], &[(b'A', b'Z')]],
+    "lower" => &[&[(b'a', b'z')]],
+    "upper" => &[&[(b'A', b'Z')]],
+    "word" => &[
+      &[(b'a', b'z')],
+      &[(b'A', b'Z')],
+      &[(b'0', b'9')],
+      &[(b'_', b'_')],
+    ],
+    // Tab, line feed, vertical tab, form feed, carriage return and space
+    "space" => &[&[(0x09, 0x0d)], &[(b' ', b' ')]],
+    _ => return None,
+  };
+
+  Some(sequences)
+}
@@ -0,0 +1,7 @@
+mod character_class;
+mod program;
+mod regex_compiler;
+
+pub use character_class::*;
+pub use program::*;
+pub use regex_compiler::*;
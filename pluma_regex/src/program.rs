@@ -0,0 +1,64 @@
+use std::fmt;
+
+/// One step of a compiled regular expression. Programs are run by a
+/// backtracking matcher, which starts at the first instruction with a
+/// position in the subject string.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Instruction {
+  /// Matches one byte in this (inclusive) range, and moves past it.
+  ByteRange(u8, u8),
+
+  /// Continues at the first instruction, and if that fails, backtracks to
+  /// continue at the second one instead.
+  Split(usize, usize),
+
+  Jump(usize),
+
+  /// Records the current position in a capture slot.
+  Save(usize),
+
+  Match,
+}
+
+/// A compiled regular expression. Slots 0 and 1 hold the start and end of
+/// the whole match, and each named capture gets the next two slots, in the
+/// order that the captures appear in the expression.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Program {
+  pub instructions: Vec<Instruction>,
+  pub capture_names: Vec<String>,
+}
+
+impl Program {
+  pub fn slot_count(&self) -> usize {
+    2 * (self.capture_names.len() + 1)
+  }
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Instruction::ByteRange(low, high) if low == high => write!(f, "byte {:#04x}", low),
+      Instruction::ByteRange(low, high) => write!(f, "byte {:#04x}-{:#04x}", low, high),
+      Instruction::Split(first, second) => write!(f, "split {}, {}", first, second),
+      Instruction::Jump(target) => write!(f, "jump {}", target),
+      Instruction::Save(slot) => write!(f, "save {}", slot),
+      Instruction::Match => write!(f, "match"),
+    }
+  }
+}
+
+impl fmt::Display for Program {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, name) in self.capture_names.iter().enumerate() {
+      writeln!(f, "capture {} (slots {}, {})", name, 2 * i + 2, 2 * i + 3)?;
+    }
+
+    for (i, instruction) in self.instructions.iter().enumerate() {
+      writeln!(f, "{:>4}: {}", i, instruction)?;
+    }
+
+    Ok(())
+  }
+}
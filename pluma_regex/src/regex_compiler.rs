@@ -0,0 +1,199 @@
+use crate::character_class::*;
+use crate::program::*;
+use pluma_ast::*;
+
+/// Compiles a regular expression into a program for a backtracking matcher
+/// (in the style of a Thompson NFA, where alternatives are tried in order).
+/// The expression should already have been analyzed, so every character
+/// class exists and no capture name is used twice.
+pub fn compile_regex(node: &RegExprNode) -> Program {
+  let mut capture_names = Vec::new();
+  collect_capture_names(node, &mut capture_names);
+
+  RegexCompiler {
+    instructions: Vec::new(),
+    capture_names,
+  }
+  .compile(node)
+}
+
+struct RegexCompiler {
+  instructions: Vec<Instruction>,
+  capture_names: Vec<String>,
+}
+
+impl RegexCompiler {
+  fn compile(mut self, node: &RegExprNode) -> Program {
+    self.push(Instruction::Save(0));
+    self.compile_node(node);
+    self.push(Instruction::Save(1));
+    self.push(Instruction::Match);
+
+    Program {
+      instructions: self.instructions,
+      capture_names: self.capture_names,
+    }
+  }
+
+  fn compile_node(&mut self, node: &RegExprNode) {
+    match &node.kind {
+      RegExprKind::Literal(value) => {
+        for byte in value.bytes() {
+          self.push(Instruction::ByteRange(byte, byte));
+        }
+      }
+
+      RegExprKind::CharacterClass(name) => {
+        let sequences =
+          character_class(name).expect("analyzer should reject undefined character classes");
+
+        self.compile_alternatives(sequences, |compiler, sequence| {
+          for (low, high) in sequence.iter() {
+            compiler.push(Instruction::ByteRange(*low, *high));
+          }
+        });
+      }
+
+      RegExprKind::Grouping(inner) => self.compile_node(inner),
+
+      RegExprKind::Sequence(parts) => {
+        for part in parts {
+          self.compile_node(part);
+        }
+      }
+
+      RegExprKind::Alternation(alternatives) => {
+        self.compile_alternatives(alternatives, |compiler, alternative| {
+          compiler.compile_node(alternative)
+        });
+      }
+
+      RegExprKind::NamedCapture(name, inner) => {
+        let index = self
+          .capture_names
+          .iter()
+          .position(|capture_name| capture_name == name)
+          .unwrap();
+
+        self.push(Instruction::Save(2 * index + 2));
+        self.compile_node(inner);
+        self.push(Instruction::Save(2 * index + 3));
+      }
+
+      RegExprKind::ZeroOrMore(inner) => self.compile_star(inner),
+
+      RegExprKind::OneOrMore(inner) => {
+        let start = self.instructions.len();
+        self.compile_node(inner);
+
+        let next = self.instructions.len() + 1;
+        self.push(Instruction::Split(start, next));
+      }
+
+      RegExprKind::OneOrZero(inner) => self.compile_optional(inner, 1),
+
+      RegExprKind::ExactCount(inner, count) => self.compile_repeated(inner, *count),
+
+      RegExprKind::AtLeastCount(inner, min) => {
+        self.compile_repeated(inner, *min);
+        self.compile_star(inner);
+      }
+
+      RegExprKind::AtMostCount(inner, max) => self.compile_optional(inner, *max),
+
+      RegExprKind::RangeCount(inner, min, max) => {
+        self.compile_repeated(inner, *min);
+        self.compile_optional(inner, max.saturating_sub(*min));
+      }
+    }
+  }
+
+  /// Tries each alternative in order. Every alternative but the last is
+  /// preceded by a split that skips to the next one, and followed by a
+  /// jump past the rest.
+  fn compile_alternatives<T>(&mut self, alternatives: &[T], compile: impl Fn(&mut Self, &T)) {
+    let mut jumps = Vec::with_capacity(alternatives.len());
+
+    for (i, alternative) in alternatives.iter().enumerate() {
+      if i == alternatives.len() - 1 {
+        compile(self, alternative);
+        break;
+      }
+
+      let split = self.push(Instruction::Split(0, 0));
+      compile(self, alternative);
+      jumps.push(self.push(Instruction::Jump(0)));
+
+      self.instructions[split] = Instruction::Split(split + 1, self.instructions.len());
+    }
+
+    let end = self.instructions.len();
+
+    for jump in jumps {
+      self.instructions[jump] = Instruction::Jump(end);
+    }
+  }
+
+  fn compile_star(&mut self, inner: &RegExprNode) {
+    let split = self.push(Instruction::Split(0, 0));
+    self.compile_node(inner);
+    self.push(Instruction::Jump(split));
+
+    self.instructions[split] = Instruction::Split(split + 1, self.instructions.len());
+  }
+
+  fn compile_repeated(&mut self, inner: &RegExprNode, count: usize) {
+    for _ in 0..count {
+      self.compile_node(inner);
+    }
+  }
+
+  /// Matches `inner` up to `count` times. Each repetition is only tried
+  /// after the one before it matched, so failing skips straight to the end.
+  fn compile_optional(&mut self, inner: &RegExprNode, count: usize) {
+    let mut splits = Vec::with_capacity(count);
+
+    for _ in 0..count {
+      splits.push(self.push(Instruction::Split(0, 0)));
+      self.compile_node(inner);
+    }
+
+    let end = self.instructions.len();
+
+    for split in splits {
+      self.instructions[split] = Instruction::Split(split + 1, end);
+    }
+  }
+
+  fn push(&mut self, instruction: Instruction) -> usize {
+    self.instructions.push(instruction);
+    self.instructions.len() - 1
+  }
+}
+
+/// The names of an expression's captures, in the order they appear.
+fn collect_capture_names(node: &RegExprNode, names: &mut Vec<String>) {
+  match &node.kind {
+    RegExprKind::Literal(..) | RegExprKind::CharacterClass(..) => {}
+
+    RegExprKind::NamedCapture(name, inner) => {
+      names.push(name.clone());
+      collect_capture_names(inner, names);
+    }
+
+    RegExprKind::Sequence(parts) | RegExprKind::Alternation(parts) => {
+      for part in parts {
+        collect_capture_names(part, names);
+      }
+    }
+
+    RegExprKind::Grouping(inner)
+    | RegExprKind::OneOrMore(inner)
+    | RegExprKind::ZeroOrMore(inner)
+    | RegExprKind::OneOrZero(inner)
+    | RegExprKind::AtLeastCount(inner, _)
+    | RegExprKind::AtMostCount(inner, _)
+    | RegExprKind::ExactCount(inner, _)
+    | RegExprKind::RangeCount(inner, _, _) => collect_capture_names(inner, names),
+  }
+}
@@ -0,0 +1,61 @@
+use pluma_ast::*;
+
+/// Finds the last regular expression at the top level of a module.
+pub fn last_reg_expr(module: &ModuleNode) -> &RegExprNode {
+  module
+    .body
+    .iter()
+    .rev()
+    .find_map(|statement| {
+      let expr = match &statement.kind {
+        TopLevelStatementKind::Let(let_node) => &let_node.value,
+        TopLevelStatementKind::Expr(expr) => expr,
+        _ => return None,
+      };
+
+      match &expr.kind {
+        ExprKind::RegExpr(reg_expr) => Some(reg_expr),
+        _ => None,
+      }
+    })
+    .expect("expected a regular expression")
+}
+
+#[macro_export]
+macro_rules! test_regex {
+  ($($name:ident: $source:literal,)*) => {
+    $(
+        #[test]
+        fn $name() {
+            use insta::assert_snapshot;
+            use pluma_parser::*;
+            use pluma_regex::*;
+
+            let replaced = $source.replace("\n    |", "\n");
+            let source = replaced.trim();
+            let source_copy = source.clone();
+            let bytes = Vec::from(source);
+            let tokenizer = Tokenizer::from_source(&bytes, false);
+            let mut parser = Parser::new(&bytes, tokenizer, false);
+            let (ast, _imports, _, errors) = parser.parse_module();
+
+            if !errors.is_empty() {
+              panic!("parse errors: {:#?}", errors);
+            }
+
+            let program = compile_regex(macros::last_reg_expr(&ast));
+
+            let file_name = format!("{}", stringify!($name));
+
+            let formatted = format!("
+=== Source ===
+{}
+
+=== Program ===
+{}", source_copy, program);
+
+            assert_snapshot!(file_name, formatted, &source_copy);
+        }
+    )*
+  }
+}
@@ -0,0 +1,60 @@
+#[macro_use]
+mod macros;
+
+test_regex! {
+  literal: r#"
+    |let r = /"hi"/
+  "#,
+
+  character_class: r#"
+    |let r = /letter digit/
+  "#,
+
+  any_character: r#"
+    |let r = /any/
+  "#,
+
+  alternation: r#"
+    |let r = /"a" | "b" | "c"/
+  "#,
+
+  zero_or_more: r#"
+    |let r = /"a"* /
+  "#,
+
+  one_or_more: r#"
+    |let r = /"a"+ /
+  "#,
+
+  one_or_zero: r#"
+    |let r = /"a"? "b"/
+  "#,
+
+  exact_count: r#"
+    |let r = /"a"{3}/
+  "#,
+
+  at_least_count: r#"
+    |let r = /"a"{2,}/
+  "#,
+
+  at_most_count: r#"
+    |let r = /"a"{,2}/
+  "#,
+
+  range_count: r#"
+    |let r = /"a"{1,3}/
+  "#,
+
+  group: r#"
+    |let r = /("a" | "b")+ "c"/
+  "#,
+
+  named_captures: r#"
+    |let r = /<year: digit{4}> "-" <month: digit{2}> /
+  "#,
+
+  repeated_named_capture: r#"
+    |let r = /(<last: letter> ",")* /
+  "#,
+}
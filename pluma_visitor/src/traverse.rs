@@ -140,7 +140,20 @@ impl Traverse for ExprNode {
         }
       }
 
-      ExprKind::EmptyTuple => {}
+      ExprKind::List(elements) => {
+        for element in elements {
+          element.traverse(visitor);
+        }
+      }
+
+      ExprKind::Dict(entries) => {
+        for (key, value) in entries {
+          key.traverse(visitor);
+          value.traverse(visitor);
+        }
+      }
+
+      ExprKind::Break | ExprKind::EmptyTuple | ExprKind::RegExpr(..) | ExprKind::Underscore => {}
 
       ExprKind::MultiPartIdentifier(parts) => {
         for part in parts {
@@ -170,14 +183,20 @@ impl Traverse for ExprNode {
         expr.traverse(visitor);
         asserted_type.traverse(visitor);
       }
-
-      _other_kind => todo!("traverse other kind"),
     }
 
     visitor.leave_expr(self);
   }
 }
 
+impl Traverse for ExternDefNode {
+  fn traverse<V: Visitor>(&self, visitor: &mut V) {
+    visitor.enter_extern_def(self);
+
+    visitor.leave_extern_def(self);
+  }
+}
+
 impl Traverse for IdentifierNode {
   fn traverse<V: Visitor>(&self, visitor: &mut V) {
     visitor.enter_identifier(self);
@@ -296,6 +315,7 @@ impl Traverse for TopLevelStatementNode {
       TopLevelStatementKind::Def(node) => node.traverse(visitor),
       TopLevelStatementKind::Expr(node) => node.traverse(visitor),
       TopLevelStatementKind::IntrinsicDef(node) => node.traverse(visitor),
+      TopLevelStatementKind::ExternDef(node) => node.traverse(visitor),
       TopLevelStatementKind::IntrinsicTypeDef(node) => node.traverse(visitor),
       TopLevelStatementKind::VisibilityMarker(..) => {}
     };
@@ -140,7 +140,20 @@ impl TraverseMut for ExprNode {
         }
       }
 
-      ExprKind::EmptyTuple => {}
+      ExprKind::List(elements) => {
+        for element in elements {
+          element.traverse_mut(visitor);
+        }
+      }
+
+      ExprKind::Dict(entries) => {
+        for (key, value) in entries {
+          key.traverse_mut(visitor);
+          value.traverse_mut(visitor);
+        }
+      }
+
+      ExprKind::Break | ExprKind::EmptyTuple | ExprKind::RegExpr(..) | ExprKind::Underscore => {}
 
       ExprKind::MultiPartIdentifier(parts) => {
         for part in parts {
@@ -170,14 +183,20 @@ impl TraverseMut for ExprNode {
         expr.traverse_mut(visitor);
         asserted_type.traverse_mut(visitor);
       }
-
-      _other_kind => todo!("traverseMut other kind"),
     }
 
     visitor.leave_expr(self);
   }
 }
 
+impl TraverseMut for ExternDefNode {
+  fn traverse_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+    visitor.enter_extern_def(self);
+
+    visitor.leave_extern_def(self);
+  }
+}
+
 impl TraverseMut for IdentifierNode {
   fn traverse_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
     visitor.enter_identifier(self);
@@ -296,6 +315,7 @@ impl TraverseMut for TopLevelStatementNode {
       TopLevelStatementKind::Def(node) => node.traverse_mut(visitor),
       TopLevelStatementKind::Expr(node) => node.traverse_mut(visitor),
       TopLevelStatementKind::IntrinsicDef(node) => node.traverse_mut(visitor),
+      TopLevelStatementKind::ExternDef(node) => node.traverse_mut(visitor),
       TopLevelStatementKind::IntrinsicTypeDef(node) => node.traverse_mut(visitor),
       TopLevelStatementKind::VisibilityMarker(..) => {}
     };
@@ -21,6 +21,10 @@ pub trait Visitor {
 
   fn leave_expr(&mut self, _node: &ExprNode) {}
 
+  fn enter_extern_def(&mut self, _node: &ExternDefNode) {}
+
+  fn leave_extern_def(&mut self, _node: &ExternDefNode) {}
+
   fn enter_identifier(&mut self, _node: &IdentifierNode) {}
 
   fn leave_identifier(&mut self, _node: &IdentifierNode) {}
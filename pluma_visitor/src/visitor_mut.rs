@@ -21,6 +21,10 @@ pub trait VisitorMut {
 
   fn leave_expr(&mut self, _node: &mut ExprNode) {}
 
+  fn enter_extern_def(&mut self, _node: &mut ExternDefNode) {}
+
+  fn leave_extern_def(&mut self, _node: &mut ExternDefNode) {}
+
   fn enter_identifier(&mut self, _node: &mut IdentifierNode) {}
 
   fn leave_identifier(&mut self, _node: &mut IdentifierNode) {}